@@ -7,23 +7,50 @@ use blake2b_ref::{Blake2b, Blake2bBuilder};
 use bytes::Bytes;
 
 const INFO_LOCK_CODE_HASH_FILENAME: &str = "info_lock_code_hash.rs";
+const REGISTRY_CODE_HASH_FILENAME: &str = "registry_code_hash.rs";
+const REGISTRY_TYPE_ARGS_FILENAME: &str = "registry_type_args.rs";
+const REGISTRY_TYPE_ARGS_ENV: &str = "REGISTRY_TYPE_ARGS";
 const CKB_HASH_PERSONALIZATION: &[u8] = b"ckb-default-hash";
 
 fn main() {
+    write_code_hash("info-lock-script", INFO_LOCK_CODE_HASH_FILENAME);
+    write_code_hash("registry-type-script", REGISTRY_CODE_HASH_FILENAME);
+
+    // The type id of the canonical registry, all zero in debug builds for the tests. A release
+    // build without it would accept any registry, so it must be given.
+    println!("cargo:rerun-if-env-changed={}", REGISTRY_TYPE_ARGS_ENV);
+    let registry_type_args = match env::var(REGISTRY_TYPE_ARGS_ENV) {
+        Ok(args) => args,
+        Err(_) if cfg!(debug_assertions) => hex::encode([0u8; 32]),
+        Err(_) => panic!(
+            "{} must be set to the registry type id for a release build",
+            REGISTRY_TYPE_ARGS_ENV
+        ),
+    };
+    let registry_type_args = registry_type_args.trim_start_matches("0x");
+    assert_eq!(hex::decode(registry_type_args).unwrap().len(), 32);
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(REGISTRY_TYPE_ARGS_FILENAME);
+    let mut file = BufWriter::new(File::create(&path).unwrap());
+
+    write!(&mut file, "{:?}", registry_type_args).unwrap();
+}
+
+fn write_code_hash(binary_name: &str, filename: &str) {
     let path = if cfg!(debug_assertions) {
-        Path::new("../../build/debug/info-lock-script")
+        Path::new("../../build/debug").join(binary_name)
     } else {
-        Path::new("../../build/release/info-lock-script")
+        Path::new("../../build/release").join(binary_name)
     };
 
-    let info_lock_bin = Bytes::from(fs::read(path).unwrap());
+    let bin = Bytes::from(fs::read(path).unwrap());
 
     let mut hash = [0u8; 32];
     let mut hasher = new_blake2b();
-    hasher.update(&info_lock_bin);
+    hasher.update(&bin);
     hasher.finalize(&mut hash);
 
-    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(INFO_LOCK_CODE_HASH_FILENAME);
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(filename);
     let mut file = BufWriter::new(File::create(&path).unwrap());
 
     write!(&mut file, "{:?}", hex::encode(hash)).unwrap();
@@ -1,6 +1,16 @@
+mod admin_verify;
+mod close_verify;
+mod direct_verify;
+mod governance_verify;
 mod liquidity_verify;
+mod matcher_verify;
+mod migrate_verify;
+mod operation_verify;
+mod order_verify;
+mod registry_verify;
 mod swap_verify;
 mod type_id;
+mod uniform_verify;
 
 use alloc::vec::Vec;
 use core::result::Result;
@@ -14,13 +24,15 @@ use share::ckb_std::{
         prelude::*,
     },
     default_alloc,
+    error::SysError,
     high_level::{
         load_cell, load_cell_data, load_cell_lock_hash, load_cell_type_hash, load_script,
         load_witness_args, QueryIter,
     },
 };
 use share::{
-    blake2b, cell::InfoCellData, decode_u128, decode_u64, get_cell_type_hash, hash::blake2b_256,
+    blake2b, cell::InfoCellData, decode_batch_operations, decode_batch_settlement,
+    decode_batch_witness, decode_u128, get_cell_type_hash, hash::blake2b_256, Settlement,
 };
 
 use crate::error::Error;
@@ -34,6 +46,7 @@ const INFO_CAPACITY: u64 = 25_000_000_000;
 const INFO_VERSION: u8 = 1;
 const INFO_INDEX: usize = 0;
 const POOL_INDEX: usize = 1;
+const MATCHER_INDEX: usize = 2;
 const SUDT_CELL_DATA_LEN: usize = 16;
 
 pub static INFO_LOCK_CODE_HASH: &str =
@@ -51,6 +64,21 @@ pub fn main() -> Result<(), Error> {
         return Ok(());
     }
 
+    if input_info_cell_count == 1 && output_info_cell_count == 0 {
+        let info_in_data = InfoCellData::from_raw(&load_cell_data(INFO_INDEX, Source::Input)?)?;
+        let pool_in_cell = load_cell(POOL_INDEX, Source::Input)?;
+        let pool_in_data = decode_u128(&load_cell_data(POOL_INDEX, Source::Input)?)?;
+
+        basic_verify(&info_in_data, &pool_in_cell, pool_in_data)?;
+
+        if is_pool_migration()? {
+            migrate_verify::migrate_tx_verification(&info_in_data, &pool_in_cell)?;
+        } else {
+            close_verify::close_tx_verification(&info_in_data)?;
+        }
+        return Ok(());
+    }
+
     if input_info_cell_count != 1 || output_info_cell_count != 1 {
         return Err(Error::MoreThanOneLiquidityPool);
     }
@@ -70,36 +98,133 @@ pub fn main() -> Result<(), Error> {
 
     basic_verify(&info_in_data, &pool_in_cell, pool_in_data)?;
 
+    if info_in_data.admin_lock_hash != info_out_data.admin_lock_hash
+        || info_in_data.pending_admin_lock_hash != info_out_data.pending_admin_lock_hash
+        || info_in_data.activation_timestamp != info_out_data.activation_timestamp
+        || info_in_data.migration_code_hash != info_out_data.migration_code_hash
+    {
+        governance_verify::governance_tx_verification(
+            &info_in_data,
+            &info_out_data,
+            &pool_in_cell,
+            pool_in_data,
+        )?;
+        return Ok(());
+    }
+
+    verify_info_params(&info_in_data, &info_out_data)?;
+
+    if info_in_data.paused != info_out_data.paused {
+        admin_verify::pause_tx_verification(
+            &info_in_data,
+            &info_out_data,
+            &pool_in_cell,
+            pool_in_data,
+        )?;
+        return Ok(());
+    }
+
     let raw_witness: Vec<u8> = load_witness_args(0, Source::Input)?
         .input_type()
         .to_opt()
         .unwrap()
         .unpack();
-    let swap_cell_count = decode_u64(&raw_witness)? as usize;
+    let (swap_cell_count, matcher_cell_count) = decode_batch_witness(&raw_witness)?;
+    let settlement = decode_batch_settlement(&raw_witness)?;
+    let operations = decode_batch_operations(&raw_witness);
+    let request_base = MATCHER_INDEX + matcher_cell_count;
     let output_cell_count = QueryIter::new(load_cell, Source::Output).count();
+    let mut filled_output_count = output_cell_count;
 
-    if output_cell_count == 4 && swap_cell_count == 0 {
-        liquidity_verify::verify_initial_mint(
-            liquidity_sudt_type_hash,
-            &mut ckb_reserve,
-            &mut sudt_reserve,
-            &mut total_liquidity,
-        )?;
-    } else {
-        swap_verify::swap_tx_verification(
-            &info_out_cell,
+    if info_in_data.paused && (swap_cell_count != 0 || matcher_cell_count == 0) {
+        return Err(Error::PoolPaused);
+    }
+
+    if matcher_cell_count == 0 {
+        if settlement != Settlement::Sequential || !operations.is_empty() {
+            return Err(Error::InvalidDirectSwap);
+        }
+
+        direct_verify::direct_swap_verification(
             swap_cell_count,
+            &pool_in_cell,
+            pool_in_data,
+            &pool_out_cell,
+            pool_out_data,
             &mut ckb_reserve,
             &mut sudt_reserve,
         )?;
+    } else if output_cell_count == request_base + 1 && swap_cell_count == 0 {
+        if info_in_data.paused {
+            return Err(Error::PoolPaused);
+        }
 
-        liquidity_verify::liquidity_tx_verification(
-            swap_cell_count,
+        if !operations.is_empty() {
+            return Err(Error::InvalidOperations);
+        }
+
+        liquidity_verify::verify_initial_mint(
+            request_base,
+            liquidity_sudt_type_hash,
             &mut ckb_reserve,
             &mut sudt_reserve,
             &mut total_liquidity,
-            liquidity_sudt_type_hash,
         )?;
+    } else {
+        order_verify::verify_request_order(request_base, swap_cell_count)?;
+
+        if !operations.is_empty() {
+            // Settled swaps are filled all at once, they can't be interleaved
+            if settlement != Settlement::Sequential {
+                return Err(Error::InvalidOperations);
+            }
+
+            filled_output_count = operation_verify::interleaved_tx_verification(
+                operations,
+                &info_out_cell,
+                request_base,
+                swap_cell_count,
+                liquidity_sudt_type_hash,
+                info_in_data.paused,
+                &mut ckb_reserve,
+                &mut sudt_reserve,
+                &mut total_liquidity,
+            )?;
+        } else {
+            if settlement != Settlement::Sequential {
+                uniform_verify::uniform_swap_verification(
+                    &info_out_cell,
+                    request_base,
+                    swap_cell_count,
+                    settlement,
+                    &mut ckb_reserve,
+                    &mut sudt_reserve,
+                )?;
+                // Settled swaps pay no referral
+                filled_output_count = swap_verify::first_referral_index(
+                    &info_out_cell,
+                    request_base,
+                    swap_cell_count,
+                )?;
+            } else {
+                filled_output_count = swap_verify::swap_tx_verification(
+                    &info_out_cell,
+                    request_base,
+                    swap_cell_count,
+                    &mut ckb_reserve,
+                    &mut sudt_reserve,
+                )?;
+            }
+
+            liquidity_verify::liquidity_tx_verification(
+                request_base + swap_cell_count,
+                &mut ckb_reserve,
+                &mut sudt_reserve,
+                &mut total_liquidity,
+                liquidity_sudt_type_hash,
+                info_in_data.paused,
+            )?;
+        }
     }
 
     if info_out_cell.capacity().unpack() != INFO_CAPACITY
@@ -128,6 +253,10 @@ pub fn main() -> Result<(), Error> {
         return Err(Error::InvalidPoolOutputData);
     }
 
+    if matcher_cell_count != 0 {
+        matcher_verify::verify_batch_surplus(request_base, swap_cell_count, filled_output_count)?;
+    }
+
     Ok(())
 }
 
@@ -168,9 +297,52 @@ fn basic_verify(
     Ok(())
 }
 
+/// A migration carries the pool cell over to a new info cell, while a close destroys both.
+fn is_pool_migration() -> Result<bool, Error> {
+    let pool_type_hash = load_cell_type_hash(POOL_INDEX, Source::Input)?;
+    let info_out_type_hash = match load_cell_type_hash(INFO_INDEX, Source::Output) {
+        Ok(hash) => hash,
+        Err(SysError::IndexOutOfBound) => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+    let pool_out_type_hash = match load_cell_type_hash(POOL_INDEX, Source::Output) {
+        Ok(hash) => hash,
+        Err(SysError::IndexOutOfBound) => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(info_out_type_hash.is_some() && pool_out_type_hash == pool_type_hash)
+}
+
+fn verify_info_params(
+    info_in_data: &InfoCellData,
+    info_out_data: &InfoCellData,
+) -> Result<(), Error> {
+    if info_in_data.liquidity_sudt_type_hash != info_out_data.liquidity_sudt_type_hash {
+        return Err(Error::LiquiditySUDTTypeHashMismatch);
+    }
+
+    if info_in_data.creator_lock_hash != info_out_data.creator_lock_hash
+        || info_in_data.admin_lock_hash != info_out_data.admin_lock_hash
+    {
+        return Err(Error::InfoParamsChanged);
+    }
+
+    Ok(())
+}
+
 fn verify_info_creation(info_out_cell: &CellOutput) -> Result<(), Error> {
     type_id::verify_type_id()?;
 
+    // A pending change would take effect at once, its activation has never been delayed
+    let info_out_data = InfoCellData::from_raw(&load_cell_data(INFO_INDEX, Source::Output)?)?;
+    if info_out_data.pending_admin_lock_hash.is_some()
+        || info_out_data.migration_code_hash.is_some()
+        || info_out_data.activation_timestamp != 0
+    {
+        return Err(Error::PendingGovernanceChange);
+    }
+
     let info_out_lock_args: Vec<u8> = info_out_cell.lock().args().unpack();
     let pool_type_hash = get_cell_type_hash!(POOL_INDEX, Source::Output);
     let (output_info_lock_count, is_data_deploy) = get_info_cell_count()?;
@@ -201,6 +373,8 @@ fn verify_info_creation(info_out_cell: &CellOutput) -> Result<(), Error> {
         return Err(Error::CellDataLenTooShort);
     }
 
+    registry_verify::verify_registration(pool_type_hash)?;
+
     Ok(())
 }
 
@@ -0,0 +1,79 @@
+use core::result::Result;
+
+use share::cell::InfoCellData;
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::CellOutput, prelude::*},
+    high_level::{load_cell, load_cell_data, load_cell_lock_hash, QueryIter},
+};
+use share::{decode_u128, get_cell_type_hash};
+
+use crate::entry::{INFO_CAPACITY, INFO_INDEX, POOL_INDEX};
+use crate::error::Error;
+
+/// Switch the pool between paused and active, nothing but the status can change.
+pub fn pause_tx_verification(
+    info_in_data: &InfoCellData,
+    info_out_data: &InfoCellData,
+    pool_in_cell: &CellOutput,
+    pool_in_data: u128,
+) -> Result<(), Error> {
+    let admin_lock_hash = info_in_data
+        .admin_lock_hash
+        .ok_or(Error::MissingAdminLockHash)?;
+
+    if !QueryIter::new(load_cell_lock_hash, Source::Input).any(|hash| hash == admin_lock_hash) {
+        return Err(Error::AdminNotAuthorized);
+    }
+
+    verify_pool_state_unchanged(info_in_data, info_out_data, pool_in_cell, pool_in_data)
+}
+
+/// Admin transactions must carry the info and pool cells over untouched apart from the info
+/// cell parameters they are allowed to change.
+pub fn verify_pool_state_unchanged(
+    info_in_data: &InfoCellData,
+    info_out_data: &InfoCellData,
+    pool_in_cell: &CellOutput,
+    pool_in_data: u128,
+) -> Result<(), Error> {
+    let info_lock_hash = load_cell_lock_hash(INFO_INDEX, Source::Input)?;
+    if load_cell_lock_hash(INFO_INDEX, Source::Output)? != info_lock_hash
+        || load_cell_lock_hash(POOL_INDEX, Source::Output)? != info_lock_hash
+    {
+        return Err(Error::InfoLockHashChanged);
+    }
+
+    if load_cell(INFO_INDEX, Source::Output)?.capacity().unpack() != INFO_CAPACITY
+        || info_out_data.ckb_reserve != info_in_data.ckb_reserve
+    {
+        return Err(Error::InvalidCKBReserve);
+    }
+
+    if info_out_data.sudt_reserve != info_in_data.sudt_reserve {
+        return Err(Error::InvalidSUDTReserve);
+    }
+
+    if info_out_data.total_liquidity != info_in_data.total_liquidity {
+        return Err(Error::InvalidTotalLiquidity);
+    }
+
+    if info_out_data.liquidity_sudt_type_hash != info_in_data.liquidity_sudt_type_hash {
+        return Err(Error::LiquiditySUDTTypeHashMismatch);
+    }
+
+    if load_cell(POOL_INDEX, Source::Output)?.capacity().unpack()
+        != pool_in_cell.capacity().unpack()
+    {
+        return Err(Error::InvalidOutputPoolCapacity);
+    }
+
+    let pool_type_hash = get_cell_type_hash!(POOL_INDEX, Source::Input);
+    if get_cell_type_hash!(POOL_INDEX, Source::Output) != pool_type_hash
+        || decode_u128(&load_cell_data(POOL_INDEX, Source::Output)?)? != pool_in_data
+    {
+        return Err(Error::InvalidPoolOutputData);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use core::result::Result;
+
+use share::cell::InfoCellData;
+use share::ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_cell_lock_hash, QueryIter},
+};
+use share::get_cell_type_hash;
+
+use crate::entry::registry_verify;
+use crate::entry::{INFO_INDEX, POOL_INDEX};
+use crate::error::Error;
+
+/// The creator closes an empty pool and takes back its capacity, the pool is removed from the
+/// registry in the same transaction so its sUDT can get a canonical pool again.
+pub fn close_tx_verification(info_in_data: &InfoCellData) -> Result<(), Error> {
+    if info_in_data.total_liquidity != 0
+        || info_in_data.ckb_reserve != 0
+        || info_in_data.sudt_reserve != 0
+    {
+        return Err(Error::PoolNotEmpty);
+    }
+
+    let creator_lock_hash = info_in_data
+        .creator_lock_hash
+        .ok_or(Error::MissingCreatorLockHash)?;
+    let info_lock_hash = load_cell_lock_hash(INFO_INDEX, Source::Input)?;
+
+    if QueryIter::new(load_cell_lock_hash, Source::Output).any(|hash| hash == info_lock_hash) {
+        return Err(Error::PoolCellNotDestroyed);
+    }
+
+    if !QueryIter::new(load_cell_lock_hash, Source::Input).any(|hash| hash == creator_lock_hash) {
+        return Err(Error::CloseNotAuthorized);
+    }
+
+    registry_verify::verify_unregistration(get_cell_type_hash!(POOL_INDEX, Source::Input))
+}
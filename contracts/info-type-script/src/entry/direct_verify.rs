@@ -0,0 +1,59 @@
+use core::result::Result;
+
+use num_bigint::BigUint;
+use share::ckb_std::ckb_types::{packed::CellOutput, prelude::*};
+
+use crate::entry::{FEE_RATE, ONE, THOUSAND};
+use crate::error::Error;
+
+/// A direct swap has no request cells, the submitter pays into the pool with its own cells and
+/// takes the output in the same transaction. Only the pool transition is verified, everything
+/// else is authorized by the submitter's locks.
+pub fn direct_swap_verification(
+    swap_cell_count: usize,
+    pool_in_cell: &CellOutput,
+    pool_in_data: u128,
+    pool_out_cell: &CellOutput,
+    pool_out_data: u128,
+    ckb_reserve: &mut u128,
+    sudt_reserve: &mut u128,
+) -> Result<(), Error> {
+    if swap_cell_count != 0 {
+        return Err(Error::InvalidDirectSwap);
+    }
+
+    let pool_in_capacity = pool_in_cell.capacity().unpack() as u128;
+    let pool_out_capacity = pool_out_cell.capacity().unpack() as u128;
+
+    if pool_out_capacity > pool_in_capacity && pool_out_data < pool_in_data {
+        let ckb_got = pool_out_capacity - pool_in_capacity;
+        let sudt_paid = pool_in_data - pool_out_data;
+
+        let numerator = BigUint::from(ckb_got) * FEE_RATE * (*sudt_reserve);
+        let denominator = (*ckb_reserve) * THOUSAND + BigUint::from(ckb_got) * FEE_RATE;
+
+        if BigUint::from(sudt_paid) != numerator / denominator + ONE {
+            return Err(Error::BuySUDTFailed);
+        }
+
+        *ckb_reserve += ckb_got;
+        *sudt_reserve -= sudt_paid;
+    } else if pool_out_capacity < pool_in_capacity && pool_out_data > pool_in_data {
+        let sudt_got = pool_out_data - pool_in_data;
+        let ckb_paid = pool_in_capacity - pool_out_capacity;
+
+        let numerator = BigUint::from(sudt_got) * FEE_RATE * (*ckb_reserve);
+        let denominator = (*sudt_reserve) * THOUSAND + FEE_RATE * BigUint::from(sudt_got);
+
+        if BigUint::from(ckb_paid) != numerator / denominator + ONE {
+            return Err(Error::SellSUDTFailed);
+        }
+
+        *ckb_reserve -= ckb_paid;
+        *sudt_reserve += sudt_got;
+    } else {
+        return Err(Error::InvalidDirectSwap);
+    }
+
+    Ok(())
+}
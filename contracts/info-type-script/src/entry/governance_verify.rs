@@ -0,0 +1,113 @@
+use core::result::Result;
+
+use share::cell::InfoCellData;
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::CellOutput, prelude::*},
+    high_level::{load_cell_lock_hash, load_header, load_input_since, QueryIter},
+};
+
+use crate::entry::{admin_verify, INFO_INDEX};
+use crate::error::Error;
+
+/// Absolute since flag with the median timestamp metric.
+const SINCE_ABSOLUTE_TIMESTAMP_FLAG: u64 = 0x4000_0000_0000_0000;
+const SINCE_FLAG_MASK: u64 = 0xFF00_0000_0000_0000;
+const SINCE_VALUE_MASK: u64 = 0x00FF_FFFF_FFFF_FFFF;
+/// Two days in seconds.
+const GOVERNANCE_DELAY: u64 = 172_800;
+
+/// Propose, cancel or execute an admin change, or propose a migration. A proposal is written into
+/// the info cell along with its activation timestamp, the governance delay after the proposal, and
+/// can only be executed once the median time has passed it, so LPs always have time to burn their
+/// liquidity before it takes effect. Batches in between carry the proposal over untouched.
+pub fn governance_tx_verification(
+    info_in_data: &InfoCellData,
+    info_out_data: &InfoCellData,
+    pool_in_cell: &CellOutput,
+    pool_in_data: u128,
+) -> Result<(), Error> {
+    let admin_lock_hash = info_in_data
+        .admin_lock_hash
+        .ok_or(Error::MissingAdminLockHash)?;
+
+    if !QueryIter::new(load_cell_lock_hash, Source::Input).any(|hash| hash == admin_lock_hash) {
+        return Err(Error::AdminNotAuthorized);
+    }
+
+    if info_in_data.creator_lock_hash != info_out_data.creator_lock_hash
+        || info_in_data.paused != info_out_data.paused
+    {
+        return Err(Error::InfoParamsChanged);
+    }
+
+    let is_pending = |data: &InfoCellData| {
+        data.pending_admin_lock_hash.is_some() || data.migration_code_hash.is_some()
+    };
+
+    if info_in_data.admin_lock_hash == info_out_data.admin_lock_hash {
+        if is_pending(info_out_data) {
+            verify_proposal(info_out_data.activation_timestamp)?;
+        } else if !is_pending(info_in_data) || info_out_data.activation_timestamp != 0 {
+            // A cancel must have something to cancel
+            return Err(Error::InvalidGovernanceChange);
+        }
+    } else {
+        // Installing the pending admin clears the whole proposal
+        if info_in_data.pending_admin_lock_hash.is_none()
+            || info_out_data.admin_lock_hash != info_in_data.pending_admin_lock_hash
+            || is_pending(info_out_data)
+            || info_out_data.activation_timestamp != 0
+        {
+            return Err(Error::InvalidGovernanceChange);
+        }
+
+        verify_activation(info_in_data.activation_timestamp)?;
+    }
+
+    admin_verify::verify_pool_state_unchanged(
+        info_in_data,
+        info_out_data,
+        pool_in_cell,
+        pool_in_data,
+    )
+}
+
+/// The proposal time is the absolute since of the info input, which the chain bounds from above.
+/// It can't be earlier than the info input itself, given in the header deps, so a proposal can
+/// only be backdated to the last transition of the pool.
+fn verify_proposal(activation_timestamp: u64) -> Result<(), Error> {
+    let proposed_at = input_since_timestamp()?.ok_or(Error::InvalidProposalTimestamp)?;
+    let info_in_timestamp: u64 = load_header(INFO_INDEX, Source::Input)?
+        .raw()
+        .timestamp()
+        .unpack();
+
+    if proposed_at < info_in_timestamp / 1000
+        || Some(activation_timestamp) != proposed_at.checked_add(GOVERNANCE_DELAY)
+    {
+        return Err(Error::InvalidProposalTimestamp);
+    }
+
+    Ok(())
+}
+
+/// The info input must carry an absolute timestamp since of at least the activation timestamp.
+pub fn verify_activation(activation_timestamp: u64) -> Result<(), Error> {
+    let executed_at = input_since_timestamp()?.ok_or(Error::GovernanceDelayNotPassed)?;
+    if executed_at < activation_timestamp {
+        return Err(Error::GovernanceDelayNotPassed);
+    }
+
+    Ok(())
+}
+
+/// The absolute median timestamp of the info input since, in seconds.
+fn input_since_timestamp() -> Result<Option<u64>, Error> {
+    let since = load_input_since(INFO_INDEX, Source::Input)?;
+    if since & SINCE_FLAG_MASK != SINCE_ABSOLUTE_TIMESTAMP_FLAG {
+        return Ok(None);
+    }
+
+    Ok(Some(since & SINCE_VALUE_MASK))
+}
@@ -18,63 +18,95 @@ use crate::entry::{INFO_INDEX, INFO_VERSION, ONE, POOL_INDEX, SUDT_CAPACITY, SUD
 use crate::error::Error;
 
 pub fn liquidity_tx_verification(
-    swap_cell_count: usize,
+    base_index: usize,
     ckb_reserve: &mut u128,
     sudt_reserve: &mut u128,
     total_liquidity: &mut u128,
     liquidity_sudt_type_hash: [u8; 32],
+    paused: bool,
 ) -> Result<(), Error> {
     let info_in_type_hash = get_cell_type_hash!(INFO_INDEX, Source::Input);
     let pool_type_hash = get_cell_type_hash!(POOL_INDEX, Source::Input);
     let input_cell_count = QueryIter::new(load_cell, Source::Input).count();
-    let base_index = swap_cell_count + 3;
 
     for idx in base_index..input_cell_count {
-        let liquidity_order_cell = load_cell(idx, Source::Input)?;
-        let raw_lock_args: Vec<u8> = liquidity_order_cell.lock().args().unpack();
-        let liquidity_order_lock_args = LiquidityRequestLockArgs::from_raw(&raw_lock_args)?;
+        verify_liquidity(
+            idx,
+            base_index,
+            info_in_type_hash,
+            pool_type_hash,
+            liquidity_sudt_type_hash,
+            paused,
+            ckb_reserve,
+            sudt_reserve,
+            total_liquidity,
+        )?;
+    }
 
-        if liquidity_order_lock_args.version != INFO_VERSION {
-            return Err(Error::VersionDiff);
-        }
+    Ok(())
+}
 
-        let liquidity_order_data = decode_u128(&load_cell_data(idx, Source::Input)?)?;
-        let liquidity_type_hash = get_cell_type_hash!(idx, Source::Input);
-        if liquidity_order_lock_args.info_type_hash != info_in_type_hash {
-            return Err(Error::LiquidityArgsInfoTypeHashMismatch);
-        }
+/// Fills the liquidity request at `idx` against the reserves, `base_index` is the index of the
+/// first liquidity request.
+pub fn verify_liquidity(
+    idx: usize,
+    base_index: usize,
+    info_in_type_hash: [u8; 32],
+    pool_type_hash: [u8; 32],
+    liquidity_sudt_type_hash: [u8; 32],
+    paused: bool,
+    ckb_reserve: &mut u128,
+    sudt_reserve: &mut u128,
+    total_liquidity: &mut u128,
+) -> Result<(), Error> {
+    let liquidity_order_cell = load_cell(idx, Source::Input)?;
+    let raw_lock_args: Vec<u8> = liquidity_order_cell.lock().args().unpack();
+    let liquidity_order_lock_args = LiquidityRequestLockArgs::from_raw(&raw_lock_args)?;
 
-        if liquidity_type_hash == liquidity_sudt_type_hash {
-            burn_liquidity(
-                idx,
-                base_index,
-                &liquidity_order_cell,
-                liquidity_order_data,
-                ckb_reserve,
-                sudt_reserve,
-                total_liquidity,
-            )?;
-        } else if liquidity_type_hash == pool_type_hash {
-            mint_liquidity(
-                idx,
-                base_index,
-                pool_type_hash,
-                liquidity_sudt_type_hash,
-                &liquidity_order_cell,
-                liquidity_order_data,
-                ckb_reserve,
-                sudt_reserve,
-                total_liquidity,
-            )?;
-        } else {
-            return Err(Error::UnknownLiquidity);
-        }
+    if liquidity_order_lock_args.version != INFO_VERSION {
+        return Err(Error::VersionDiff);
     }
 
-    Ok(())
+    let liquidity_order_data = decode_u128(&load_cell_data(idx, Source::Input)?)?;
+    let liquidity_type_hash = get_cell_type_hash!(idx, Source::Input);
+    if liquidity_order_lock_args.info_type_hash != info_in_type_hash {
+        return Err(Error::LiquidityArgsInfoTypeHashMismatch);
+    }
+
+    if liquidity_type_hash == liquidity_sudt_type_hash {
+        burn_liquidity(
+            idx,
+            base_index,
+            &liquidity_order_cell,
+            liquidity_order_data,
+            ckb_reserve,
+            sudt_reserve,
+            total_liquidity,
+        )
+    } else if liquidity_type_hash == pool_type_hash {
+        // Withdrawals are always allowed, only new liquidity is rejected
+        if paused {
+            return Err(Error::PoolPaused);
+        }
+
+        mint_liquidity(
+            idx,
+            base_index,
+            pool_type_hash,
+            liquidity_sudt_type_hash,
+            &liquidity_order_cell,
+            liquidity_order_data,
+            ckb_reserve,
+            sudt_reserve,
+            total_liquidity,
+        )
+    } else {
+        Err(Error::UnknownLiquidity)
+    }
 }
 
 pub fn verify_initial_mint(
+    order_cell_index: usize,
     liquidity_sudt_type_hash: [u8; 32],
     ckb_reserve: &mut u128,
     sudt_reserve: &mut u128,
@@ -84,7 +116,6 @@ pub fn verify_initial_mint(
         return Err(Error::InvalidInfoInData);
     }
 
-    let order_cell_index: usize = 3;
     let order_cell = load_cell(order_cell_index, Source::Input)?;
     let raw_lock_args: Vec<u8> = order_cell.lock().args().unpack();
     let order_lock_args = LiquidityRequestLockArgs::from_raw(&raw_lock_args)?;
@@ -101,8 +132,12 @@ pub fn verify_initial_mint(
         return Err(Error::LiquidityArgsUserLockHashMismatch);
     }
 
-    let sudt_injected = order_data;
-    let ckb_injected = order_cell.capacity().unpack() - SUDT_CAPACITY;
+    let sudt_injected = order_data
+        .checked_sub(order_lock_args.tips_sudt)
+        .ok_or(Error::InvalidTips)?;
+    let ckb_injected = (order_cell.capacity().unpack() - SUDT_CAPACITY)
+        .checked_sub(order_lock_args.tips)
+        .ok_or(Error::InvalidTips)?;
     let user_liquidity = liquidity_sudt_data;
     let mint_liquidity = (BigUint::from(sudt_injected) * ckb_injected).sqrt();
 
@@ -167,10 +202,14 @@ fn mint_liquidity(
             return Err(Error::InvalidChangeCell);
         }
 
-        sudt_injected = liquidity_order_data;
-        ckb_injected = liquidity_order_cell.capacity().unpack() as u128
+        sudt_injected = liquidity_order_data
+            .checked_sub(liquidity_order_lock_args.tips_sudt)
+            .ok_or(Error::InvalidTips)?;
+        ckb_injected = (liquidity_order_cell.capacity().unpack() as u128
             - SUDT_CAPACITY as u128
-            - change_cell.capacity().unpack() as u128;
+            - change_cell.capacity().unpack() as u128)
+            .checked_sub(liquidity_order_lock_args.tips as u128)
+            .ok_or(Error::InvalidTips)?;
 
         if ckb_injected == 0 || sudt_injected == 0 {
             return Err(Error::InjectAmountEqZero);
@@ -201,8 +240,12 @@ fn mint_liquidity(
             return Err(Error::LiquidityArgsUserLockHashMismatch);
         }
 
-        sudt_injected = liquidity_order_data - decode_u128(&change_data[0..16])?;
-        ckb_injected = (liquidity_order_cell.capacity().unpack() - SUDT_CAPACITY * 2) as u128;
+        sudt_injected = (liquidity_order_data - decode_u128(&change_data[0..16])?)
+            .checked_sub(liquidity_order_lock_args.tips_sudt)
+            .ok_or(Error::InvalidTips)?;
+        ckb_injected = (liquidity_order_cell.capacity().unpack() - SUDT_CAPACITY * 2)
+            .checked_sub(liquidity_order_lock_args.tips)
+            .ok_or(Error::InvalidTips)? as u128;
 
         if ckb_injected == 0 || sudt_injected == 0 {
             return Err(Error::InjectAmountEqZero);
@@ -294,19 +337,30 @@ fn burn_liquidity(
         return Err(Error::InvalidMinSUDTGot);
     }
 
-    if user_ckb_got != (BigUint::from(*ckb_reserve) * burned_liquidity / *total_liquidity) + ONE {
+    // Burning all liquidity empties the pool, the rounding up would exceed the reserves
+    let (ckb_expected, sudt_expected) = if burned_liquidity == *total_liquidity {
+        (BigUint::from(*ckb_reserve), BigUint::from(*sudt_reserve))
+    } else {
+        (
+            (BigUint::from(*ckb_reserve) * burned_liquidity / *total_liquidity) + ONE,
+            (BigUint::from(*sudt_reserve) * burned_liquidity / *total_liquidity) + ONE,
+        )
+    };
+
+    // The matcher keeps the tips out of the withdrawn amounts
+    if user_ckb_got + liquidity_lock_args.tips != ckb_expected {
         return Err(Error::CKBGotAmountDiff);
     }
 
-    if user_sudt_got != (BigUint::from(*sudt_reserve) * burned_liquidity / *total_liquidity) + ONE {
+    if user_sudt_got + liquidity_lock_args.tips_sudt != sudt_expected {
         return Err(Error::SUDTGotAmountDiff);
     }
 
-    let user_ckb_got: u128 = user_ckb_got.try_into().unwrap();
-    let user_sudt_got: u128 = user_sudt_got.try_into().unwrap();
+    let ckb_expected: u128 = ckb_expected.try_into().unwrap();
+    let sudt_expected: u128 = sudt_expected.try_into().unwrap();
 
-    *ckb_reserve -= user_ckb_got;
-    *sudt_reserve -= user_sudt_got;
+    *ckb_reserve -= ckb_expected;
+    *sudt_reserve -= sudt_expected;
     *total_liquidity -= burned_liquidity;
 
     Ok(())
@@ -0,0 +1,88 @@
+use alloc::vec::Vec;
+use core::result::Result;
+
+use share::cell::{LiquidityRequestLockArgs, SwapRequestLockArgs};
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{load_cell, load_cell_data, load_cell_type_hash, QueryIter},
+};
+use share::{decode_u128, get_cell_type_hash};
+
+use crate::entry::{MATCHER_INDEX, POOL_INDEX, SUDT_CELL_DATA_LEN};
+use crate::error::Error;
+
+/// The matcher cells sit between the pool cell and the requests, in both inputs and
+/// outputs. Whatever the other cells lose in the batch ends up in the matcher cells or
+/// the transaction fee, so it must be covered by the tips declared in the requests. The
+/// outputs after the `filled_output_count` outputs filled by the batch are the matcher's.
+pub fn verify_batch_surplus(
+    request_base: usize,
+    swap_cell_count: usize,
+    filled_output_count: usize,
+) -> Result<(), Error> {
+    let input_cell_count = QueryIter::new(load_cell, Source::Input).count();
+    let mut tips = 0u128;
+    let mut tips_sudt = 0u128;
+
+    for idx in request_base..input_cell_count {
+        let raw_lock_args: Vec<u8> = load_cell(idx, Source::Input)?.lock().args().unpack();
+        if idx < request_base + swap_cell_count {
+            let lock_args = SwapRequestLockArgs::from_raw(&raw_lock_args)?;
+            tips += lock_args.tips as u128;
+            tips_sudt += lock_args.tips_sudt;
+        } else {
+            let lock_args = LiquidityRequestLockArgs::from_raw(&raw_lock_args)?;
+            tips += lock_args.tips as u128;
+            tips_sudt += lock_args.tips_sudt;
+        }
+    }
+
+    let is_matcher = |idx: usize| idx >= MATCHER_INDEX && idx < request_base;
+    let is_matcher_out = |idx: usize| is_matcher(idx) || idx >= filled_output_count;
+    let ckb_in = sum_capacity(Source::Input, is_matcher);
+    let ckb_out = sum_capacity(Source::Output, is_matcher_out);
+    if ckb_in > ckb_out + tips {
+        return Err(Error::InvalidCKBSurplus);
+    }
+
+    let pool_type_hash = get_cell_type_hash!(POOL_INDEX, Source::Input);
+    let sudt_in = sum_sudt(Source::Input, pool_type_hash, is_matcher)?;
+    let sudt_out = sum_sudt(Source::Output, pool_type_hash, is_matcher_out)?;
+    if sudt_in > sudt_out + tips_sudt {
+        return Err(Error::InvalidSUDTSurplus);
+    }
+
+    Ok(())
+}
+
+fn sum_capacity<F: Fn(usize) -> bool>(source: Source, is_matcher: F) -> u128 {
+    QueryIter::new(load_cell, source)
+        .enumerate()
+        .filter(|(idx, _)| !is_matcher(*idx))
+        .map(|(_, cell)| cell.capacity().unpack() as u128)
+        .sum()
+}
+
+fn sum_sudt<F: Fn(usize) -> bool>(
+    source: Source,
+    sudt_type_hash: [u8; 32],
+    is_matcher: F,
+) -> Result<u128, Error> {
+    let mut amount = 0u128;
+
+    for (idx, type_hash) in QueryIter::new(load_cell_type_hash, source).enumerate() {
+        if is_matcher(idx) || type_hash != Some(sudt_type_hash) {
+            continue;
+        }
+
+        let data = load_cell_data(idx, source)?;
+        if data.len() < SUDT_CELL_DATA_LEN {
+            return Err(Error::SUDTCellDataLenTooShort);
+        }
+
+        amount += decode_u128(&data[0..16])?;
+    }
+
+    Ok(amount)
+}
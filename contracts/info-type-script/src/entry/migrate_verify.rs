@@ -0,0 +1,108 @@
+use alloc::vec::Vec;
+use core::result::Result;
+
+use share::cell::InfoCellData;
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::CellOutput, prelude::*},
+    high_level::{load_cell, load_cell_data, load_cell_lock_hash, QueryIter},
+};
+use share::{blake2b, decode_u128, get_cell_type_hash};
+
+use crate::entry::{governance_verify, INFO_CAPACITY, INFO_INDEX, POOL_INDEX};
+use crate::error::Error;
+
+const INFO_CELL_DATA_PREFIX_LEN: usize = 80;
+
+/// Move a pool to the new version of the info type script proposed by the admin, once the
+/// governance delay has passed. Only the old side is verified here, the new info cell is verified
+/// by the new code as an info creation. The reserves and the liquidity sUDT type hash are carried
+/// over, so the liquidity can still be burnt against the new info cell. Minting needs the old info
+/// lock as the owner of the liquidity sUDT, which is destroyed here, so no liquidity is minted
+/// after the migration.
+pub fn migrate_tx_verification(
+    info_in_data: &InfoCellData,
+    pool_in_cell: &CellOutput,
+) -> Result<(), Error> {
+    let admin_lock_hash = info_in_data
+        .admin_lock_hash
+        .ok_or(Error::MissingAdminLockHash)?;
+
+    if !QueryIter::new(load_cell_lock_hash, Source::Input).any(|hash| hash == admin_lock_hash) {
+        return Err(Error::MigrationNotAuthorized);
+    }
+
+    if info_in_data.paused {
+        return Err(Error::PoolPaused);
+    }
+
+    let info_out_code_hash: Option<[u8; 32]> = load_cell(INFO_INDEX, Source::Output)?
+        .type_()
+        .to_opt()
+        .map(|script| script.code_hash().unpack());
+    if info_in_data.migration_code_hash.is_none()
+        || info_out_code_hash != info_in_data.migration_code_hash
+    {
+        return Err(Error::InvalidMigrationTarget);
+    }
+
+    governance_verify::verify_activation(info_in_data.activation_timestamp)?;
+
+    let info_lock_hash = load_cell_lock_hash(INFO_INDEX, Source::Input)?;
+    if QueryIter::new(load_cell_lock_hash, Source::Output).any(|hash| hash == info_lock_hash) {
+        return Err(Error::PoolCellNotDestroyed);
+    }
+
+    // Only the fields known by this version are preserved, the new version may extend the data
+    let info_out_cell = load_cell(INFO_INDEX, Source::Output)?;
+    let info_out_raw_data = load_cell_data(INFO_INDEX, Source::Output)?;
+    if info_out_raw_data.len() < INFO_CELL_DATA_PREFIX_LEN {
+        return Err(Error::CellDataLenTooShort);
+    }
+
+    let info_out_data = InfoCellData::from_raw(&info_out_raw_data[..INFO_CELL_DATA_PREFIX_LEN])?;
+    if info_out_data.ckb_reserve != info_in_data.ckb_reserve
+        || info_out_data.sudt_reserve != info_in_data.sudt_reserve
+        || info_out_data.total_liquidity != info_in_data.total_liquidity
+        || info_out_data.liquidity_sudt_type_hash != info_in_data.liquidity_sudt_type_hash
+    {
+        return Err(Error::InvalidMigratedInfoData);
+    }
+
+    if info_out_cell.capacity().unpack() < INFO_CAPACITY {
+        return Err(Error::InfoCapacityDiff);
+    }
+
+    let pool_type_hash = get_cell_type_hash!(POOL_INDEX, Source::Input);
+    let pool_out_cell = load_cell(POOL_INDEX, Source::Output)?;
+    let pool_out_data = load_cell_data(POOL_INDEX, Source::Output)?;
+
+    if get_cell_type_hash!(POOL_INDEX, Source::Output) != pool_type_hash
+        || pool_out_cell.capacity().unpack() != pool_in_cell.capacity().unpack()
+        || pool_out_data.len() < 16
+        || decode_u128(&pool_out_data[0..16])? != info_in_data.sudt_reserve
+    {
+        return Err(Error::InvalidMigratedPoolCell);
+    }
+
+    let info_out_lock_args: Vec<u8> = info_out_cell.lock().args().unpack();
+    if info_out_lock_args.len() != 64 {
+        return Err(Error::Encoding);
+    }
+
+    if info_out_lock_args[0..32] != blake2b!("ckb", pool_type_hash) {
+        return Err(Error::InfoLockArgsFrontHalfMismatch);
+    }
+
+    if info_out_lock_args[32..64] != get_cell_type_hash!(INFO_INDEX, Source::Output) {
+        return Err(Error::InfoLockArgsSecondHalfMismatch);
+    }
+
+    if load_cell_lock_hash(INFO_INDEX, Source::Output)?
+        != load_cell_lock_hash(POOL_INDEX, Source::Output)?
+    {
+        return Err(Error::InfoCreationCellLockHashMismatch);
+    }
+
+    Ok(())
+}
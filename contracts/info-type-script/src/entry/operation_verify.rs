@@ -0,0 +1,80 @@
+use core::result::Result;
+
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::packed::CellOutput,
+    high_level::{load_cell, QueryIter},
+};
+use share::{get_cell_type_hash, Operation};
+
+use crate::entry::liquidity_verify::verify_liquidity;
+use crate::entry::swap_verify::{first_referral_index, verify_swap};
+use crate::entry::{INFO_INDEX, POOL_INDEX};
+use crate::error::Error;
+
+/// Fills the requests in the order of `operations`, each one taking the next swap or the next
+/// liquidity request, so a liquidity request can be filled before some of the swaps. The
+/// inputs and outputs keep the layout of a batch filling all the swaps first. Returns the number
+/// of outputs filled by the batch, up to the last referral output.
+pub fn interleaved_tx_verification(
+    operations: &[u8],
+    info_out_cell: &CellOutput,
+    request_base: usize,
+    swap_cell_count: usize,
+    liquidity_sudt_type_hash: [u8; 32],
+    paused: bool,
+    ckb_reserve: &mut u128,
+    sudt_reserve: &mut u128,
+    total_liquidity: &mut u128,
+) -> Result<usize, Error> {
+    let input_cell_count = QueryIter::new(load_cell, Source::Input).count();
+    let liquidity_base = request_base + swap_cell_count;
+    if operations.len() != input_cell_count - request_base {
+        return Err(Error::InvalidOperations);
+    }
+
+    let info_in_type_hash = get_cell_type_hash!(INFO_INDEX, Source::Input);
+    let pool_type_hash = get_cell_type_hash!(POOL_INDEX, Source::Input);
+    let mut referral_index = first_referral_index(info_out_cell, request_base, swap_cell_count)?;
+    let mut swap_index = request_base;
+    let mut liquidity_index = liquidity_base;
+
+    for operation in operations {
+        match Operation::from_u8(*operation).map_err(|_| Error::InvalidOperations)? {
+            Operation::Swap => {
+                if swap_index == liquidity_base {
+                    return Err(Error::InvalidOperations);
+                }
+
+                verify_swap(
+                    swap_index,
+                    info_in_type_hash,
+                    &mut referral_index,
+                    ckb_reserve,
+                    sudt_reserve,
+                )?;
+                swap_index += 1;
+            }
+            Operation::Liquidity => {
+                if liquidity_index == input_cell_count {
+                    return Err(Error::InvalidOperations);
+                }
+
+                verify_liquidity(
+                    liquidity_index,
+                    liquidity_base,
+                    info_in_type_hash,
+                    pool_type_hash,
+                    liquidity_sudt_type_hash,
+                    paused,
+                    ckb_reserve,
+                    sudt_reserve,
+                    total_liquidity,
+                )?;
+                liquidity_index += 1;
+            }
+        }
+    }
+
+    Ok(referral_index)
+}
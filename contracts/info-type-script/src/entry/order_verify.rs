@@ -0,0 +1,39 @@
+use core::result::Result;
+
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{load_input_out_point, QueryIter},
+};
+use share::request_order_key;
+
+use crate::entry::INFO_INDEX;
+use crate::error::Error;
+
+/// The matcher has no say in the order of the requests. The swaps and then the liquidity
+/// requests must each be sorted by `request_order_key`, which mixes in the info input so the
+/// order of a batch is only known once the previous one is committed.
+pub fn verify_request_order(request_base: usize, swap_cell_count: usize) -> Result<(), Error> {
+    let info_out_point = load_input_out_point(INFO_INDEX, Source::Input)?;
+    let input_cell_count = QueryIter::new(load_input_out_point, Source::Input).count();
+    let liquidity_base = request_base + swap_cell_count;
+
+    verify_sorted(info_out_point.as_slice(), request_base, liquidity_base)?;
+    verify_sorted(info_out_point.as_slice(), liquidity_base, input_cell_count)
+}
+
+fn verify_sorted(info_out_point: &[u8], start: usize, end: usize) -> Result<(), Error> {
+    let mut prev_key = None;
+
+    for idx in start..end {
+        let out_point = load_input_out_point(idx, Source::Input)?;
+        let key = request_order_key(info_out_point, out_point.as_slice());
+
+        if prev_key.map_or(false, |prev| prev >= key) {
+            return Err(Error::UnorderedRequests);
+        }
+        prev_key = Some(key);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+use core::result::Result;
+
+use share::cell::RegistryCellData;
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::CellOutput, prelude::*},
+    high_level::{load_cell, load_cell_data, load_script, QueryIter},
+};
+
+use crate::entry::HashType;
+use crate::error::Error;
+
+pub static REGISTRY_CODE_HASH: &str = include!(concat!(env!("OUT_DIR"), "/registry_code_hash.rs"));
+pub static REGISTRY_TYPE_ARGS: &str = include!(concat!(env!("OUT_DIR"), "/registry_type_args.rs"));
+
+/// A pool can only be created by inserting its pool type hash into the canonical registry, which
+/// keeps its keys sorted and therefore unique.
+pub fn verify_registration(pool_type_hash: [u8; 32]) -> Result<(), Error> {
+    let registry_inputs = load_registry_nodes(Source::Input)?;
+    if registry_inputs.len() != 1 {
+        return Err(Error::PoolNotRegistered);
+    }
+
+    let prev_node = &registry_inputs[0];
+    if prev_node.key == pool_type_hash {
+        return Err(Error::PoolAlreadyRegistered);
+    }
+
+    if prev_node.info_type_code_hash != load_script()?.code_hash().unpack() {
+        return Err(Error::RegistryInfoTypeMismatch);
+    }
+
+    if load_registry_nodes(Source::Output)?
+        .iter()
+        .any(|node| node.key == pool_type_hash)
+    {
+        return Ok(());
+    }
+
+    Err(Error::PoolNotRegistered)
+}
+
+/// A closed pool leaves the registry, the registry type script checks the list stays linked.
+pub fn verify_unregistration(pool_type_hash: [u8; 32]) -> Result<(), Error> {
+    let is_pool_node = |node: &RegistryCellData| node.key == pool_type_hash;
+
+    if !load_registry_nodes(Source::Input)?.iter().any(is_pool_node)
+        || load_registry_nodes(Source::Output)?
+            .iter()
+            .any(is_pool_node)
+    {
+        return Err(Error::PoolNotUnregistered);
+    }
+
+    Ok(())
+}
+
+fn load_registry_nodes(source: Source) -> Result<Vec<RegistryCellData>, Error> {
+    let registry_code_hash = hex::decode(REGISTRY_CODE_HASH).unwrap();
+    let registry_type_args = hex::decode(REGISTRY_TYPE_ARGS).unwrap();
+    let is_registry_cell = |cell: &CellOutput| {
+        cell.type_().to_opt().map_or(false, |script| {
+            let args: Vec<u8> = script.args().unpack();
+            script.code_hash().as_slice() == registry_code_hash.as_slice()
+                && script.hash_type() == HashType::Data.as_byte()
+                && args == registry_type_args
+        })
+    };
+
+    let mut nodes = Vec::new();
+    for (idx, cell) in QueryIter::new(load_cell, source).enumerate() {
+        if is_registry_cell(&cell) {
+            nodes.push(RegistryCellData::from_raw(&load_cell_data(idx, source)?)?);
+        }
+    }
+
+    Ok(nodes)
+}
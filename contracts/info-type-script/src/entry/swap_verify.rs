@@ -1,56 +1,124 @@
 use alloc::vec::Vec;
 
 use num_bigint::BigUint;
-use share::cell::SwapRequestLockArgs;
+use share::cell::{Referral, SwapRequestLockArgs};
 use share::ckb_std::ckb_types::packed::CellOutput;
 use share::ckb_std::{
     ckb_constants::Source,
     ckb_types::prelude::*,
-    high_level::{load_cell, load_cell_data, load_cell_lock_hash},
+    high_level::{load_cell, load_cell_data, load_cell_lock_hash, load_cell_type_hash, QueryIter},
 };
 use share::{decode_u128, get_cell_type_hash};
 
-use crate::entry::{FEE_RATE, INFO_CAPACITY, ONE, SUDT_CAPACITY, THOUSAND};
+use crate::entry::{FEE_RATE, INFO_CAPACITY, INFO_INDEX, ONE, SUDT_CAPACITY, THOUSAND};
 use crate::error::Error;
 
+const BPS_BASE: u128 = 10_000;
+
+/// Returns the number of outputs filled by the batch, up to the last referral output.
 pub fn swap_tx_verification(
     info_out_cell: &CellOutput,
+    request_base: usize,
     swap_cell_count: usize,
     ckb_reserve: &mut u128,
     sudt_reserve: &mut u128,
-) -> Result<(), Error> {
+) -> Result<usize, Error> {
+    let info_in_type_hash = get_cell_type_hash!(INFO_INDEX, Source::Input);
+    let mut referral_index = first_referral_index(info_out_cell, request_base, swap_cell_count)?;
+
+    for idx in request_base..(request_base + swap_cell_count) {
+        verify_swap(
+            idx,
+            info_in_type_hash,
+            &mut referral_index,
+            ckb_reserve,
+            sudt_reserve,
+        )?;
+    }
+
+    Ok(referral_index)
+}
+
+/// Referral outputs follow the swap outputs and the two outputs of each liquidity request.
+pub fn first_referral_index(
+    info_out_cell: &CellOutput,
+    request_base: usize,
+    swap_cell_count: usize,
+) -> Result<usize, Error> {
     if info_out_cell.capacity().unpack() != INFO_CAPACITY {
         return Err(Error::InfoCapacityDiff);
     }
 
-    for idx in 3..(3 + swap_cell_count) {
-        let req_cell = load_cell(idx, Source::Input)?;
-        let raw_lock_args: Vec<u8> = req_cell.lock().args().unpack();
-        let req_lock_args = SwapRequestLockArgs::from_raw(&raw_lock_args)?;
-        let output_cell = load_cell(idx, Source::Output)?;
+    let input_cell_count = QueryIter::new(load_cell, Source::Input).count();
+    Ok(2 * input_cell_count - swap_cell_count - request_base)
+}
+
+/// Fills the swap request at `idx` against the reserves, `referral_index` is the next referral
+/// output.
+pub fn verify_swap(
+    idx: usize,
+    info_in_type_hash: [u8; 32],
+    referral_index: &mut usize,
+    ckb_reserve: &mut u128,
+    sudt_reserve: &mut u128,
+) -> Result<(), Error> {
+    let req_cell = load_cell(idx, Source::Input)?;
+    let raw_lock_args: Vec<u8> = req_cell.lock().args().unpack();
+    let req_lock_args = SwapRequestLockArgs::from_raw(&raw_lock_args)?;
+    let output_cell = load_cell(idx, Source::Output)?;
+
+    if let Some(info_type_hash) = req_lock_args.info_type_hash {
+        if info_type_hash != info_in_type_hash {
+            return Err(Error::SwapArgsInfoTypeHashMismatch);
+        }
+    }
+
+    if load_cell_lock_hash(idx, Source::Output)? != req_lock_args.user_lock_hash {
+        return Err(Error::InvalidOutputLockHash);
+    }
 
-        if load_cell_lock_hash(idx, Source::Output)? != req_lock_args.user_lock_hash {
-            return Err(Error::InvalidOutputLockHash);
+    let is_ckb_in = req_cell.type_().is_none();
+    let amount_in = if is_ckb_in {
+        ckb_exchange_sudt(
+            idx,
+            &req_cell,
+            &req_lock_args,
+            &output_cell,
+            ckb_reserve,
+            sudt_reserve,
+        )?
+    } else {
+        sudt_exchange_ckb(
+            idx,
+            &req_cell,
+            &req_lock_args,
+            &output_cell,
+            ckb_reserve,
+            sudt_reserve,
+        )?
+    };
+
+    if let Some(referral) = &req_lock_args.referral {
+        // The referral share is taken from the fee, it never reaches the pool
+        let referral_amount =
+            amount_in * (THOUSAND - FEE_RATE) * referral.bps as u128 / (THOUSAND * BPS_BASE);
+        if referral_amount == 0 {
+            return Ok(());
         }
 
-        if req_cell.type_().is_none() {
-            ckb_exchange_sudt(
-                idx,
-                &req_cell,
-                &req_lock_args,
-                &output_cell,
-                ckb_reserve,
-                sudt_reserve,
-            )?;
+        verify_referral_output(
+            *referral_index,
+            referral,
+            referral_amount,
+            is_ckb_in,
+            req_lock_args.sudt_type_hash,
+        )?;
+        *referral_index += 1;
+
+        if is_ckb_in {
+            *ckb_reserve -= referral_amount;
         } else {
-            sudt_exchange_ckb(
-                idx,
-                &req_cell,
-                &req_lock_args,
-                &output_cell,
-                ckb_reserve,
-                sudt_reserve,
-            )?;
+            *sudt_reserve -= referral_amount;
         }
     }
 
@@ -64,39 +132,44 @@ fn ckb_exchange_sudt(
     output_cell: &CellOutput,
     ckb_reserve: &mut u128,
     sudt_reserve: &mut u128,
-) -> Result<(), Error> {
+) -> Result<u128, Error> {
     let req_capcity = req_cell.capacity().unpack();
     let output_capcity = output_cell.capacity().unpack();
-    let ckb_got = req_capcity - SUDT_CAPACITY;
+    let ckb_sent = req_capcity - SUDT_CAPACITY;
 
-    if ckb_got == 0 {
+    if ckb_sent == 0 {
         return Err(Error::RequestCapcityEqSUDTCapcity);
     }
 
+    let ckb_got = req_lock_args
+        .amount_swapped(true, ckb_sent as u128)
+        .ok_or(Error::InvalidTips)?;
+
     if req_lock_args.sudt_type_hash != get_cell_type_hash!(index, Source::Output) {
         return Err(Error::InvalidOutputTypeHash);
     }
 
-    if req_capcity <= output_capcity || req_capcity - output_capcity != ckb_got {
+    if req_capcity <= output_capcity || req_capcity - output_capcity != ckb_sent {
         return Err(Error::InvalidSwapOutputCapacity);
     }
 
-    let sudt_paid = decode_u128(&load_cell_data(index, Source::Output)?)?;
-    if sudt_paid < req_lock_args.min_amount_out {
+    let sudt_got = decode_u128(&load_cell_data(index, Source::Output)?)?;
+    if sudt_got < req_lock_args.min_amount_out {
         return Err(Error::SwapAmountLessThanMin);
     }
 
     let numerator = BigUint::from(ckb_got) * FEE_RATE * (*sudt_reserve);
     let denominator = (*ckb_reserve) * THOUSAND + BigUint::from(ckb_got) * FEE_RATE;
+    let sudt_paid = sudt_got + req_lock_args.tips_sudt;
 
     if BigUint::from(sudt_paid) != numerator / denominator + ONE {
         return Err(Error::BuySUDTFailed);
     }
 
-    *ckb_reserve += ckb_got as u128;
+    *ckb_reserve += ckb_got;
     *sudt_reserve -= sudt_paid;
 
-    Ok(())
+    Ok(ckb_got)
 }
 
 fn sudt_exchange_ckb(
@@ -106,19 +179,23 @@ fn sudt_exchange_ckb(
     output_cell: &CellOutput,
     ckb_reserve: &mut u128,
     sudt_reserve: &mut u128,
-) -> Result<(), Error> {
-    let sudt_got = decode_u128(&load_cell_data(index, Source::Input)?)?;
+) -> Result<u128, Error> {
+    let sudt_sent = decode_u128(&load_cell_data(index, Source::Input)?)?;
 
-    if sudt_got == 0 {
+    if sudt_sent == 0 {
         return Err(Error::SwapInputSUDTAmountEqZero);
     }
 
+    let sudt_got = req_lock_args
+        .amount_swapped(false, sudt_sent)
+        .ok_or(Error::InvalidTips)?;
+
     if output_cell.type_().is_some() {
         return Err(Error::InvalidOutputTypeHash);
     }
 
-    let ckb_paid = (output_cell.capacity().unpack() - req_cell.capacity().unpack()) as u128;
-    if ckb_paid < req_lock_args.min_amount_out {
+    let ckb_got = (output_cell.capacity().unpack() - req_cell.capacity().unpack()) as u128;
+    if ckb_got < req_lock_args.min_amount_out {
         return Err(Error::InvalidSwapOutputCapacity);
     }
 
@@ -128,6 +205,7 @@ fn sudt_exchange_ckb(
 
     let numerator = BigUint::from(sudt_got) * FEE_RATE * (*ckb_reserve);
     let denominator = (*sudt_reserve) * THOUSAND + FEE_RATE * BigUint::from(sudt_got);
+    let ckb_paid = ckb_got + req_lock_args.tips as u128;
 
     if BigUint::from(ckb_paid) != numerator / denominator + ONE {
         return Err(Error::SellSUDTFailed);
@@ -136,5 +214,36 @@ fn sudt_exchange_ckb(
     *ckb_reserve -= ckb_paid;
     *sudt_reserve += sudt_got;
 
+    Ok(sudt_got)
+}
+
+/// A CKB referral output is topped up by the matcher to be a valid cell, so its capacity only
+/// needs to cover the referral amount, while a sUDT referral output carries the exact amount.
+fn verify_referral_output(
+    index: usize,
+    referral: &Referral,
+    referral_amount: u128,
+    is_ckb: bool,
+    sudt_type_hash: [u8; 32],
+) -> Result<(), Error> {
+    let referral_cell = load_cell(index, Source::Output)?;
+
+    if load_cell_lock_hash(index, Source::Output)? != referral.lock_hash {
+        return Err(Error::InvalidReferralOutput);
+    }
+
+    if is_ckb {
+        if referral_cell.type_().is_some()
+            || !load_cell_data(index, Source::Output)?.is_empty()
+            || (referral_cell.capacity().unpack() as u128) < referral_amount
+        {
+            return Err(Error::InvalidReferralOutput);
+        }
+    } else if load_cell_type_hash(index, Source::Output)? != Some(sudt_type_hash)
+        || decode_u128(&load_cell_data(index, Source::Output)?)? != referral_amount
+    {
+        return Err(Error::InvalidReferralOutput);
+    }
+
     Ok(())
 }
@@ -0,0 +1,258 @@
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+use num_traits::identities::Zero;
+use share::cell::SwapRequestLockArgs;
+use share::ckb_std::ckb_types::packed::CellOutput;
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{load_cell, load_cell_data, load_cell_lock_hash},
+};
+use share::{decode_u128, get_cell_type_hash, Settlement};
+
+use crate::entry::{FEE_RATE, INFO_CAPACITY, INFO_INDEX, ONE, SUDT_CAPACITY, THOUSAND};
+use crate::error::Error;
+
+/// The swaps of the batch are settled together. The CKB sold and the sUDT sold are offset
+/// against each other, at a single price or at the pool mid price, and only the rest trades
+/// against the pool curve, then every request gets its share of the other side, pro rata of its
+/// input and rounded down. The dust left by the rounding stays in the pool.
+pub fn uniform_swap_verification(
+    info_out_cell: &CellOutput,
+    request_base: usize,
+    swap_cell_count: usize,
+    settlement: Settlement,
+    ckb_reserve: &mut u128,
+    sudt_reserve: &mut u128,
+) -> Result<(), Error> {
+    if info_out_cell.capacity().unpack() != INFO_CAPACITY {
+        return Err(Error::InfoCapacityDiff);
+    }
+
+    let info_in_type_hash = get_cell_type_hash!(INFO_INDEX, Source::Input);
+    let mut requests = Vec::with_capacity(swap_cell_count);
+    let mut ckb_in = 0u128;
+    let mut sudt_in = 0u128;
+
+    for idx in request_base..(request_base + swap_cell_count) {
+        let req_cell = load_cell(idx, Source::Input)?;
+        let raw_lock_args: Vec<u8> = req_cell.lock().args().unpack();
+        let req_lock_args = SwapRequestLockArgs::from_raw(&raw_lock_args)?;
+
+        if let Some(info_type_hash) = req_lock_args.info_type_hash {
+            if info_type_hash != info_in_type_hash {
+                return Err(Error::SwapArgsInfoTypeHashMismatch);
+            }
+        }
+
+        // The referral share is taken from the fee of a curve trade, which offset requests skip
+        if req_lock_args.referral.is_some() {
+            return Err(Error::UniformSwapWithReferral);
+        }
+
+        if load_cell_lock_hash(idx, Source::Output)? != req_lock_args.user_lock_hash {
+            return Err(Error::InvalidOutputLockHash);
+        }
+
+        let is_ckb_in = req_cell.type_().is_none();
+        let amount_in = if is_ckb_in {
+            let ckb_sent = req_cell.capacity().unpack() - SUDT_CAPACITY;
+            if ckb_sent == 0 {
+                return Err(Error::RequestCapcityEqSUDTCapcity);
+            }
+
+            let amount_in = req_lock_args
+                .amount_swapped(true, ckb_sent as u128)
+                .ok_or(Error::InvalidTips)?;
+            if amount_in == 0 {
+                return Err(Error::SwapAmountAfterTipsEqZero);
+            }
+            ckb_in += amount_in;
+            amount_in
+        } else {
+            let sudt_sent = decode_u128(&load_cell_data(idx, Source::Input)?)?;
+            if sudt_sent == 0 {
+                return Err(Error::SwapInputSUDTAmountEqZero);
+            }
+
+            let amount_in = req_lock_args
+                .amount_swapped(false, sudt_sent)
+                .ok_or(Error::InvalidTips)?;
+            if amount_in == 0 {
+                return Err(Error::SwapAmountAfterTipsEqZero);
+            }
+            sudt_in += amount_in;
+            amount_in
+        };
+
+        requests.push((idx, req_cell, req_lock_args, amount_in));
+    }
+
+    let (ckb_out, sudt_out) = if settlement == Settlement::MidPrice {
+        match_at_mid_price(ckb_in, sudt_in, *ckb_reserve, *sudt_reserve)
+    } else {
+        clear(ckb_in, sudt_in, *ckb_reserve, *sudt_reserve)
+    };
+    let mut ckb_paid = 0u128;
+    let mut sudt_paid = 0u128;
+
+    // Every request swaps a positive amount, so a side with requests has a positive total
+    for (idx, req_cell, req_lock_args, amount_in) in requests {
+        let output_cell = load_cell(idx, Source::Output)?;
+
+        if req_cell.type_().is_none() {
+            sudt_paid += verify_buy_output(
+                idx,
+                &req_lock_args,
+                &output_cell,
+                BigUint::from(amount_in) * &sudt_out / ckb_in,
+            )?;
+        } else {
+            ckb_paid += verify_sell_output(
+                idx,
+                &req_cell,
+                &req_lock_args,
+                &output_cell,
+                BigUint::from(amount_in) * &ckb_out / sudt_in,
+            )?;
+        }
+    }
+
+    *ckb_reserve = (*ckb_reserve + ckb_in)
+        .checked_sub(ckb_paid)
+        .ok_or(Error::UniformPriceMismatch)?;
+    *sudt_reserve = (*sudt_reserve + sudt_in)
+        .checked_sub(sudt_paid)
+        .ok_or(Error::UniformPriceMismatch)?;
+
+    Ok(())
+}
+
+/// The CKB paid to the sellers and the sUDT paid to the buyers. With `b` CKB and `s` sUDT sold,
+/// the CKB `x` traded on the curve makes the price of the buyers `b / (s + out(x))` equal to the
+/// price of the sellers `(b - x) / s`, that is
+/// `x = (997 * sudt_reserve * b - 1000 * s * ckb_reserve) / (997 * (sudt_reserve + s))`,
+/// and symmetrically for the sUDT. When neither is positive the pool does not trade, both sides
+/// swap their whole input at a price within the fee of the pool price.
+fn clear(ckb_in: u128, sudt_in: u128, ckb_reserve: u128, sudt_reserve: u128) -> (BigUint, BigUint) {
+    let buy = BigUint::from(ckb_in) * FEE_RATE * sudt_reserve;
+    let sell = BigUint::from(sudt_in) * THOUSAND * ckb_reserve;
+    if buy > sell {
+        let ckb_net = (buy - sell) / ((BigUint::from(sudt_reserve) + sudt_in) * FEE_RATE);
+        if !ckb_net.is_zero() {
+            let numerator = &ckb_net * FEE_RATE * sudt_reserve;
+            let denominator = BigUint::from(ckb_reserve) * THOUSAND + &ckb_net * FEE_RATE;
+            let pool_sudt_out = numerator / denominator + ONE;
+
+            return (BigUint::from(ckb_in) - ckb_net, pool_sudt_out + sudt_in);
+        }
+    }
+
+    let sell = BigUint::from(sudt_in) * FEE_RATE * ckb_reserve;
+    let buy = BigUint::from(ckb_in) * THOUSAND * sudt_reserve;
+    if sell > buy {
+        let sudt_net = (sell - buy) / ((BigUint::from(ckb_reserve) + ckb_in) * FEE_RATE);
+        if !sudt_net.is_zero() {
+            let numerator = &sudt_net * FEE_RATE * ckb_reserve;
+            let denominator = BigUint::from(sudt_reserve) * THOUSAND + &sudt_net * FEE_RATE;
+            let pool_ckb_out = numerator / denominator + ONE;
+
+            return (pool_ckb_out + ckb_in, BigUint::from(sudt_in) - sudt_net);
+        }
+    }
+
+    (BigUint::from(ckb_in), BigUint::from(sudt_in))
+}
+
+/// The CKB paid to the sellers and the sUDT paid to the buyers. The smaller side is fully
+/// matched at the price `ckb_reserve / sudt_reserve` without fee, the residual of the larger side
+/// is swapped on the curve as by a single request.
+fn match_at_mid_price(
+    ckb_in: u128,
+    sudt_in: u128,
+    ckb_reserve: u128,
+    sudt_reserve: u128,
+) -> (BigUint, BigUint) {
+    let buy = BigUint::from(ckb_in) * sudt_reserve;
+    let sell = BigUint::from(sudt_in) * ckb_reserve;
+    if buy > sell {
+        let ckb_matched = sell / sudt_reserve;
+        let ckb_residual = BigUint::from(ckb_in) - &ckb_matched;
+        let numerator = &ckb_residual * FEE_RATE * sudt_reserve;
+        let denominator = BigUint::from(ckb_reserve) * THOUSAND + &ckb_residual * FEE_RATE;
+        let pool_sudt_out = numerator / denominator + ONE;
+
+        (ckb_matched, pool_sudt_out + sudt_in)
+    } else if sell > buy {
+        let sudt_matched = buy / ckb_reserve;
+        let sudt_residual = BigUint::from(sudt_in) - &sudt_matched;
+        let numerator = &sudt_residual * FEE_RATE * ckb_reserve;
+        let denominator = BigUint::from(sudt_reserve) * THOUSAND + &sudt_residual * FEE_RATE;
+        let pool_ckb_out = numerator / denominator + ONE;
+
+        (pool_ckb_out + ckb_in, sudt_matched)
+    } else {
+        (BigUint::from(ckb_in), BigUint::from(sudt_in))
+    }
+}
+
+fn verify_buy_output(
+    index: usize,
+    req_lock_args: &SwapRequestLockArgs,
+    output_cell: &CellOutput,
+    share: BigUint,
+) -> Result<u128, Error> {
+    if req_lock_args.sudt_type_hash != get_cell_type_hash!(index, Source::Output) {
+        return Err(Error::InvalidOutputTypeHash);
+    }
+
+    // All the CKB sent is swapped or paid as tips
+    if output_cell.capacity().unpack() != SUDT_CAPACITY {
+        return Err(Error::InvalidSwapOutputCapacity);
+    }
+
+    let sudt_got = decode_u128(&load_cell_data(index, Source::Output)?)?;
+    if sudt_got < req_lock_args.min_amount_out {
+        return Err(Error::SwapAmountLessThanMin);
+    }
+
+    let sudt_paid = sudt_got + req_lock_args.tips_sudt;
+    if BigUint::from(sudt_paid) != share {
+        return Err(Error::UniformPriceMismatch);
+    }
+
+    Ok(sudt_paid)
+}
+
+fn verify_sell_output(
+    index: usize,
+    req_cell: &CellOutput,
+    req_lock_args: &SwapRequestLockArgs,
+    output_cell: &CellOutput,
+    share: BigUint,
+) -> Result<u128, Error> {
+    if output_cell.type_().is_some() {
+        return Err(Error::InvalidOutputTypeHash);
+    }
+
+    let ckb_got = output_cell
+        .capacity()
+        .unpack()
+        .checked_sub(req_cell.capacity().unpack())
+        .ok_or(Error::InvalidSwapOutputCapacity)? as u128;
+    if ckb_got < req_lock_args.min_amount_out {
+        return Err(Error::InvalidSwapOutputCapacity);
+    }
+
+    if !load_cell_data(index, Source::Output)?.is_empty() {
+        return Err(Error::InvalidSwapOutputData);
+    }
+
+    let ckb_paid = ckb_got + req_lock_args.tips as u128;
+    if BigUint::from(ckb_paid) != share {
+        return Err(Error::UniformPriceMismatch);
+    }
+
+    Ok(ckb_paid)
+}
@@ -15,16 +15,27 @@ mod error;
 use alloc::vec::Vec;
 use core::result::Result;
 
+use dynamic_loading::verify_cancel;
+use share::cell::{InfoCellData, LiquidityRequestLockArgs};
 use share::ckb_std::{
     ckb_constants::Source,
-    ckb_types::prelude::*,
+    ckb_types::{packed::CellOutput, prelude::*},
     default_alloc,
-    high_level::{load_cell_lock_hash, load_script, load_witness_args, QueryIter},
+    high_level::{
+        load_cell, load_cell_data, load_cell_lock_hash, load_script, load_script_hash,
+        load_witness_args, QueryIter,
+    },
 };
-use share::{ckb_std, get_cell_type_hash};
+use share::{ckb_std, decode_batch_witness, decode_u128, get_cell_type_hash};
 
 use error::Error;
 
+const SUDT_CAPACITY: u64 = 14_200_000_000;
+const SUDT_CELL_DATA_LEN: usize = 16;
+const INFO_INDEX: usize = 0;
+const POOL_INDEX: usize = 1;
+const MATCHER_INDEX: usize = 2;
+
 default_alloc!(4 * 1024, 2048 * 1024, 64);
 
 ckb_std::entry!(program_entry);
@@ -42,6 +53,10 @@ fn main() -> Result<(), Error> {
     let self_args: Vec<u8> = load_script()?.args().unpack();
 
     // Cancel request
+    if verify_cancel(&self_args[32..64])? {
+        return Ok(());
+    }
+
     for (idx, lock_hash) in QueryIter::new(load_cell_lock_hash, Source::Input).enumerate() {
         if lock_hash == self_args[32..64]
             && load_witness_args(idx, Source::Input)?.total_size() != 0
@@ -50,9 +65,212 @@ fn main() -> Result<(), Error> {
         }
     }
 
-    if get_cell_type_hash!(0, Source::Input) == self_args[0..32] {
+    if get_cell_type_hash!(INFO_INDEX, Source::Input) != self_args[0..32] {
+        return Err(Error::NoInfoCell);
+    }
+
+    let self_hash = load_script_hash()?;
+    let req_lock_args = LiquidityRequestLockArgs::from_raw(&self_args)?;
+    let info_in_data = InfoCellData::from_raw(&load_cell_data(INFO_INDEX, Source::Input)?)?;
+    let pool_type_hash = get_cell_type_hash!(POOL_INDEX, Source::Input);
+
+    let raw_witness: Vec<u8> = load_witness_args(INFO_INDEX, Source::Input)?
+        .input_type()
+        .to_opt()
+        .ok_or(Error::ItemMissing)?
+        .unpack();
+    let (swap_cell_count, matcher_cell_count) = decode_batch_witness(&raw_witness)?;
+    if matcher_cell_count == 0 {
+        // A direct swap has no requests
+        return Err(Error::UnknownLiquidity);
+    }
+    let base_index = MATCHER_INDEX + matcher_cell_count + swap_cell_count;
+
+    for index in QueryIter::new(load_cell_lock_hash, Source::Input)
+        .enumerate()
+        .filter_map(|(idx, hash)| if hash == self_hash { Some(idx) } else { None })
+    {
+        if index < base_index {
+            return Err(Error::UnknownLiquidity);
+        }
+
+        let req_cell = load_cell(index, Source::Input)?;
+        let req_type_hash = get_cell_type_hash!(index, Source::Input);
+        let output_index = (index - base_index) * 2 + base_index;
+
+        if req_type_hash == info_in_data.liquidity_sudt_type_hash {
+            verify_remove_liquidity(
+                index,
+                output_index,
+                &req_cell,
+                &req_lock_args,
+                pool_type_hash,
+            )?;
+        } else if req_type_hash == pool_type_hash {
+            verify_add_liquidity(
+                index,
+                output_index,
+                &req_cell,
+                &req_lock_args,
+                &info_in_data,
+                pool_type_hash,
+            )?;
+        } else {
+            return Err(Error::UnknownLiquidity);
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_add_liquidity(
+    index: usize,
+    output_index: usize,
+    req_cell: &CellOutput,
+    req_lock_args: &LiquidityRequestLockArgs,
+    info_in_data: &InfoCellData,
+    pool_type_hash: [u8; 32],
+) -> Result<(), Error> {
+    let liquidity_cell = load_cell(output_index, Source::Output)?;
+    let liquidity_data = load_cell_data(output_index, Source::Output)?;
+
+    if load_cell_lock_hash(output_index, Source::Output)? != req_lock_args.user_lock_hash {
+        return Err(Error::InvalidOutputLockHash);
+    }
+
+    if get_cell_type_hash!(output_index, Source::Output) != info_in_data.liquidity_sudt_type_hash {
+        return Err(Error::InvalidOutputTypeHash);
+    }
+
+    if liquidity_data.len() < SUDT_CELL_DATA_LEN {
+        return Err(Error::InvalidOutputData);
+    }
+
+    if decode_u128(&liquidity_data[0..16])? == 0 {
+        return Err(Error::MintLiquidityEqZero);
+    }
+
+    let req_capacity = req_cell.capacity().unpack();
+    let liquidity_capacity = liquidity_cell.capacity().unpack();
+    let req_sudt_amount = decode_u128(&load_cell_data(index, Source::Input)?)?;
+
+    // Initial mint has no change cell, the user injects everything in the request cell
+    if info_in_data.total_liquidity == 0 {
+        if liquidity_capacity < SUDT_CAPACITY || req_capacity <= SUDT_CAPACITY {
+            return Err(Error::InvalidCapacity);
+        }
+
         return Ok(());
     }
 
-    Err(Error::NoInfoCell)
+    let change_cell = load_cell(output_index + 1, Source::Output)?;
+    let change_capacity = change_cell.capacity().unpack();
+    let change_data = load_cell_data(output_index + 1, Source::Output)?;
+
+    if load_cell_lock_hash(output_index + 1, Source::Output)? != req_lock_args.user_lock_hash {
+        return Err(Error::InvalidOutputLockHash);
+    }
+
+    if change_data.is_empty() {
+        // Ckb change, all sudt in request is injected
+        if change_cell.type_().is_some() {
+            return Err(Error::InvalidOutputTypeHash);
+        }
+
+        if liquidity_capacity < SUDT_CAPACITY
+            || req_capacity < SUDT_CAPACITY
+            || req_capacity - SUDT_CAPACITY < change_capacity
+        {
+            return Err(Error::InvalidCapacity);
+        }
+
+        let ckb_injected = req_capacity - SUDT_CAPACITY - change_capacity;
+        if ckb_injected <= req_lock_args.tips
+            || ckb_injected - req_lock_args.tips < req_lock_args.amount_0
+        {
+            return Err(Error::InvalidMinCkbInject);
+        }
+
+        if req_sudt_amount <= req_lock_args.tips_sudt
+            || req_sudt_amount - req_lock_args.tips_sudt < req_lock_args.amount_1
+        {
+            return Err(Error::InvalidMinSUDTInject);
+        }
+    } else if change_data.len() >= SUDT_CELL_DATA_LEN {
+        // Sudt change, all ckb except two sudt cells capacity is injected
+        if get_cell_type_hash!(output_index + 1, Source::Output) != pool_type_hash {
+            return Err(Error::InvalidOutputTypeHash);
+        }
+
+        if (liquidity_capacity as u128 + change_capacity as u128) < SUDT_CAPACITY as u128 * 2
+            || req_capacity <= SUDT_CAPACITY * 2
+        {
+            return Err(Error::InvalidCapacity);
+        }
+
+        let ckb_injected = req_capacity - SUDT_CAPACITY * 2;
+        if ckb_injected < req_lock_args.tips
+            || ckb_injected - req_lock_args.tips < req_lock_args.amount_0
+        {
+            return Err(Error::InvalidMinCkbInject);
+        }
+
+        let sudt_change = decode_u128(&change_data[0..16])?;
+        if sudt_change >= req_sudt_amount
+            || req_sudt_amount - sudt_change < req_lock_args.tips_sudt
+            || req_sudt_amount - sudt_change - req_lock_args.tips_sudt < req_lock_args.amount_1
+        {
+            return Err(Error::InvalidMinSUDTInject);
+        }
+    } else {
+        return Err(Error::InvalidOutputData);
+    }
+
+    Ok(())
+}
+
+fn verify_remove_liquidity(
+    index: usize,
+    output_index: usize,
+    req_cell: &CellOutput,
+    req_lock_args: &LiquidityRequestLockArgs,
+    pool_type_hash: [u8; 32],
+) -> Result<(), Error> {
+    if decode_u128(&load_cell_data(index, Source::Input)?)? == 0 {
+        return Err(Error::InvalidOutputData);
+    }
+
+    let sudt_cell = load_cell(output_index, Source::Output)?;
+    let sudt_data = load_cell_data(output_index, Source::Output)?;
+    let ckb_cell = load_cell(output_index + 1, Source::Output)?;
+
+    if load_cell_lock_hash(output_index, Source::Output)? != req_lock_args.user_lock_hash
+        || load_cell_lock_hash(output_index + 1, Source::Output)? != req_lock_args.user_lock_hash
+    {
+        return Err(Error::InvalidOutputLockHash);
+    }
+
+    if get_cell_type_hash!(output_index, Source::Output) != pool_type_hash
+        || ckb_cell.type_().is_some()
+    {
+        return Err(Error::InvalidOutputTypeHash);
+    }
+
+    if sudt_data.len() < SUDT_CELL_DATA_LEN
+        || !load_cell_data(output_index + 1, Source::Output)?.is_empty()
+    {
+        return Err(Error::InvalidOutputData);
+    }
+
+    let ckb_out = sudt_cell.capacity().unpack() as u128 + ckb_cell.capacity().unpack() as u128;
+    let ckb_min = req_cell.capacity().unpack() as u128 + req_lock_args.amount_0 as u128;
+    if ckb_out < ckb_min {
+        return Err(Error::InvalidMinCkbGot);
+    }
+
+    if decode_u128(&sudt_data[0..16])? < req_lock_args.amount_1 {
+        return Err(Error::InvalidMinSUDTGot);
+    }
+
+    Ok(())
 }
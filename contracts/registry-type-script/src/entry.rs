@@ -0,0 +1,192 @@
+use alloc::vec::Vec;
+use core::result::Result;
+
+// Import CKB syscalls and structures
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use share::ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    default_alloc,
+    high_level::{
+        load_cell, load_cell_data, load_cell_lock_hash, load_cell_type_hash, load_script,
+        load_script_hash, load_transaction, QueryIter,
+    },
+};
+use share::{blake2b, cell::RegistryCellData};
+
+use crate::error::Error;
+
+const INFO_INDEX: usize = 0;
+const POOL_INDEX: usize = 1;
+const EMPTY_KEY: [u8; 32] = [0u8; 32];
+
+// Alloc 4K fast HEAP + 2M HEAP to receives PrefilledData
+default_alloc!(4 * 1024, 2048 * 1024, 64);
+
+pub fn main() -> Result<(), Error> {
+    let input_count = QueryIter::new(load_cell, Source::GroupInput).count();
+    let output_count = QueryIter::new(load_cell, Source::GroupOutput).count();
+
+    match (input_count, output_count) {
+        (0, 1) => verify_genesis(),
+        (1, 2) => verify_insertion(),
+        (2, 1) => verify_removal(),
+        _ => Err(Error::InvalidRegistryCellCount),
+    }
+}
+
+/// The registry starts with a single head node which sorts before every key.
+fn verify_genesis() -> Result<(), Error> {
+    verify_type_id()?;
+
+    let head = RegistryCellData::from_raw(&load_cell_data(0, Source::GroupOutput)?)?;
+    if head.key != EMPTY_KEY || head.next_key != EMPTY_KEY {
+        return Err(Error::InvalidRegistryHead);
+    }
+
+    Ok(())
+}
+
+/// Insert a key right after an existing node. The list stays sorted, so a key
+/// can never be registered twice.
+fn verify_insertion() -> Result<(), Error> {
+    let prev_in = RegistryCellData::from_raw(&load_cell_data(0, Source::GroupInput)?)?;
+    let prev_out = RegistryCellData::from_raw(&load_cell_data(0, Source::GroupOutput)?)?;
+    let new_node = RegistryCellData::from_raw(&load_cell_data(1, Source::GroupOutput)?)?;
+
+    if prev_out.key != prev_in.key
+        || prev_out.next_key != new_node.key
+        || new_node.next_key != prev_in.next_key
+    {
+        return Err(Error::InvalidRegistryLink);
+    }
+
+    if prev_out.info_type_code_hash != prev_in.info_type_code_hash
+        || new_node.info_type_code_hash != prev_in.info_type_code_hash
+    {
+        return Err(Error::InfoTypeCodeHashChanged);
+    }
+
+    if new_node.key <= prev_in.key
+        || (prev_in.next_key != EMPTY_KEY && new_node.key >= prev_in.next_key)
+    {
+        return Err(Error::RegistryKeyNotSorted);
+    }
+
+    let lock_hash = load_cell_lock_hash(0, Source::GroupInput)?;
+    if load_cell_lock_hash(0, Source::GroupOutput)? != lock_hash
+        || load_cell_lock_hash(1, Source::GroupOutput)? != lock_hash
+    {
+        return Err(Error::RegistryLockHashChanged);
+    }
+
+    if load_cell(0, Source::GroupOutput)?.capacity().unpack()
+        < load_cell(0, Source::GroupInput)?.capacity().unpack()
+    {
+        return Err(Error::InvalidRegistryCapacity);
+    }
+
+    // A key can only be registered along with the creation of its pool, the info
+    // type script checks the key against the created pool.
+    let is_info_cell = |code_hash: Option<[u8; 32]>| code_hash == Some(prev_in.info_type_code_hash);
+    let info_out_code_hash = load_cell(INFO_INDEX, Source::Output)?
+        .type_()
+        .to_opt()
+        .map(|script| script.code_hash().unpack());
+
+    if !is_info_cell(info_out_code_hash)
+        || QueryIter::new(load_cell, Source::Input)
+            .any(|cell| is_info_cell(cell.type_().to_opt().map(|s| s.code_hash().unpack())))
+    {
+        return Err(Error::NoPoolCreation);
+    }
+
+    Ok(())
+}
+
+/// Remove a node by linking its predecessor to its successor, along with the close of the pool
+/// of its key.
+fn verify_removal() -> Result<(), Error> {
+    let first_in = RegistryCellData::from_raw(&load_cell_data(0, Source::GroupInput)?)?;
+    let second_in = RegistryCellData::from_raw(&load_cell_data(1, Source::GroupInput)?)?;
+    let prev_out = RegistryCellData::from_raw(&load_cell_data(0, Source::GroupOutput)?)?;
+
+    let (prev_idx, prev_in, removed) = if first_in.next_key == second_in.key {
+        (0, first_in, second_in)
+    } else {
+        (1, second_in, first_in)
+    };
+
+    if removed.key == EMPTY_KEY
+        || prev_in.next_key != removed.key
+        || prev_out.key != prev_in.key
+        || prev_out.next_key != removed.next_key
+    {
+        return Err(Error::InvalidRegistryLink);
+    }
+
+    if prev_out.info_type_code_hash != prev_in.info_type_code_hash
+        || removed.info_type_code_hash != prev_in.info_type_code_hash
+    {
+        return Err(Error::InfoTypeCodeHashChanged);
+    }
+
+    if load_cell_lock_hash(0, Source::GroupOutput)?
+        != load_cell_lock_hash(prev_idx, Source::GroupInput)?
+    {
+        return Err(Error::RegistryLockHashChanged);
+    }
+
+    if load_cell(0, Source::GroupOutput)?.capacity().unpack()
+        < load_cell(prev_idx, Source::GroupInput)?.capacity().unpack()
+    {
+        return Err(Error::InvalidRegistryCapacity);
+    }
+
+    // The info type script closes the pool only when it is empty and the creator signs
+    let is_info_cell = |code_hash: Option<[u8; 32]>| code_hash == Some(prev_in.info_type_code_hash);
+    let info_in_code_hash = load_cell(INFO_INDEX, Source::Input)?
+        .type_()
+        .to_opt()
+        .map(|script| script.code_hash().unpack());
+
+    if !is_info_cell(info_in_code_hash)
+        || load_cell_type_hash(POOL_INDEX, Source::Input)? != Some(removed.key)
+        || QueryIter::new(load_cell, Source::Output)
+            .any(|cell| is_info_cell(cell.type_().to_opt().map(|s| s.code_hash().unpack())))
+    {
+        return Err(Error::NoPoolClose);
+    }
+
+    Ok(())
+}
+
+fn verify_type_id() -> Result<(), Error> {
+    let script_args: Vec<u8> = load_script()?.args().unpack();
+    if script_args.len() != 32 {
+        return Err(Error::InvalidRegistryTypeArgsLen);
+    }
+
+    let self_hash = load_script_hash()?;
+    let first_cell_input_outpoint = load_transaction()?
+        .raw()
+        .inputs()
+        .get(0)
+        .unwrap()
+        .previous_output();
+
+    let first_output_index = QueryIter::new(load_cell_type_hash, Source::Output)
+        .position(|hash| hash == Some(self_hash))
+        .unwrap() as u64;
+
+    let hash = blake2b!(
+        first_cell_input_outpoint.tx_hash().unpack(),
+        first_output_index.to_le_bytes()
+    );
+
+    if hash[..] != script_args[..] {
+        return Err(Error::InvalidTypeID);
+    }
+
+    Ok(())
+}
@@ -0,0 +1 @@
+pub use share::error::RegistryTypeError as Error;
@@ -15,6 +15,7 @@ mod error;
 use alloc::vec::Vec;
 use core::result::Result;
 
+use dynamic_loading::verify_cancel;
 use num_bigint::BigUint;
 use share::ckb_std::{
     self,
@@ -26,11 +27,12 @@ use share::ckb_std::{
         load_witness_args, QueryIter,
     },
 };
-use share::{cell::SwapRequestLockArgs, decode_u128, get_cell_type_hash};
+use share::{cell::SwapRequestLockArgs, decode_batch_witness, decode_u128, get_cell_type_hash};
 
 use crate::error::Error;
 
 const SUDT_CAPACITY: u64 = 14_200_000_000;
+const MATCHER_INDEX: usize = 2;
 
 // Alloc 4K fast HEAP + 2M HEAP to receives PrefilledData
 default_alloc!(4 * 1024, 2048 * 1024, 64);
@@ -50,6 +52,10 @@ fn main() -> Result<(), Error> {
     let script_args: Vec<u8> = load_script()?.args().unpack();
 
     // Cancel request
+    if verify_cancel(&script_args[32..64])? {
+        return Ok(());
+    }
+
     for (idx, lock_hash) in QueryIter::new(load_cell_lock_hash, Source::Input).enumerate() {
         if lock_hash == script_args[32..64]
             && load_witness_args(idx, Source::Input)?.total_size() != 0
@@ -61,10 +67,29 @@ fn main() -> Result<(), Error> {
     let self_hash = load_script_hash()?;
     let req_lock_args = SwapRequestLockArgs::from_raw(&script_args)?;
 
+    if let Some(info_type_hash) = req_lock_args.info_type_hash {
+        if get_cell_type_hash!(0, Source::Input) != info_type_hash {
+            return Err(Error::InfoTypeHashMismatch);
+        }
+    }
+
+    let raw_witness: Vec<u8> = load_witness_args(0, Source::Input)?
+        .input_type()
+        .to_opt()
+        .ok_or(Error::ItemMissing)?
+        .unpack();
+    let (swap_cell_count, matcher_cell_count) = decode_batch_witness(&raw_witness)?;
+    let request_base = MATCHER_INDEX + matcher_cell_count;
+
     for index in QueryIter::new(load_cell_lock_hash, Source::Input)
         .enumerate()
         .filter_map(|(idx, hash)| if hash == self_hash { Some(idx) } else { None })
     {
+        // Only the swap requests counted in the batch are verified by the info type script
+        if index < request_base || index >= request_base + swap_cell_count {
+            return Err(Error::UnknownSwap);
+        }
+
         let req_cell = load_cell(index, Source::Input)?;
         let output_cell = load_cell(index, Source::Output)?;
 
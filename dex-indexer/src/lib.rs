@@ -0,0 +1,388 @@
+//! Rebuilds the history of the pools out of committed transactions. Pool transactions are
+//! recognised by the info type script, and every transition of an info cell adds a point to the
+//! series of its pool: reserves, liquidity supply, and the swap volume and fees of the
+//! transaction. Transactions are indexed in the order they were committed, along with their
+//! resolved input cells.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ckb_standalone_debugger::transaction::{MockTransaction, ReprMockTransaction};
+use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::core::TransactionView;
+use ckb_tool::ckb_types::packed::{CellOutput, WitnessArgs};
+use ckb_tool::ckb_types::prelude::*;
+use dex_sdk::cell::decode_sudt_amount;
+use dex_sdk::{PoolState, FEE_RATE, INFO_INDEX, MATCHER_INDEX, SUDT_CAPACITY, THOUSAND};
+use share::cell::{InfoCellData, SwapRequestLockArgs};
+use share::{decode_batch_settlement, decode_batch_witness, Settlement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A pool cell or request can not be decoded.
+    Encoding,
+    /// The input cells don't match the inputs of the transaction.
+    MissingInputCell,
+    InvalidJson,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    CreatePool,
+    InitialMint,
+    /// A batch of swap requests only.
+    Swap,
+    /// A batch of liquidity requests only.
+    Liquidity,
+    /// A batch of swap and liquidity requests.
+    Batch,
+    DirectSwap,
+    ClosePool,
+    /// The pool moved to a new info cell, its history goes on under the new info type hash.
+    Migration,
+    /// The reserves and the liquidity are unchanged, as when the pool is paused, or the
+    /// transaction has no batch witness.
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolPoint {
+    pub timestamp:   u64,
+    pub tx_hash:     [u8; 32],
+    pub kind:        TxKind,
+    /// The pool after the transaction.
+    pub state:       PoolState,
+    /// The CKB and sUDT swapped in, tips excluded.
+    pub ckb_volume:  u128,
+    pub sudt_volume: u128,
+    /// The 0.3% pool fee paid in CKB and in sUDT, referral shares included.
+    pub ckb_fees:    u128,
+    pub sudt_fees:   u128,
+}
+
+impl PoolPoint {
+    /// The CKB price of one sUDT unit.
+    pub fn price(&self) -> Option<f64> {
+        if self.state.ckb_reserve == 0 || self.state.sudt_reserve == 0 {
+            return None;
+        }
+
+        Some(self.state.ckb_reserve as f64 / self.state.sudt_reserve as f64)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoolHistory {
+    pub info_type_hash: [u8; 32],
+    pub sudt_type_hash: Option<[u8; 32]>,
+    pub points:         Vec<PoolPoint>,
+    /// The info type hash of the pool after its migration.
+    pub migrated_to:    Option<[u8; 32]>,
+}
+
+impl PoolHistory {
+    pub fn latest(&self) -> Option<&PoolPoint> {
+        self.points.last()
+    }
+}
+
+/// Indexes the pools of one version of the info type script, identified by its code hash. A
+/// migrated pool gets a new info type hash, and so a new history, the old one ends with a
+/// `Migration` point and links to it.
+pub struct Indexer {
+    info_type_code_hash: [u8; 32],
+    pools:               BTreeMap<[u8; 32], PoolHistory>,
+}
+
+impl Indexer {
+    pub fn new(info_type_code_hash: [u8; 32]) -> Self {
+        Indexer {
+            info_type_code_hash,
+            pools: BTreeMap::new(),
+        }
+    }
+
+    pub fn pool(&self, info_type_hash: &[u8; 32]) -> Option<&PoolHistory> {
+        self.pools.get(info_type_hash)
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &PoolHistory> {
+        self.pools.values()
+    }
+
+    /// Indexes a transaction dumped as a `ReprMockTransaction`, which carries its input cells.
+    /// Dumps have no block, `timestamp` can be any increasing sequence.
+    pub fn index_json(&mut self, timestamp: u64, json: &str) -> Result<(), Error> {
+        let repr: ReprMockTransaction =
+            serde_json::from_str(json).map_err(|_| Error::InvalidJson)?;
+        let mock_tx: MockTransaction = repr.into();
+        let inputs = mock_tx
+            .mock_info
+            .inputs
+            .iter()
+            .map(|input| (input.output.clone(), input.data.clone()))
+            .collect::<Vec<_>>();
+
+        self.index(timestamp, &mock_tx.tx.into_view(), &inputs)
+    }
+
+    /// Indexes `tx` committed at `timestamp`, `inputs` are the cells it consumes.
+    pub fn index(
+        &mut self,
+        timestamp: u64,
+        tx: &TransactionView,
+        inputs: &[(CellOutput, Bytes)],
+    ) -> Result<(), Error> {
+        if inputs.len() != tx.inputs().len() {
+            return Err(Error::MissingInputCell);
+        }
+
+        let tx_hash: [u8; 32] = tx.hash().unpack();
+        let info_inputs = self.info_cells(inputs.iter().cloned());
+        let info_outputs = self.info_cells(tx.outputs_with_data_iter());
+
+        for (index, info_type_hash, data) in info_outputs.iter() {
+            let info_out = InfoCellData::from_raw(data).map_err(|_| Error::Encoding)?;
+            let state = pool_state(&info_out);
+
+            let info_in = info_inputs
+                .iter()
+                .find(|(_, type_hash, _)| type_hash == info_type_hash);
+            let point = match info_in {
+                None => {
+                    // The pool cell follows the info cell
+                    let sudt_type_hash = tx
+                        .outputs()
+                        .get(index + 1)
+                        .and_then(|cell| cell.type_().to_opt())
+                        .map(|script| script.calc_script_hash().unpack());
+                    self.pools.insert(*info_type_hash, PoolHistory {
+                        info_type_hash: *info_type_hash,
+                        sudt_type_hash,
+                        points: Vec::new(),
+                        migrated_to: None,
+                    });
+
+                    new_point(timestamp, tx_hash, TxKind::CreatePool, state)
+                }
+                Some((_, _, data)) => {
+                    let info_in = InfoCellData::from_raw(data).map_err(|_| Error::Encoding)?;
+                    transition_point(timestamp, tx, inputs, &pool_state(&info_in), state)?
+                }
+            };
+
+            self.pools
+                .entry(*info_type_hash)
+                .or_insert_with(|| PoolHistory {
+                    info_type_hash: *info_type_hash,
+                    sudt_type_hash: None,
+                    points:         Vec::new(),
+                    migrated_to:    None,
+                })
+                .points
+                .push(point);
+        }
+
+        for (index, info_type_hash, data) in info_inputs.iter() {
+            if info_outputs
+                .iter()
+                .any(|(_, hash, _)| hash == info_type_hash)
+            {
+                continue;
+            }
+
+            if let Some(history) = self.pools.get_mut(info_type_hash) {
+                let point = match migration_target(tx, inputs, *index) {
+                    Some(target) => {
+                        let info_in = InfoCellData::from_raw(data).map_err(|_| Error::Encoding)?;
+                        history.migrated_to = Some(target);
+                        new_point(timestamp, tx_hash, TxKind::Migration, pool_state(&info_in))
+                    }
+                    None => {
+                        let state = PoolState {
+                            ckb_reserve:     0,
+                            sudt_reserve:    0,
+                            total_liquidity: 0,
+                        };
+                        new_point(timestamp, tx_hash, TxKind::ClosePool, state)
+                    }
+                };
+                history.points.push(point);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The index, type hash and data of the info cells among `cells`.
+    fn info_cells<I>(&self, cells: I) -> Vec<(usize, [u8; 32], Bytes)>
+    where
+        I: Iterator<Item = (CellOutput, Bytes)>,
+    {
+        cells
+            .enumerate()
+            .filter_map(|(index, (output, data))| {
+                let script = output.type_().to_opt()?;
+                let code_hash: [u8; 32] = script.code_hash().unpack();
+                if code_hash != self.info_type_code_hash {
+                    return None;
+                }
+
+                Some((index, script.calc_script_hash().unpack(), data))
+            })
+            .collect()
+    }
+}
+
+/// The info type hash the info input at `index` migrates to. As checked by the info type script,
+/// the output at the same index is a typed info cell, followed by the pool cell of the same type.
+fn migration_target(
+    tx: &TransactionView,
+    inputs: &[(CellOutput, Bytes)],
+    index: usize,
+) -> Option<[u8; 32]> {
+    let (pool_in, _) = inputs.get(index + 1)?;
+    let pool_type_hash = pool_in.type_().to_opt()?.calc_script_hash();
+    let info_out_type = tx.outputs().get(index)?.type_().to_opt()?;
+    let pool_out_type = tx.outputs().get(index + 1)?.type_().to_opt()?;
+    if pool_out_type.calc_script_hash() != pool_type_hash {
+        return None;
+    }
+
+    Some(info_out_type.calc_script_hash().unpack())
+}
+
+fn pool_state(info: &InfoCellData) -> PoolState {
+    PoolState {
+        ckb_reserve:     info.ckb_reserve,
+        sudt_reserve:    info.sudt_reserve,
+        total_liquidity: info.total_liquidity,
+    }
+}
+
+fn new_point(timestamp: u64, tx_hash: [u8; 32], kind: TxKind, state: PoolState) -> PoolPoint {
+    PoolPoint {
+        timestamp,
+        tx_hash,
+        kind,
+        state,
+        ckb_volume: 0,
+        sudt_volume: 0,
+        ckb_fees: 0,
+        sudt_fees: 0,
+    }
+}
+
+/// The point of a pool going from `before` to `after`, classified by the batch witness of the
+/// info input. Without a valid batch witness the transaction is not a batch, it is `Other`.
+fn transition_point(
+    timestamp: u64,
+    tx: &TransactionView,
+    inputs: &[(CellOutput, Bytes)],
+    before: &PoolState,
+    after: PoolState,
+) -> Result<PoolPoint, Error> {
+    let mut point = new_point(timestamp, tx.hash().unpack(), TxKind::Other, after);
+    if before == &after {
+        return Ok(point);
+    }
+
+    if before.total_liquidity == 0 {
+        point.kind = TxKind::InitialMint;
+        return Ok(point);
+    }
+
+    let raw_witness: Vec<u8> = match tx
+        .witnesses()
+        .get(INFO_INDEX)
+        .and_then(|witness| WitnessArgs::from_slice(&witness.raw_data()).ok())
+        .and_then(|witness| witness.input_type().to_opt())
+    {
+        Some(raw) => raw.unpack(),
+        None => return Ok(point),
+    };
+    let (swap_count, matcher_count, settlement) = match (
+        decode_batch_witness(&raw_witness),
+        decode_batch_settlement(&raw_witness),
+    ) {
+        (Ok((swap_count, matcher_count)), Ok(settlement)) => {
+            (swap_count, matcher_count, settlement)
+        }
+        _ => return Ok(point),
+    };
+
+    if matcher_count == 0 {
+        // The pool is the only counterparty, the reserve growing is the one swapped in
+        point.kind = TxKind::DirectSwap;
+        if after.ckb_reserve > before.ckb_reserve {
+            point.ckb_volume = after.ckb_reserve - before.ckb_reserve;
+            point.ckb_fees = fee(point.ckb_volume);
+        } else {
+            point.sudt_volume = after.sudt_reserve.saturating_sub(before.sudt_reserve);
+            point.sudt_fees = fee(point.sudt_volume);
+        }
+        return Ok(point);
+    }
+
+    let request_base = MATCHER_INDEX + matcher_count;
+    let liquidity_count = inputs.len().saturating_sub(request_base + swap_count);
+    point.kind = match (swap_count, liquidity_count) {
+        (_, 0) => TxKind::Swap,
+        (0, _) => TxKind::Liquidity,
+        _ => TxKind::Batch,
+    };
+
+    let (mut ckb_paid, mut sudt_paid) = (0, 0);
+    for index in request_base..(request_base + swap_count) {
+        let (req_cell, req_data) = inputs.get(index).ok_or(Error::MissingInputCell)?;
+        let (output, data) = tx.output_with_data(index).ok_or(Error::Encoding)?;
+        let raw_args: Bytes = req_cell.lock().args().unpack();
+        let args = SwapRequestLockArgs::from_raw(&raw_args).map_err(|_| Error::Encoding)?;
+        let req_capacity: u64 = req_cell.capacity().unpack();
+
+        if req_cell.type_().is_none() {
+            let ckb_in = (req_capacity as u128)
+                .checked_sub(SUDT_CAPACITY as u128 + args.tips as u128)
+                .ok_or(Error::Encoding)?;
+            point.ckb_volume += ckb_in;
+            sudt_paid += decode_sudt_amount(&data).map_err(|_| Error::Encoding)? + args.tips_sudt;
+            if settlement == Settlement::Sequential {
+                point.ckb_fees += fee(ckb_in);
+            }
+        } else {
+            let sudt_in = decode_sudt_amount(req_data)
+                .map_err(|_| Error::Encoding)?
+                .checked_sub(args.tips_sudt)
+                .ok_or(Error::Encoding)?;
+            point.sudt_volume += sudt_in;
+            let capacity: u64 = output.capacity().unpack();
+            ckb_paid += capacity.saturating_sub(req_capacity) as u128 + args.tips as u128;
+            if settlement == Settlement::Sequential {
+                point.sudt_fees += fee(sudt_in);
+            }
+        }
+    }
+
+    // Settled swaps offset each other, only the side left over pays the fee to the pool
+    if settlement != Settlement::Sequential {
+        if point.ckb_volume > ckb_paid {
+            point.ckb_fees = fee(point.ckb_volume - ckb_paid);
+        } else if point.sudt_volume > sudt_paid {
+            point.sudt_fees = fee(point.sudt_volume - sudt_paid);
+        }
+    }
+
+    Ok(point)
+}
+
+fn fee(amount_in: u128) -> u128 {
+    amount_in - amount_in * FEE_RATE / THOUSAND
+}
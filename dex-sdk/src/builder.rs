@@ -0,0 +1,957 @@
+use std::convert::TryFrom;
+
+use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::core::{TransactionBuilder, TransactionView};
+use ckb_tool::ckb_types::packed::{CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs};
+use ckb_tool::ckb_types::prelude::*;
+use share::cell::{InfoCellData, RegistryCellData};
+use share::{blake2b, request_order_key, Operation, Settlement};
+
+use crate::cell::{
+    decode_sudt_amount, encode_info_data, encode_sudt_amount, occupied_capacity,
+    output_with_capacity, output_with_min_capacity, Deployment, LiveCell,
+};
+use crate::error::Error;
+use crate::matcher::PendingRequest;
+use crate::pool::{liquidity_sudt_type, Pool, PoolState};
+use crate::quote::{self, Asset};
+use crate::request::{LiquidityRequest, SwapRequest};
+use crate::{INFO_CAPACITY, INFO_VERSION, POOL_CAPACITY, SUDT_CAPACITY};
+
+const EMPTY_KEY: [u8; 32] = [0u8; 32];
+
+/// Creates a pool and registers it right after its predecessor in the registry. The funding
+/// cells pay for the info, pool and new registry cells, the rest goes to the change lock.
+pub struct CreatePool<'a> {
+    deployment:        &'a Deployment,
+    sudt_type:         Script,
+    change_lock:       Script,
+    funding:           Vec<LiveCell>,
+    registry:          Option<LiveCell>,
+    creator_lock_hash: Option<[u8; 32]>,
+    admin_lock_hash:   Option<[u8; 32]>,
+    cell_deps:         Vec<CellDep>,
+    fee:               u64,
+}
+
+impl<'a> CreatePool<'a> {
+    pub fn new(deployment: &'a Deployment, sudt_type: Script, change_lock: Script) -> Self {
+        CreatePool {
+            deployment,
+            sudt_type,
+            change_lock,
+            funding: Vec::new(),
+            registry: None,
+            creator_lock_hash: None,
+            admin_lock_hash: None,
+            cell_deps: Vec::new(),
+            fee: 0,
+        }
+    }
+
+    pub fn funding(mut self, cell: LiveCell) -> Self {
+        self.funding.push(cell);
+        self
+    }
+
+    /// Picks the registry cell to insert the pool after, among the live registry cells.
+    pub fn registry(mut self, cells: &[LiveCell]) -> Result<Self, Error> {
+        let registry_type_hash: [u8; 32] =
+            self.deployment.registry_type().calc_script_hash().unpack();
+        let info_type_code_hash: [u8; 32] = self.deployment.info_type.code_hash.unpack();
+        let key: [u8; 32] = self.sudt_type.calc_script_hash().unpack();
+
+        for cell in cells {
+            if cell.type_hash() != Some(registry_type_hash) {
+                continue;
+            }
+
+            let node = RegistryCellData::from_raw(&cell.data)?;
+            if node.key == key {
+                return Err(Error::PoolAlreadyRegistered);
+            }
+
+            if node.info_type_code_hash == info_type_code_hash
+                && node.key < key
+                && (node.next_key == EMPTY_KEY || key < node.next_key)
+            {
+                self.registry = Some(cell.clone());
+            }
+        }
+
+        if self.registry.is_none() {
+            return Err(Error::MissingRegistryCell);
+        }
+
+        Ok(self)
+    }
+
+    /// The lock receiving the capacity of the pool when it is closed.
+    pub fn creator(mut self, lock_hash: [u8; 32]) -> Self {
+        self.creator_lock_hash = Some(lock_hash);
+        self
+    }
+
+    /// The lock allowed to pause the pool.
+    pub fn admin(mut self, lock_hash: [u8; 32]) -> Self {
+        self.admin_lock_hash = Some(lock_hash);
+        self
+    }
+
+    /// Cell deps of the funding cell locks.
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn build(self) -> Result<TransactionView, Error> {
+        let deployment = self.deployment;
+        let registry = self.registry.ok_or(Error::MissingRegistryCell)?;
+        let first_input = self.funding.first().ok_or(Error::InsufficientCapacity)?;
+        let pool_type_hash: [u8; 32] = self.sudt_type.calc_script_hash().unpack();
+
+        // The info type args is a type id of the first input and the first output
+        let first_tx_hash: [u8; 32] = first_input.out_point.tx_hash().unpack();
+        let info_type = deployment.info_type.script(Bytes::from(
+            blake2b!(first_tx_hash, 0u64.to_le_bytes()).to_vec(),
+        ));
+        let info_type_hash: [u8; 32] = info_type.calc_script_hash().unpack();
+
+        let mut info_lock_args = blake2b!("ckb", pool_type_hash).to_vec();
+        info_lock_args.extend_from_slice(&info_type_hash);
+        let info_lock = deployment.info_lock.script(Bytes::from(info_lock_args));
+        let liquidity_type =
+            liquidity_sudt_type(&self.sudt_type, info_lock.calc_script_hash().unpack());
+
+        let info_data = InfoCellData {
+            ckb_reserve:              0,
+            sudt_reserve:             0,
+            total_liquidity:          0,
+            liquidity_sudt_type_hash: liquidity_type.calc_script_hash().unpack(),
+            creator_lock_hash:        self.creator_lock_hash,
+            admin_lock_hash:          self.admin_lock_hash,
+            paused:                   false,
+            pending_admin_lock_hash:  None,
+            activation_timestamp:     0,
+            migration_code_hash:      None,
+        };
+        let info_data_len = if self.creator_lock_hash.is_some() || self.admin_lock_hash.is_some() {
+            145
+        } else {
+            80
+        };
+
+        let prev_node = RegistryCellData::from_raw(&registry.data)?;
+        let prev_data = registry_data(prev_node.key, pool_type_hash, prev_node.info_type_code_hash);
+        let new_data = registry_data(
+            pool_type_hash,
+            prev_node.next_key,
+            prev_node.info_type_code_hash,
+        );
+        let new_registry = output_with_min_capacity(
+            registry.output.lock(),
+            registry.output.type_().to_opt(),
+            0,
+            new_data.len(),
+        )?;
+
+        let funding_capacity = self
+            .funding
+            .iter()
+            .map(|cell| cell.capacity())
+            .try_fold(0u64, u64::checked_add)
+            .ok_or(Error::Overflow)?;
+        let new_registry_capacity: u64 = new_registry.capacity().unpack();
+        let change_capacity = funding_capacity
+            .checked_sub(INFO_CAPACITY + POOL_CAPACITY + new_registry_capacity + self.fee)
+            .ok_or(Error::InsufficientCapacity)?;
+
+        let outputs = vec![
+            (
+                output_with_capacity(
+                    info_lock.clone(),
+                    Some(info_type),
+                    INFO_CAPACITY,
+                    info_data_len,
+                )?,
+                encode_info_data(&info_data, info_data_len)?,
+            ),
+            (
+                output_with_capacity(info_lock, Some(self.sudt_type), POOL_CAPACITY, 16)?,
+                encode_sudt_amount(0),
+            ),
+            (registry.output.clone(), prev_data),
+            (new_registry, new_data),
+            (
+                output_with_capacity(self.change_lock, None, change_capacity, 0)?,
+                Bytes::new(),
+            ),
+        ];
+
+        let inputs = self
+            .funding
+            .iter()
+            .chain(Some(&registry))
+            .collect::<Vec<_>>();
+        let cell_deps = vec![
+            deployment.info_type.cell_dep.clone(),
+            deployment.info_lock.cell_dep.clone(),
+            deployment.registry_type.cell_dep.clone(),
+            deployment.sudt.cell_dep.clone(),
+        ];
+
+        Ok(build_tx(
+            inputs,
+            outputs,
+            cell_deps.into_iter().chain(self.cell_deps).collect(),
+            Vec::new(),
+        ))
+    }
+}
+
+/// A batch transaction and the pool state it leads to.
+pub struct Batch {
+    pub tx:    TransactionView,
+    pub state: PoolState,
+}
+
+/// Fills swap and liquidity requests against a pool, in the order required by the info type
+/// script whatever the order they are added in. The first matcher cell collects the CKB tips
+/// and pays the transaction fee and the referral cell capacity, the first matcher cell of the
+/// pool sUDT collects the sUDT tips. A pool without liquidity can only be filled with a single
+/// add liquidity request, the initial mint.
+pub struct BatchBuilder<'a> {
+    deployment: &'a Deployment,
+    pool:       &'a Pool,
+    matchers:   Vec<LiveCell>,
+    swaps:      Vec<SwapRequest>,
+    liquidity:  Vec<LiquidityRequest>,
+    cell_deps:  Vec<CellDep>,
+    fee:        u64,
+    settlement: Settlement,
+    operations: Vec<Operation>,
+}
+
+#[derive(Default)]
+struct Fill {
+    outputs:   Vec<(CellOutput, Bytes)>,
+    referrals: Vec<(CellOutput, Bytes)>,
+    tips:      u128,
+    tips_sudt: u128,
+    top_up:    u64,
+}
+
+impl Fill {
+    /// Appends the outputs of `other` after the ones of `self`.
+    fn append(&mut self, other: Fill) {
+        self.outputs.extend(other.outputs);
+        self.referrals.extend(other.referrals);
+        self.tips += other.tips;
+        self.tips_sudt += other.tips_sudt;
+        self.top_up += other.top_up;
+    }
+}
+
+impl<'a> BatchBuilder<'a> {
+    pub fn new(deployment: &'a Deployment, pool: &'a Pool) -> Self {
+        BatchBuilder {
+            deployment,
+            pool,
+            matchers: Vec::new(),
+            swaps: Vec::new(),
+            liquidity: Vec::new(),
+            cell_deps: Vec::new(),
+            fee: 0,
+            settlement: Settlement::Sequential,
+            operations: Vec::new(),
+        }
+    }
+
+    pub fn matcher(mut self, cell: LiveCell) -> Self {
+        self.matchers.push(cell);
+        self
+    }
+
+    pub fn swap(mut self, request: SwapRequest) -> Self {
+        self.swaps.push(request);
+        self
+    }
+
+    pub fn liquidity(mut self, request: LiquidityRequest) -> Self {
+        self.liquidity.push(request);
+        self
+    }
+
+    /// Cell deps of the matcher cell locks.
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Settles all the swaps together, at a single price or matched at the pool mid price.
+    /// Requests with a referrer are not supported.
+    pub fn settlement(mut self, settlement: Settlement) -> Self {
+        self.settlement = settlement;
+        self
+    }
+
+    /// Interleaves the swaps and the liquidity requests, each operation filling the next request
+    /// of its group in the canonical order. By default all the swaps are filled first.
+    pub fn operations(mut self, operations: Vec<Operation>) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    pub fn build(mut self) -> Result<Batch, Error> {
+        if self.matchers.is_empty() {
+            return Err(Error::MissingMatcherCell);
+        }
+
+        let info_out_point = self.pool.info.out_point.clone();
+        self.swaps
+            .sort_by_key(|request| request_order(&info_out_point, &request.cell));
+        self.liquidity
+            .sort_by_key(|request| request_order(&info_out_point, &request.cell));
+
+        let pool = self.pool;
+        let mut state = pool.state();
+        let mut fill = Fill::default();
+
+        if state.total_liquidity == 0 {
+            if !self.swaps.is_empty() || self.liquidity.len() != 1 {
+                return Err(Error::InvalidInitialMint);
+            }
+
+            fill_initial_mint(pool, &self.liquidity[0], &mut state, &mut fill)?;
+        } else {
+            let liquidity_type_hash = pool.info_data.liquidity_sudt_type_hash;
+            let has_add = self
+                .liquidity
+                .iter()
+                .any(|request| request.cell.type_hash() != Some(liquidity_type_hash));
+            if pool.info_data.paused && (!self.swaps.is_empty() || has_add) {
+                return Err(Error::PoolPaused);
+            }
+
+            // Liquidity outputs follow all the swap outputs whatever the operations
+            let mut liquidity_fill = Fill::default();
+            if self.settlement == Settlement::Sequential {
+                let operations = self.operations()?;
+                let mut swaps = self.swaps.iter();
+                let mut liquidity = self.liquidity.iter();
+
+                for operation in operations {
+                    match operation {
+                        Operation::Swap => {
+                            let request = swaps.next().ok_or(Error::InvalidOperations)?;
+                            fill_swap(pool, request, &mut state, &mut fill)?;
+                        }
+                        Operation::Liquidity => {
+                            let request = liquidity.next().ok_or(Error::InvalidOperations)?;
+                            fill_liquidity(pool, request, &mut state, &mut liquidity_fill)?;
+                        }
+                    }
+                }
+            } else {
+                if !self.operations.is_empty() {
+                    return Err(Error::InvalidOperations);
+                }
+
+                fill_settled_swaps(pool, &self.swaps, self.settlement, &mut state, &mut fill)?;
+                for request in self.liquidity.iter() {
+                    fill_liquidity(pool, request, &mut state, &mut liquidity_fill)?;
+                }
+            }
+            fill.append(liquidity_fill);
+        }
+
+        let mut info_data = pool.info_data.clone();
+        info_data.ckb_reserve = state.ckb_reserve;
+        info_data.sudt_reserve = state.sudt_reserve;
+        info_data.total_liquidity = state.total_liquidity;
+
+        let pool_capacity = u64::try_from(POOL_CAPACITY as u128 + state.ckb_reserve)
+            .map_err(|_| Error::Overflow)?;
+        let mut outputs = vec![
+            (
+                pool.info
+                    .output
+                    .clone()
+                    .as_builder()
+                    .capacity(INFO_CAPACITY.pack())
+                    .build(),
+                encode_info_data(&info_data, pool.info.data.len())?,
+            ),
+            (
+                pool.pool
+                    .output
+                    .clone()
+                    .as_builder()
+                    .capacity(pool_capacity.pack())
+                    .build(),
+                encode_sudt_amount(state.sudt_reserve),
+            ),
+        ];
+
+        // The matchers keep what the other cells lose, less the fee and the referral cells
+        let matcher_delta = fill.tips as i128 + pool.info.capacity() as i128
+            - INFO_CAPACITY as i128
+            - fill.top_up as i128
+            - self.fee as i128;
+        outputs.extend(self.matcher_outputs(matcher_delta, fill.tips_sudt)?);
+        outputs.extend(fill.outputs);
+        outputs.extend(fill.referrals);
+
+        let inputs = vec![&pool.info, &pool.pool]
+            .into_iter()
+            .chain(self.matchers.iter())
+            .chain(self.swaps.iter().map(|request| &request.cell))
+            .chain(self.liquidity.iter().map(|request| &request.cell))
+            .collect::<Vec<_>>();
+
+        let mut cell_deps = vec![
+            self.deployment.info_type.cell_dep.clone(),
+            self.deployment.info_lock.cell_dep.clone(),
+            self.deployment.sudt.cell_dep.clone(),
+        ];
+        if !self.swaps.is_empty() {
+            cell_deps.push(self.deployment.swap_lock.cell_dep.clone());
+        }
+        if !self.liquidity.is_empty() {
+            cell_deps.push(self.deployment.liquidity_lock.cell_dep.clone());
+        }
+        cell_deps.extend(self.cell_deps.iter().cloned());
+
+        let witness = batch_witness(
+            self.swaps.len(),
+            self.matchers.len(),
+            self.settlement,
+            &self.operations,
+        );
+        let tx = build_tx(inputs, outputs, cell_deps, vec![witness]);
+
+        Ok(Batch { tx, state })
+    }
+
+    /// The operations of a sequential batch, which must fill each request once.
+    fn operations(&self) -> Result<Vec<Operation>, Error> {
+        if self.operations.is_empty() {
+            let swaps = vec![Operation::Swap; self.swaps.len()];
+            let liquidity = vec![Operation::Liquidity; self.liquidity.len()];
+            return Ok([swaps, liquidity].concat());
+        }
+
+        if self.operations.len() != self.swaps.len() + self.liquidity.len() {
+            return Err(Error::InvalidOperations);
+        }
+
+        Ok(self.operations.clone())
+    }
+
+    fn matcher_outputs(
+        &self,
+        capacity_delta: i128,
+        tips_sudt: u128,
+    ) -> Result<Vec<(CellOutput, Bytes)>, Error> {
+        let mut outputs = self
+            .matchers
+            .iter()
+            .map(|cell| (cell.output.clone(), cell.data.clone()))
+            .collect::<Vec<_>>();
+
+        let (output, data) = &mut outputs[0];
+        let capacity = u64::try_from(output.capacity().unpack() as i128 + capacity_delta)
+            .map_err(|_| Error::InsufficientCapacity)?;
+        if capacity < occupied_capacity(output, data.len())? {
+            return Err(Error::InsufficientCapacity);
+        }
+        *output = output
+            .clone()
+            .as_builder()
+            .capacity(capacity.pack())
+            .build();
+
+        if tips_sudt != 0 {
+            let sudt_type_hash = self.pool.sudt_type_hash();
+            let index = self
+                .matchers
+                .iter()
+                .position(|cell| cell.type_hash() == Some(sudt_type_hash) && cell.data.len() >= 16)
+                .ok_or(Error::MissingMatcherSUDTCell)?;
+            let data = &mut outputs[index].1;
+            let amount = decode_sudt_amount(data)?
+                .checked_add(tips_sudt)
+                .ok_or(Error::Overflow)?;
+            let mut new_data = amount.to_le_bytes().to_vec();
+            new_data.extend_from_slice(&data[16..]);
+            *data = Bytes::from(new_data);
+        }
+
+        Ok(outputs)
+    }
+}
+
+/// The info type script requires the swaps and the liquidity requests each sorted by this key.
+pub fn request_order(info_out_point: &OutPoint, cell: &LiveCell) -> [u8; 32] {
+    request_order_key(info_out_point.as_slice(), cell.out_point.as_slice())
+}
+
+/// Fills `request` alone against `state`, returning the CKB and sUDT tips it pays. This is how
+/// the matcher engine tries a request before adding it to a batch.
+pub(crate) fn fill_request(
+    pool: &Pool,
+    request: &PendingRequest,
+    state: &mut PoolState,
+) -> Result<(u128, u128), Error> {
+    let mut fill = Fill::default();
+    let liquidity_type_hash = pool.info_data.liquidity_sudt_type_hash;
+
+    match request {
+        PendingRequest::Liquidity(request) if state.total_liquidity == 0 => {
+            fill_initial_mint(pool, request, state, &mut fill)?
+        }
+        _ if state.total_liquidity == 0 => return Err(Error::InvalidInitialMint),
+        PendingRequest::Liquidity(request)
+            if request.cell.type_hash() == Some(liquidity_type_hash) =>
+        {
+            fill_remove_liquidity(pool, request, state, &mut fill)?
+        }
+        _ if pool.info_data.paused => return Err(Error::PoolPaused),
+        PendingRequest::Swap(request) => fill_swap(pool, request, state, &mut fill)?,
+        PendingRequest::Liquidity(request) => fill_add_liquidity(pool, request, state, &mut fill)?,
+    }
+
+    Ok((fill.tips, fill.tips_sudt))
+}
+
+fn fill_liquidity(
+    pool: &Pool,
+    request: &LiquidityRequest,
+    state: &mut PoolState,
+    fill: &mut Fill,
+) -> Result<(), Error> {
+    if request.cell.type_hash() == Some(pool.info_data.liquidity_sudt_type_hash) {
+        fill_remove_liquidity(pool, request, state, fill)
+    } else {
+        fill_add_liquidity(pool, request, state, fill)
+    }
+}
+
+fn fill_swap(
+    pool: &Pool,
+    request: &SwapRequest,
+    state: &mut PoolState,
+    fill: &mut Fill,
+) -> Result<(), Error> {
+    let (asset_in, amount_in) = swap_amount_in(pool, request)?;
+    let args = &request.args;
+    let referral_bps = args.referral.as_ref().map_or(0, |referral| referral.bps);
+    let quote =
+        quote::swap(state, asset_in, amount_in, referral_bps).ok_or(Error::InsufficientAmount)?;
+
+    push_swap_output(pool, request, quote.amount_out, fill)?;
+    *state = quote.state;
+
+    if quote.referral == 0 {
+        return Ok(());
+    }
+
+    let referrer_lock = request
+        .referrer_lock
+        .clone()
+        .ok_or(Error::MissingReferrerLock)?;
+
+    // A CKB referral cell is topped up to its occupied capacity, a sUDT one is fully paid
+    // by the matcher
+    if request.is_ckb_in() {
+        let amount = u64::try_from(quote.referral).map_err(|_| Error::Overflow)?;
+        let output = output_with_min_capacity(referrer_lock, None, amount, 0)?;
+        let capacity: u64 = output.capacity().unpack();
+
+        fill.top_up += capacity - amount;
+        fill.referrals.push((output, Bytes::new()));
+    } else {
+        let output =
+            output_with_min_capacity(referrer_lock, Some(pool.sudt_type()), SUDT_CAPACITY, 16)?;
+        let capacity: u64 = output.capacity().unpack();
+
+        fill.top_up += capacity;
+        fill.referrals
+            .push((output, encode_sudt_amount(quote.referral)));
+    }
+
+    Ok(())
+}
+
+/// Fills all the swaps together, only the part they don't offset trades against the pool.
+fn fill_settled_swaps(
+    pool: &Pool,
+    requests: &[SwapRequest],
+    settlement: Settlement,
+    state: &mut PoolState,
+    fill: &mut Fill,
+) -> Result<(), Error> {
+    let mut swaps = Vec::with_capacity(requests.len());
+    for request in requests {
+        if request.args.referral.is_some() {
+            return Err(Error::ReferralNotSupported);
+        }
+
+        swaps.push(swap_amount_in(pool, request)?);
+    }
+
+    let settled = if settlement == Settlement::MidPrice {
+        quote::mid_price_swaps(state, &swaps)
+    } else {
+        quote::uniform_swaps(state, &swaps)
+    };
+    let (amounts_out, after) = settled.ok_or(Error::InsufficientAmount)?;
+    for (request, amount_out) in requests.iter().zip(amounts_out) {
+        push_swap_output(pool, request, amount_out, fill)?;
+    }
+    *state = after;
+
+    Ok(())
+}
+
+/// The asset and the amount swapped by a request, tips excluded.
+fn swap_amount_in(pool: &Pool, request: &SwapRequest) -> Result<(Asset, u128), Error> {
+    let args = &request.args;
+    if args.sudt_type_hash != pool.sudt_type_hash()
+        || args
+            .info_type_hash
+            .map_or(false, |hash| hash != pool.info_type_hash())
+    {
+        return Err(Error::RequestNotForPool);
+    }
+
+    if request.is_ckb_in() {
+        let ckb_sent = (request.cell.capacity() as u128)
+            .checked_sub(SUDT_CAPACITY as u128)
+            .filter(|amount| *amount != 0)
+            .ok_or(Error::ZeroAmount)?;
+        let ckb_in = ckb_sent
+            .checked_sub(args.tips as u128)
+            .ok_or(Error::InvalidTips)?;
+
+        Ok((Asset::Ckb, ckb_in))
+    } else {
+        if request.cell.type_hash() != Some(pool.sudt_type_hash()) {
+            return Err(Error::RequestNotForPool);
+        }
+
+        let sudt_sent = decode_sudt_amount(&request.cell.data)?;
+        if sudt_sent == 0 {
+            return Err(Error::ZeroAmount);
+        }
+
+        let sudt_in = sudt_sent
+            .checked_sub(args.tips_sudt)
+            .ok_or(Error::InvalidTips)?;
+
+        Ok((Asset::Sudt, sudt_in))
+    }
+}
+
+/// Pays `amount_out` to the user of a request, less the tips in the output asset.
+fn push_swap_output(
+    pool: &Pool,
+    request: &SwapRequest,
+    amount_out: u128,
+    fill: &mut Fill,
+) -> Result<(), Error> {
+    let args = &request.args;
+    let tips = args.tips as u128;
+    let tips_sudt = args.tips_sudt;
+
+    if request.is_ckb_in() {
+        let sudt_got = amount_out
+            .checked_sub(tips_sudt)
+            .ok_or(Error::InvalidTips)?;
+        if sudt_got < args.min_amount_out {
+            return Err(Error::AmountLessThanMin);
+        }
+
+        let output = output_with_capacity(
+            request.user_lock.clone(),
+            Some(pool.sudt_type()),
+            SUDT_CAPACITY,
+            16,
+        )?;
+        fill.outputs.push((output, encode_sudt_amount(sudt_got)));
+    } else {
+        let ckb_got = amount_out.checked_sub(tips).ok_or(Error::InvalidTips)?;
+        if ckb_got < args.min_amount_out {
+            return Err(Error::AmountLessThanMin);
+        }
+
+        let capacity = u64::try_from(request.cell.capacity() as u128 + ckb_got)
+            .map_err(|_| Error::Overflow)?;
+        let output = output_with_capacity(request.user_lock.clone(), None, capacity, 0)?;
+        fill.outputs.push((output, Bytes::new()));
+    }
+
+    fill.tips += tips;
+    fill.tips_sudt += tips_sudt;
+
+    Ok(())
+}
+
+fn verify_liquidity_request(pool: &Pool, request: &LiquidityRequest) -> Result<(), Error> {
+    if request.args.info_type_hash != pool.info_type_hash() {
+        return Err(Error::RequestNotForPool);
+    }
+
+    if request.args.version != INFO_VERSION {
+        return Err(Error::InvalidRequestVersion);
+    }
+
+    Ok(())
+}
+
+fn fill_initial_mint(
+    pool: &Pool,
+    request: &LiquidityRequest,
+    state: &mut PoolState,
+    fill: &mut Fill,
+) -> Result<(), Error> {
+    verify_liquidity_request(pool, request)?;
+    if request.cell.type_hash() != Some(pool.sudt_type_hash()) {
+        return Err(Error::InvalidInitialMint);
+    }
+
+    let args = &request.args;
+    let sudt_injected = request
+        .amount()
+        .checked_sub(args.tips_sudt)
+        .ok_or(Error::InvalidTips)?;
+    let ckb_injected = (request.cell.capacity() as u128)
+        .checked_sub(SUDT_CAPACITY as u128)
+        .ok_or(Error::InsufficientCapacity)?
+        .checked_sub(args.tips as u128)
+        .ok_or(Error::InvalidTips)?;
+
+    let quote = quote::initial_mint(state, ckb_injected, sudt_injected).ok_or(Error::ZeroAmount)?;
+
+    let output = output_with_capacity(
+        request.user_lock.clone(),
+        Some(pool.liquidity_sudt_type()?),
+        SUDT_CAPACITY,
+        16,
+    )?;
+    fill.outputs
+        .push((output, encode_sudt_amount(quote.liquidity)));
+    fill.tips += args.tips as u128;
+    fill.tips_sudt += args.tips_sudt;
+    *state = quote.state;
+
+    Ok(())
+}
+
+/// All the sUDT is injected when the rest of the CKB makes a valid change cell, otherwise all
+/// the CKB but the capacity of the liquidity and sUDT change cells is injected.
+fn fill_add_liquidity(
+    pool: &Pool,
+    request: &LiquidityRequest,
+    state: &mut PoolState,
+    fill: &mut Fill,
+) -> Result<(), Error> {
+    verify_liquidity_request(pool, request)?;
+    if request.cell.type_hash() != Some(pool.sudt_type_hash()) {
+        return Err(Error::RequestNotForPool);
+    }
+
+    let args = &request.args;
+    let capacity = request.cell.capacity() as u128;
+    let sudt_available = request
+        .amount()
+        .checked_sub(args.tips_sudt)
+        .ok_or(Error::InvalidTips)?;
+    if sudt_available == 0 {
+        return Err(Error::ZeroAmount);
+    }
+
+    let ckb_change = quote::mint_with_sudt(state, sudt_available).and_then(|quote| {
+        let change =
+            capacity.checked_sub(SUDT_CAPACITY as u128 + args.tips as u128 + quote.ckb_injected)?;
+        let change = u64::try_from(change).ok()?;
+        output_with_capacity(request.user_lock.clone(), None, change, 0)
+            .ok()
+            .map(|output| (quote, output))
+    });
+
+    let (quote, change) = match ckb_change {
+        Some((quote, change_output)) => {
+            if args.amount_0 == 0
+                || quote.ckb_injected < args.amount_0 as u128
+                || sudt_available < args.amount_1
+            {
+                return Err(Error::AmountLessThanMin);
+            }
+
+            (quote, (change_output, Bytes::new()))
+        }
+        None => {
+            let ckb_injected = capacity
+                .checked_sub(SUDT_CAPACITY as u128 * 2 + args.tips as u128)
+                .filter(|amount| *amount != 0)
+                .ok_or(Error::InsufficientCapacity)?;
+            let quote =
+                quote::mint_with_ckb(state, ckb_injected).ok_or(Error::InsufficientAmount)?;
+            let sudt_change = sudt_available
+                .checked_sub(quote.sudt_injected)
+                .ok_or(Error::InsufficientAmount)?;
+
+            if args.amount_1 == 0
+                || quote.sudt_injected < args.amount_1
+                || ckb_injected < args.amount_0 as u128
+            {
+                return Err(Error::AmountLessThanMin);
+            }
+
+            let change_output = output_with_capacity(
+                request.user_lock.clone(),
+                Some(pool.sudt_type()),
+                SUDT_CAPACITY,
+                16,
+            )?;
+            (quote, (change_output, encode_sudt_amount(sudt_change)))
+        }
+    };
+
+    let output = output_with_capacity(
+        request.user_lock.clone(),
+        Some(pool.liquidity_sudt_type()?),
+        SUDT_CAPACITY,
+        16,
+    )?;
+    fill.outputs
+        .push((output, encode_sudt_amount(quote.liquidity)));
+    fill.outputs.push(change);
+    fill.tips += args.tips as u128;
+    fill.tips_sudt += args.tips_sudt;
+    *state = quote.state;
+
+    Ok(())
+}
+
+fn fill_remove_liquidity(
+    pool: &Pool,
+    request: &LiquidityRequest,
+    state: &mut PoolState,
+    fill: &mut Fill,
+) -> Result<(), Error> {
+    verify_liquidity_request(pool, request)?;
+
+    let args = &request.args;
+    let burned = request.amount();
+    if burned == 0 {
+        return Err(Error::ZeroAmount);
+    }
+
+    let quote = quote::burn(state, burned).ok_or(Error::InsufficientAmount)?;
+    let ckb_got = quote
+        .ckb_removed
+        .checked_sub(args.tips as u128)
+        .ok_or(Error::InvalidTips)?;
+    let sudt_got = quote
+        .sudt_removed
+        .checked_sub(args.tips_sudt)
+        .ok_or(Error::InvalidTips)?;
+
+    if ckb_got < args.amount_0 as u128 || sudt_got < args.amount_1 {
+        return Err(Error::AmountLessThanMin);
+    }
+
+    let sudt_output = output_with_capacity(
+        request.user_lock.clone(),
+        Some(pool.sudt_type()),
+        SUDT_CAPACITY,
+        16,
+    )?;
+    let ckb_capacity = (request.cell.capacity() as u128 + ckb_got)
+        .checked_sub(SUDT_CAPACITY as u128)
+        .and_then(|capacity| u64::try_from(capacity).ok())
+        .ok_or(Error::InsufficientCapacity)?;
+    let ckb_output = output_with_capacity(request.user_lock.clone(), None, ckb_capacity, 0)?;
+
+    fill.outputs
+        .push((sudt_output, encode_sudt_amount(sudt_got)));
+    fill.outputs.push((ckb_output, Bytes::new()));
+    fill.tips += args.tips as u128;
+    fill.tips_sudt += args.tips_sudt;
+    *state = quote.state;
+
+    Ok(())
+}
+
+/// The swap count, followed by the matcher count unless there is a single matcher cell, and by
+/// the settlement and the operations unless the swaps are all filled sequentially first.
+pub fn batch_witness(
+    swap_count: usize,
+    matcher_count: usize,
+    settlement: Settlement,
+    operations: &[Operation],
+) -> Bytes {
+    let extended = settlement != Settlement::Sequential || !operations.is_empty();
+    let mut input_type = (swap_count as u64).to_le_bytes().to_vec();
+    if matcher_count != 1 || extended {
+        input_type.extend_from_slice(&(matcher_count as u64).to_le_bytes());
+    }
+    if extended {
+        let settlement: u64 = match settlement {
+            Settlement::Sequential => 0,
+            Settlement::UniformPrice => 1,
+            Settlement::MidPrice => 2,
+        };
+        input_type.extend_from_slice(&settlement.to_le_bytes());
+    }
+    input_type.extend(operations.iter().map(|operation| *operation as u8));
+
+    WitnessArgs::new_builder()
+        .input_type(Some(Bytes::from(input_type)).pack())
+        .build()
+        .as_bytes()
+}
+
+fn registry_data(key: [u8; 32], next_key: [u8; 32], info_type_code_hash: [u8; 32]) -> Bytes {
+    let mut data = key.to_vec();
+    data.extend_from_slice(&next_key);
+    data.extend_from_slice(&info_type_code_hash);
+    Bytes::from(data)
+}
+
+/// Inputs without a given witness get an empty one, to be filled by their signers.
+fn build_tx(
+    inputs: Vec<&LiveCell>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    cell_deps: Vec<CellDep>,
+    mut witnesses: Vec<Bytes>,
+) -> TransactionView {
+    witnesses.resize(inputs.len(), Bytes::new());
+
+    let (outputs, outputs_data): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+    let inputs = inputs.into_iter().map(|cell| {
+        CellInput::new_builder()
+            .previous_output(cell.out_point.clone())
+            .build()
+    });
+
+    TransactionBuilder::default()
+        .inputs(inputs)
+        .outputs(outputs)
+        .outputs_data(outputs_data.pack())
+        .cell_deps(cell_deps)
+        .witnesses(witnesses.pack())
+        .build()
+}
@@ -0,0 +1,181 @@
+use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::core::{Capacity, ScriptHashType};
+use ckb_tool::ckb_types::packed::{Byte32, CellDep, CellOutput, OutPoint, Script};
+use ckb_tool::ckb_types::prelude::*;
+use share::cell::InfoCellData;
+
+use crate::error::Error;
+
+const INFO_CELL_DATA_LEN: usize = 80;
+const EXTENDED_INFO_CELL_DATA_LEN: usize = 145;
+const GOVERNED_INFO_CELL_DATA_LEN: usize = 217;
+
+/// A live cell with its content, as returned by an indexer.
+#[derive(Debug, Clone)]
+pub struct LiveCell {
+    pub out_point: OutPoint,
+    pub output:    CellOutput,
+    pub data:      Bytes,
+}
+
+impl LiveCell {
+    pub fn new(out_point: OutPoint, output: CellOutput, data: Bytes) -> Self {
+        LiveCell {
+            out_point,
+            output,
+            data,
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.output.capacity().unpack()
+    }
+
+    pub fn lock_hash(&self) -> [u8; 32] {
+        self.output.lock().calc_script_hash().unpack()
+    }
+
+    pub fn type_hash(&self) -> Option<[u8; 32]> {
+        self.output
+            .type_()
+            .to_opt()
+            .map(|script| script.calc_script_hash().unpack())
+    }
+}
+
+/// A deployed script, referenced by the scripts built from it.
+#[derive(Debug, Clone)]
+pub struct ScriptRef {
+    pub code_hash: Byte32,
+    pub hash_type: ScriptHashType,
+    pub cell_dep:  CellDep,
+}
+
+impl ScriptRef {
+    pub fn script(&self, args: Bytes) -> Script {
+        Script::new_builder()
+            .code_hash(self.code_hash.clone())
+            .hash_type(self.hash_type.into())
+            .args(args.pack())
+            .build()
+    }
+}
+
+/// Where the DEX scripts are deployed. The registry is identified by the type id of its head
+/// cell, which the info type script is built with.
+#[derive(Debug, Clone)]
+pub struct Deployment {
+    pub info_type:          ScriptRef,
+    pub info_lock:          ScriptRef,
+    pub swap_lock:          ScriptRef,
+    pub liquidity_lock:     ScriptRef,
+    pub registry_type:      ScriptRef,
+    pub sudt:               ScriptRef,
+    pub registry_type_args: Bytes,
+}
+
+impl Deployment {
+    pub fn registry_type(&self) -> Script {
+        self.registry_type.script(self.registry_type_args.clone())
+    }
+}
+
+pub fn encode_sudt_amount(amount: u128) -> Bytes {
+    Bytes::from(amount.to_le_bytes().to_vec())
+}
+
+/// Decodes the amount of a sUDT cell, which may be followed by extra data.
+pub fn decode_sudt_amount(data: &[u8]) -> Result<u128, Error> {
+    if data.len() < 16 {
+        return Err(Error::Encoding);
+    }
+
+    Ok(share::decode_u128(&data[0..16])?)
+}
+
+/// Encodes the info cell data into `len` bytes, one of the lengths accepted by
+/// `InfoCellData::from_raw`, so an updated info cell keeps the layout of its input.
+pub fn encode_info_data(data: &InfoCellData, len: usize) -> Result<Bytes, Error> {
+    if len != INFO_CELL_DATA_LEN
+        && len != EXTENDED_INFO_CELL_DATA_LEN
+        && len != GOVERNED_INFO_CELL_DATA_LEN
+    {
+        return Err(Error::Encoding);
+    }
+
+    if (len == INFO_CELL_DATA_LEN
+        && (data.creator_lock_hash.is_some() || data.admin_lock_hash.is_some() || data.paused))
+        || (len != GOVERNED_INFO_CELL_DATA_LEN
+            && (data.pending_admin_lock_hash.is_some()
+                || data.activation_timestamp != 0
+                || data.migration_code_hash.is_some()))
+    {
+        return Err(Error::Encoding);
+    }
+
+    let mut buf = Vec::with_capacity(len);
+    buf.extend_from_slice(&data.ckb_reserve.to_le_bytes());
+    buf.extend_from_slice(&data.sudt_reserve.to_le_bytes());
+    buf.extend_from_slice(&data.total_liquidity.to_le_bytes());
+    buf.extend_from_slice(&data.liquidity_sudt_type_hash);
+
+    if len > INFO_CELL_DATA_LEN {
+        buf.extend_from_slice(&data.creator_lock_hash.unwrap_or_default());
+        buf.extend_from_slice(&data.admin_lock_hash.unwrap_or_default());
+        buf.push(data.paused as u8);
+    }
+
+    if len == GOVERNED_INFO_CELL_DATA_LEN {
+        buf.extend_from_slice(&data.pending_admin_lock_hash.unwrap_or_default());
+        buf.extend_from_slice(&data.activation_timestamp.to_le_bytes());
+        buf.extend_from_slice(&data.migration_code_hash.unwrap_or_default());
+    }
+
+    Ok(Bytes::from(buf))
+}
+
+/// The minimal capacity of a cell holding `data_len` bytes of data.
+pub fn occupied_capacity(output: &CellOutput, data_len: usize) -> Result<u64, Error> {
+    let data_capacity = Capacity::bytes(data_len).map_err(|_| Error::Overflow)?;
+    let capacity = output
+        .occupied_capacity(data_capacity)
+        .map_err(|_| Error::Overflow)?;
+
+    Ok(capacity.as_u64())
+}
+
+/// Builds an output holding `capacity`, or the occupied capacity if larger.
+pub fn output_with_min_capacity(
+    lock: Script,
+    type_: Option<Script>,
+    capacity: u64,
+    data_len: usize,
+) -> Result<CellOutput, Error> {
+    let output = CellOutput::new_builder()
+        .lock(lock)
+        .type_(type_.pack())
+        .build();
+    let capacity = capacity.max(occupied_capacity(&output, data_len)?);
+
+    Ok(output.as_builder().capacity(capacity.pack()).build())
+}
+
+/// Builds an output holding exactly `capacity`, which must cover its occupied capacity.
+pub fn output_with_capacity(
+    lock: Script,
+    type_: Option<Script>,
+    capacity: u64,
+    data_len: usize,
+) -> Result<CellOutput, Error> {
+    let output = CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(lock)
+        .type_(type_.pack())
+        .build();
+
+    if capacity < occupied_capacity(&output, data_len)? {
+        return Err(Error::InsufficientCapacity);
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+
+use share::ckb_std::error::SysError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Cell data or lock args can not be decoded.
+    Encoding,
+    /// The info and pool cells do not describe the same pool state.
+    InvalidPoolCells,
+    LiquiditySUDTTypeMismatch,
+    /// The lock script provided for a request does not match its user lock hash.
+    UserLockMismatch,
+    ReferrerLockMismatch,
+    MissingReferrerLock,
+    /// Swaps with a referrer can only be settled sequentially.
+    ReferralNotSupported,
+    /// The operations don't fill each request once, or the swaps are not settled sequentially.
+    InvalidOperations,
+    /// The request is bound to another pool or trades another sUDT.
+    RequestNotForPool,
+    InvalidRequestVersion,
+    InvalidTips,
+    AmountLessThanMin,
+    ZeroAmount,
+    InsufficientAmount,
+    InsufficientCapacity,
+    Overflow,
+    PoolPaused,
+    /// The pool has no liquidity yet, only a single add liquidity request can be filled.
+    InvalidInitialMint,
+    MissingMatcherCell,
+    MissingMatcherSUDTCell,
+    MissingRegistryCell,
+    PoolAlreadyRegistered,
+    /// None of the pending requests of a batch can be filled.
+    NoFillableRequest,
+    CycleBudgetExceeded,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<SysError> for Error {
+    fn from(_: SysError) -> Self {
+        Error::Encoding
+    }
+}
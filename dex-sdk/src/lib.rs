@@ -0,0 +1,32 @@
+//! Builds the transactions of the DEX for clients and matchers: pool creation, request cells
+//! and the batches filling them. The layouts and amounts follow what the contracts verify, so a
+//! built transaction only needs to be signed by the owners of the funding and matcher cells.
+
+pub mod builder;
+pub mod cell;
+pub mod error;
+pub mod matcher;
+pub mod math;
+pub mod pool;
+pub mod quote;
+pub mod request;
+
+pub use builder::{Batch, BatchBuilder, CreatePool};
+pub use cell::{Deployment, LiveCell, ScriptRef};
+pub use error::Error;
+pub use matcher::{MatchPlan, Matcher, PendingRequest};
+pub use pool::{Pool, PoolState};
+pub use request::{LiquidityRequest, SwapRequest};
+pub use share::{Operation, Settlement};
+
+pub const ONE: u128 = 1;
+pub const THOUSAND: u128 = 1_000;
+pub const FEE_RATE: u128 = 997;
+pub const BPS_BASE: u128 = 10_000;
+pub const POOL_CAPACITY: u64 = 18_600_000_000;
+pub const SUDT_CAPACITY: u64 = 14_200_000_000;
+pub const INFO_CAPACITY: u64 = 25_000_000_000;
+pub const INFO_VERSION: u8 = 1;
+pub const INFO_INDEX: usize = 0;
+pub const POOL_INDEX: usize = 1;
+pub const MATCHER_INDEX: usize = 2;
@@ -0,0 +1,310 @@
+//! Chooses the pending requests a matcher fills. The info type script fills all the swaps and
+//! then all the liquidity requests, each group in a canonical order, so a request can only be
+//! filled along with the ones sorted before it. Requests are added greedily, the one that moves
+//! the price the least or the one paying the most tips first, until none can be filled or the
+//! cycle budget is spent.
+
+use ckb_tool::ckb_types::packed::CellDep;
+
+use crate::builder::{fill_request, request_order, Batch, BatchBuilder};
+use crate::cell::{Deployment, LiveCell};
+use crate::error::Error;
+use crate::pool::{Pool, PoolState};
+use crate::request::{LiquidityRequest, SwapRequest};
+
+#[derive(Debug, Clone)]
+pub enum PendingRequest {
+    Swap(SwapRequest),
+    Liquidity(LiquidityRequest),
+}
+
+impl PendingRequest {
+    pub fn cell(&self) -> &LiveCell {
+        match self {
+            PendingRequest::Swap(request) => &request.cell,
+            PendingRequest::Liquidity(request) => &request.cell,
+        }
+    }
+
+    pub fn is_swap(&self) -> bool {
+        matches!(self, PendingRequest::Swap(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    FilledRequests,
+    /// The CKB tips plus the sUDT tips valued at the pool price.
+    Tips,
+}
+
+/// The estimated cycles of a batch, for the info type script and each request lock. The
+/// defaults are rough upper bounds, a matcher should calibrate them against its deployment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleModel {
+    pub base:          u64,
+    pub per_swap:      u64,
+    pub per_liquidity: u64,
+}
+
+impl Default for CycleModel {
+    fn default() -> Self {
+        CycleModel {
+            base:          5_000_000,
+            per_swap:      2_000_000,
+            per_liquidity: 3_000_000,
+        }
+    }
+}
+
+impl CycleModel {
+    pub fn cycles(&self, swaps: usize, liquidity: usize) -> u64 {
+        self.base
+            .saturating_add(self.per_swap.saturating_mul(swaps as u64))
+            .saturating_add(self.per_liquidity.saturating_mul(liquidity as u64))
+    }
+}
+
+pub struct MatchPlan {
+    pub batch:     Batch,
+    /// The filled requests in the order of the batch inputs.
+    pub filled:    Vec<PendingRequest>,
+    /// The requests left out, with the reason of their last failed fill.
+    pub dropped:   Vec<(PendingRequest, Error)>,
+    pub tips:      u128,
+    pub tips_sudt: u128,
+}
+
+#[derive(Default)]
+struct Selection {
+    filled:    Vec<PendingRequest>,
+    dropped:   Vec<(PendingRequest, Error)>,
+    swaps:     usize,
+    liquidity: usize,
+    tips:      u128,
+    tips_sudt: u128,
+}
+
+/// Plans and builds a batch out of pending requests, the matcher cells are used as in
+/// `BatchBuilder`.
+pub struct Matcher<'a> {
+    deployment:   &'a Deployment,
+    pool:         &'a Pool,
+    matchers:     Vec<LiveCell>,
+    cell_deps:    Vec<CellDep>,
+    fee:          u64,
+    objective:    Objective,
+    cycle_model:  CycleModel,
+    cycle_budget: u64,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(deployment: &'a Deployment, pool: &'a Pool) -> Self {
+        Matcher {
+            deployment,
+            pool,
+            matchers: Vec::new(),
+            cell_deps: Vec::new(),
+            fee: 0,
+            objective: Objective::FilledRequests,
+            cycle_model: CycleModel::default(),
+            cycle_budget: u64::max_value(),
+        }
+    }
+
+    pub fn matcher(mut self, cell: LiveCell) -> Self {
+        self.matchers.push(cell);
+        self
+    }
+
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    pub fn cycle_model(mut self, cycle_model: CycleModel) -> Self {
+        self.cycle_model = cycle_model;
+        self
+    }
+
+    pub fn cycle_budget(mut self, cycles: u64) -> Self {
+        self.cycle_budget = cycles;
+        self
+    }
+
+    pub fn plan(self, requests: Vec<PendingRequest>) -> Result<MatchPlan, Error> {
+        let mut state = self.pool.state();
+        let mut selection = Selection::default();
+        let (swaps, liquidity): (Vec<_>, Vec<_>) =
+            requests.into_iter().partition(PendingRequest::is_swap);
+
+        if state.total_liquidity == 0 {
+            selection.dropped.extend(
+                swaps
+                    .into_iter()
+                    .map(|request| (request, Error::InvalidInitialMint)),
+            );
+            self.select(liquidity, &mut state, &mut selection, 1);
+        } else {
+            let max = usize::max_value();
+            self.select(swaps, &mut state, &mut selection, max);
+            self.select(liquidity, &mut state, &mut selection, max);
+        }
+
+        if selection.filled.is_empty() {
+            return Err(Error::NoFillableRequest);
+        }
+
+        let mut builder = BatchBuilder::new(self.deployment, self.pool).fee(self.fee);
+        for cell in self.matchers {
+            builder = builder.matcher(cell);
+        }
+        for cell_dep in self.cell_deps {
+            builder = builder.cell_dep(cell_dep);
+        }
+        for request in selection.filled.iter() {
+            builder = match request {
+                PendingRequest::Swap(request) => builder.swap(request.clone()),
+                PendingRequest::Liquidity(request) => builder.liquidity(request.clone()),
+            };
+        }
+        let batch = builder.build()?;
+
+        Ok(MatchPlan {
+            batch,
+            filled: selection.filled,
+            dropped: selection.dropped,
+            tips: selection.tips,
+            tips_sudt: selection.tips_sudt,
+        })
+    }
+
+    /// Fills at most `limit` requests of `pending`, adding the best one at each step. The
+    /// requests are filled in the order required by the info type script, so each candidate is
+    /// tried along with the requests already chosen.
+    fn select(
+        &self,
+        mut pending: Vec<PendingRequest>,
+        state: &mut PoolState,
+        selection: &mut Selection,
+        limit: usize,
+    ) {
+        let info_out_point = self.pool.info.out_point.clone();
+        let start = *state;
+        let mut chosen: Vec<PendingRequest> = Vec::new();
+        let mut chosen_tips = (0, 0);
+
+        while chosen.len() < limit {
+            let mut best: Option<(usize, (f64, f64), PoolState, (u128, u128))> = None;
+            let mut errors = Vec::with_capacity(pending.len());
+
+            for (idx, request) in pending.iter().enumerate() {
+                let (swaps, liquidity) = if request.is_swap() {
+                    (selection.swaps + 1, selection.liquidity)
+                } else {
+                    (selection.swaps, selection.liquidity + 1)
+                };
+                if self.cycle_model.cycles(swaps, liquidity) > self.cycle_budget {
+                    errors.push(Error::CycleBudgetExceeded);
+                    continue;
+                }
+
+                let mut group = chosen.iter().chain(Some(request)).collect::<Vec<_>>();
+                group.sort_by_key(|request| request_order(&info_out_point, request.cell()));
+
+                match self.fill_group(&start, &group) {
+                    Ok((after, tips)) => {
+                        let score = self.score(&start, &after, tips);
+                        if best.map_or(true, |(_, best_score, ..)| score > best_score) {
+                            best = Some((idx, score, after, tips));
+                        }
+                        // Never reported, the pass fills a request
+                        errors.push(Error::NoFillableRequest);
+                    }
+                    Err(err) => errors.push(err),
+                }
+            }
+
+            let (idx, _, after, tips) = match best {
+                Some(best) => best,
+                None => {
+                    selection.dropped.extend(pending.drain(..).zip(errors));
+                    break;
+                }
+            };
+
+            let request = pending.remove(idx);
+            if request.is_swap() {
+                selection.swaps += 1;
+            } else {
+                selection.liquidity += 1;
+            }
+            chosen.push(request);
+            chosen_tips = tips;
+            *state = after;
+        }
+
+        chosen.sort_by_key(|request| request_order(&info_out_point, request.cell()));
+        selection.filled.extend(chosen);
+        selection.tips += chosen_tips.0;
+        selection.tips_sudt += chosen_tips.1;
+
+        // Only left over by the initial mint, which must be alone
+        selection.dropped.extend(
+            pending
+                .into_iter()
+                .map(|request| (request, Error::InvalidInitialMint)),
+        );
+    }
+
+    /// Fills `group` in order, returning the pool state and the CKB and sUDT tips.
+    fn fill_group(
+        &self,
+        state: &PoolState,
+        group: &[&PendingRequest],
+    ) -> Result<(PoolState, (u128, u128)), Error> {
+        let mut after = *state;
+        let mut tips = (0, 0);
+
+        for request in group {
+            let (ckb, sudt) = fill_request(self.pool, request, &mut after)?;
+            tips.0 += ckb;
+            tips.1 += sudt;
+        }
+
+        Ok((after, tips))
+    }
+
+    /// Higher is better, the price shift breaks the ties of the tips.
+    fn score(&self, before: &PoolState, after: &PoolState, tips: (u128, u128)) -> (f64, f64) {
+        let shift = match (spot_price(before), spot_price(after)) {
+            (Some(before), Some(after)) => (after / before - 1.0).abs(),
+            _ => 0.0,
+        };
+        let value = match self.objective {
+            Objective::FilledRequests => 0.0,
+            Objective::Tips => tips.0 as f64 + tips.1 as f64 * spot_price(after).unwrap_or(0.0),
+        };
+
+        (value, -shift)
+    }
+}
+
+/// The CKB price of one sUDT unit.
+fn spot_price(state: &PoolState) -> Option<f64> {
+    if state.ckb_reserve == 0 || state.sudt_reserve == 0 {
+        return None;
+    }
+
+    Some(state.ckb_reserve as f64 / state.sudt_reserve as f64)
+}
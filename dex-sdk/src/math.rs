@@ -0,0 +1,199 @@
+//! The amounts verified by the info type script. Every formula matches the contract to the
+//! shannon, including the rounding up by `ONE`, and returns `None` when it has no result.
+
+use std::convert::TryFrom;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::pool::PoolState;
+use crate::{BPS_BASE, FEE_RATE, ONE, THOUSAND};
+
+fn to_u128(value: BigUint) -> Option<u128> {
+    u128::try_from(value).ok()
+}
+
+/// The sUDT paid by the pool for `ckb_in` shannons. Tips are not swapped, the CKB tips are
+/// kept out of `ckb_in` and the sUDT tips are taken from the result.
+pub fn sudt_out(ckb_in: u128, state: &PoolState) -> Option<u128> {
+    let numerator = BigUint::from(ckb_in) * FEE_RATE * state.sudt_reserve;
+    let denominator =
+        BigUint::from(state.ckb_reserve) * THOUSAND + BigUint::from(ckb_in) * FEE_RATE;
+    if denominator.is_zero() {
+        return None;
+    }
+
+    to_u128(numerator / denominator + ONE)
+}
+
+/// The CKB paid by the pool for `sudt_in`, the counterpart of `sudt_out`.
+pub fn ckb_out(sudt_in: u128, state: &PoolState) -> Option<u128> {
+    let numerator = BigUint::from(sudt_in) * FEE_RATE * state.ckb_reserve;
+    let denominator =
+        BigUint::from(state.sudt_reserve) * THOUSAND + BigUint::from(sudt_in) * FEE_RATE;
+    if denominator.is_zero() {
+        return None;
+    }
+
+    to_u128(numerator / denominator + ONE)
+}
+
+/// The minimal CKB for which `sudt_out` pays at least `target` sUDT.
+pub fn ckb_in_for_sudt_out(target: u128, state: &PoolState) -> Option<u128> {
+    input_for_output(target, state.ckb_reserve, state.sudt_reserve)
+}
+
+/// The minimal sUDT for which `ckb_out` pays at least `target` shannons.
+pub fn sudt_in_for_ckb_out(target: u128, state: &PoolState) -> Option<u128> {
+    input_for_output(target, state.sudt_reserve, state.ckb_reserve)
+}
+
+// The output is `floor(x * 997 * r_out / (r_in * 1000 + x * 997)) + 1`, so it reaches `target`
+// once `x * 997 * (r_out - t) >= t * r_in * 1000` with `t = target - 1`. Solving for the
+// smallest such `x` needs `t < r_out`, the pool can't pay more than its reserve.
+fn input_for_output(target: u128, reserve_in: u128, reserve_out: u128) -> Option<u128> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return None;
+    }
+
+    let floor_target = target.saturating_sub(ONE);
+    if floor_target >= reserve_out {
+        return None;
+    }
+
+    let numerator = BigUint::from(floor_target) * reserve_in * THOUSAND;
+    let denominator = BigUint::from(reserve_out - floor_target) * FEE_RATE;
+    let amount_in = (numerator + &denominator - ONE) / denominator;
+
+    to_u128(amount_in).map(|amount| amount.max(ONE))
+}
+
+/// The CKB paid to the sellers and the sUDT paid to the buyers when `ckb_in` and `sudt_in` clear
+/// at a single price, only their imbalance trading on the curve. The pool trades the amount
+/// equalizing the prices of both sides, `None` means nothing to clear.
+pub fn uniform_clearing(ckb_in: u128, sudt_in: u128, state: &PoolState) -> Option<(u128, u128)> {
+    if state.ckb_reserve == 0 || state.sudt_reserve == 0 {
+        return None;
+    }
+
+    let buy = BigUint::from(ckb_in) * FEE_RATE * state.sudt_reserve;
+    let sell = BigUint::from(sudt_in) * THOUSAND * state.ckb_reserve;
+    if buy > sell {
+        let denominator = (BigUint::from(state.sudt_reserve) + sudt_in) * FEE_RATE;
+        let ckb_net = to_u128((buy - sell) / denominator)?;
+        if ckb_net != 0 {
+            return Some((
+                ckb_in - ckb_net,
+                sudt_in.checked_add(sudt_out(ckb_net, state)?)?,
+            ));
+        }
+    }
+
+    let sell = BigUint::from(sudt_in) * FEE_RATE * state.ckb_reserve;
+    let buy = BigUint::from(ckb_in) * THOUSAND * state.sudt_reserve;
+    if sell > buy {
+        let denominator = (BigUint::from(state.ckb_reserve) + ckb_in) * FEE_RATE;
+        let sudt_net = to_u128((sell - buy) / denominator)?;
+        if sudt_net != 0 {
+            return Some((
+                ckb_in.checked_add(ckb_out(sudt_net, state)?)?,
+                sudt_in - sudt_net,
+            ));
+        }
+    }
+
+    Some((ckb_in, sudt_in))
+}
+
+/// The CKB paid to the sellers and the sUDT paid to the buyers when `ckb_in` and `sudt_in` are
+/// matched at the pool mid price without fee, the residual of the larger side trading on the
+/// curve.
+pub fn mid_price_matching(ckb_in: u128, sudt_in: u128, state: &PoolState) -> Option<(u128, u128)> {
+    if state.ckb_reserve == 0 || state.sudt_reserve == 0 {
+        return None;
+    }
+
+    let buy = BigUint::from(ckb_in) * state.sudt_reserve;
+    let sell = BigUint::from(sudt_in) * state.ckb_reserve;
+    if buy > sell {
+        let ckb_matched = to_u128(sell / state.sudt_reserve)?;
+        let sudt_residual_out = sudt_out(ckb_in - ckb_matched, state)?;
+        Some((ckb_matched, sudt_in.checked_add(sudt_residual_out)?))
+    } else if sell > buy {
+        let sudt_matched = to_u128(buy / state.ckb_reserve)?;
+        let ckb_residual_out = ckb_out(sudt_in - sudt_matched, state)?;
+        Some((ckb_in.checked_add(ckb_residual_out)?, sudt_matched))
+    } else {
+        Some((ckb_in, sudt_in))
+    }
+}
+
+/// The share of `total_out` paid for `amount_in` out of `total_in`, rounded down.
+pub fn uniform_share(amount_in: u128, total_in: u128, total_out: u128) -> Option<u128> {
+    if total_in == 0 {
+        return None;
+    }
+
+    to_u128(BigUint::from(amount_in) * total_out / total_in)
+}
+
+/// The referrer share of the fee paid on `amount_in`, in the input asset.
+pub fn referral_amount(amount_in: u128, bps: u16) -> Option<u128> {
+    let amount = BigUint::from(amount_in) * (THOUSAND - FEE_RATE) * bps as u128;
+    to_u128(amount / (THOUSAND * BPS_BASE))
+}
+
+pub fn initial_liquidity(ckb_injected: u128, sudt_injected: u128) -> Option<u128> {
+    to_u128((BigUint::from(sudt_injected) * ckb_injected).sqrt())
+}
+
+/// The CKB injected along with all of `sudt_injected`, when the change is paid in CKB.
+pub fn ckb_for_sudt_injected(sudt_injected: u128, state: &PoolState) -> Option<u128> {
+    if state.sudt_reserve == 0 {
+        return None;
+    }
+
+    to_u128(BigUint::from(sudt_injected) * state.ckb_reserve / state.sudt_reserve + ONE)
+}
+
+pub fn liquidity_for_sudt_injected(sudt_injected: u128, state: &PoolState) -> Option<u128> {
+    if state.sudt_reserve == 0 {
+        return None;
+    }
+
+    to_u128(BigUint::from(sudt_injected) * state.total_liquidity / state.sudt_reserve + ONE)
+}
+
+/// The sUDT injected along with all of `ckb_injected`, when the change is paid in sUDT.
+pub fn sudt_for_ckb_injected(ckb_injected: u128, state: &PoolState) -> Option<u128> {
+    if state.ckb_reserve == 0 {
+        return None;
+    }
+
+    to_u128(BigUint::from(ckb_injected) * state.sudt_reserve / state.ckb_reserve + ONE)
+}
+
+pub fn liquidity_for_ckb_injected(ckb_injected: u128, state: &PoolState) -> Option<u128> {
+    if state.ckb_reserve == 0 {
+        return None;
+    }
+
+    to_u128(BigUint::from(ckb_injected) * state.total_liquidity / state.ckb_reserve + ONE)
+}
+
+/// The CKB and sUDT withdrawn by burning `liquidity`, tips included.
+pub fn removed_amounts(liquidity: u128, state: &PoolState) -> Option<(u128, u128)> {
+    if liquidity == 0 || liquidity > state.total_liquidity {
+        return None;
+    }
+
+    // Burning all liquidity empties the pool, the rounding up would exceed the reserves
+    if liquidity == state.total_liquidity {
+        return Some((state.ckb_reserve, state.sudt_reserve));
+    }
+
+    let ckb = BigUint::from(state.ckb_reserve) * liquidity / state.total_liquidity + ONE;
+    let sudt = BigUint::from(state.sudt_reserve) * liquidity / state.total_liquidity + ONE;
+
+    Some((to_u128(ckb)?, to_u128(sudt)?))
+}
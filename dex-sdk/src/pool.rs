@@ -0,0 +1,87 @@
+use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::packed::Script;
+use ckb_tool::ckb_types::prelude::*;
+use share::cell::InfoCellData;
+
+use crate::cell::{decode_sudt_amount, LiveCell};
+use crate::error::Error;
+use crate::POOL_CAPACITY;
+
+/// The reserves and total liquidity recorded in the info cell data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolState {
+    pub ckb_reserve:     u128,
+    pub sudt_reserve:    u128,
+    pub total_liquidity: u128,
+}
+
+/// A live pool, the info cell and the pool cell holding its sUDT reserve.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    pub info:      LiveCell,
+    pub pool:      LiveCell,
+    pub info_data: InfoCellData,
+}
+
+impl Pool {
+    pub fn from_cells(info: LiveCell, pool: LiveCell) -> Result<Self, Error> {
+        let info_data = InfoCellData::from_raw(&info.data)?;
+
+        if info.output.type_().is_none()
+            || pool.output.type_().is_none()
+            || info.lock_hash() != pool.lock_hash()
+            || pool.data.len() != 16
+            || decode_sudt_amount(&pool.data)? != info_data.sudt_reserve
+            || pool.capacity() as u128 != POOL_CAPACITY as u128 + info_data.ckb_reserve
+        {
+            return Err(Error::InvalidPoolCells);
+        }
+
+        Ok(Pool {
+            info,
+            pool,
+            info_data,
+        })
+    }
+
+    pub fn state(&self) -> PoolState {
+        PoolState {
+            ckb_reserve:     self.info_data.ckb_reserve,
+            sudt_reserve:    self.info_data.sudt_reserve,
+            total_liquidity: self.info_data.total_liquidity,
+        }
+    }
+
+    pub fn info_type_hash(&self) -> [u8; 32] {
+        self.info.type_hash().unwrap()
+    }
+
+    pub fn sudt_type(&self) -> Script {
+        self.pool.output.type_().to_opt().unwrap()
+    }
+
+    pub fn sudt_type_hash(&self) -> [u8; 32] {
+        self.pool.type_hash().unwrap()
+    }
+
+    /// The liquidity sUDT is owned by the info lock, which is unlocked in every batch. A migrated
+    /// pool keeps the liquidity sUDT of its old info lock, which can be burnt but no longer minted.
+    pub fn liquidity_sudt_type(&self) -> Result<Script, Error> {
+        let script = liquidity_sudt_type(&self.sudt_type(), self.info.lock_hash());
+        let script_hash: [u8; 32] = script.calc_script_hash().unpack();
+        if script_hash != self.info_data.liquidity_sudt_type_hash {
+            return Err(Error::LiquiditySUDTTypeMismatch);
+        }
+
+        Ok(script)
+    }
+}
+
+/// A sUDT type script of the same code as `sudt_type`, owned by `owner_lock_hash`.
+pub fn liquidity_sudt_type(sudt_type: &Script, owner_lock_hash: [u8; 32]) -> Script {
+    sudt_type
+        .clone()
+        .as_builder()
+        .args(Bytes::from(owner_lock_hash.to_vec()).pack())
+        .build()
+}
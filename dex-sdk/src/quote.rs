@@ -0,0 +1,311 @@
+//! Quotes of swaps and liquidity changes as the info type script computes them. The amounts are
+//! the ones reaching the pool, the tips of the requests are kept out of them.
+
+use crate::math;
+use crate::pool::PoolState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Asset {
+    Ckb,
+    Sudt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapQuote {
+    pub asset_in:     Asset,
+    pub amount_in:    u128,
+    /// The amount paid by the pool, the sUDT tips of a CKB request or the CKB tips of a sUDT
+    /// request are taken from it.
+    pub amount_out:   u128,
+    /// The referrer share of the fee, in the input asset.
+    pub referral:     u128,
+    /// The loss against the spot price before the swap, fee included, from 0 to 1.
+    pub price_impact: f64,
+    pub state:        PoolState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MintQuote {
+    pub ckb_injected:  u128,
+    pub sudt_injected: u128,
+    pub liquidity:     u128,
+    pub state:         PoolState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurnQuote {
+    pub ckb_removed:  u128,
+    pub sudt_removed: u128,
+    pub state:        PoolState,
+}
+
+pub fn swap(
+    state: &PoolState,
+    asset_in: Asset,
+    amount_in: u128,
+    referral_bps: u16,
+) -> Option<SwapQuote> {
+    if amount_in == 0 {
+        return None;
+    }
+
+    let referral = math::referral_amount(amount_in, referral_bps)?;
+    let mut after = *state;
+    let (amount_out, reserve_in, reserve_out) = match asset_in {
+        Asset::Ckb => {
+            let amount_out = math::sudt_out(amount_in, state)?;
+            after.ckb_reserve = state.ckb_reserve.checked_add(amount_in)? - referral;
+            after.sudt_reserve = state.sudt_reserve.checked_sub(amount_out)?;
+            (amount_out, state.ckb_reserve, state.sudt_reserve)
+        }
+        Asset::Sudt => {
+            let amount_out = math::ckb_out(amount_in, state)?;
+            after.sudt_reserve = state.sudt_reserve.checked_add(amount_in)? - referral;
+            after.ckb_reserve = state.ckb_reserve.checked_sub(amount_out)?;
+            (amount_out, state.sudt_reserve, state.ckb_reserve)
+        }
+    };
+
+    let spot_out = amount_in as f64 * reserve_out as f64 / reserve_in as f64;
+    let price_impact = (1.0 - amount_out as f64 / spot_out).max(0.0);
+
+    Some(SwapQuote {
+        asset_in,
+        amount_in,
+        amount_out,
+        referral,
+        price_impact,
+        state: after,
+    })
+}
+
+/// The swap paying at least `amount_out` with the least input, the target of a swap request
+/// whose `min_amount_out` is `amount_out`. Tips are on top of the returned `amount_in`.
+pub fn swap_for_output(
+    state: &PoolState,
+    asset_in: Asset,
+    amount_out: u128,
+    referral_bps: u16,
+) -> Option<SwapQuote> {
+    let amount_in = match asset_in {
+        Asset::Ckb => math::ckb_in_for_sudt_out(amount_out, state)?,
+        Asset::Sudt => math::sudt_in_for_ckb_out(amount_out, state)?,
+    };
+
+    swap(state, asset_in, amount_in, referral_bps)
+}
+
+/// Clears `swaps` at a single price, returning the amount paid to each of them, tips included,
+/// and the pool state after. Every swap gets its share of the other side rounded down, the dust
+/// stays in the pool.
+pub fn uniform_swaps(state: &PoolState, swaps: &[(Asset, u128)]) -> Option<(Vec<u128>, PoolState)> {
+    settle_swaps(state, swaps, math::uniform_clearing)
+}
+
+/// Matches `swaps` against each other at the pool mid price, the residual trading on the curve,
+/// and pays each side as `uniform_swaps` does.
+pub fn mid_price_swaps(
+    state: &PoolState,
+    swaps: &[(Asset, u128)],
+) -> Option<(Vec<u128>, PoolState)> {
+    settle_swaps(state, swaps, math::mid_price_matching)
+}
+
+fn settle_swaps(
+    state: &PoolState,
+    swaps: &[(Asset, u128)],
+    clearing: fn(u128, u128, &PoolState) -> Option<(u128, u128)>,
+) -> Option<(Vec<u128>, PoolState)> {
+    let total_in = |asset: Asset| -> Option<u128> {
+        swaps
+            .iter()
+            .filter(|(asset_in, _)| *asset_in == asset)
+            .try_fold(0u128, |total, (_, amount)| total.checked_add(*amount))
+    };
+    let ckb_in = total_in(Asset::Ckb)?;
+    let sudt_in = total_in(Asset::Sudt)?;
+    let (ckb_out, sudt_out) = clearing(ckb_in, sudt_in, state)?;
+
+    let mut amounts_out = Vec::with_capacity(swaps.len());
+    let mut after = *state;
+    for (asset_in, amount_in) in swaps.iter() {
+        if *amount_in == 0 {
+            return None;
+        }
+
+        let amount_out = match asset_in {
+            Asset::Ckb => {
+                let amount_out = math::uniform_share(*amount_in, ckb_in, sudt_out)?;
+                after.ckb_reserve = after.ckb_reserve.checked_add(*amount_in)?;
+                after.sudt_reserve = after.sudt_reserve.checked_sub(amount_out)?;
+                amount_out
+            }
+            Asset::Sudt => {
+                let amount_out = math::uniform_share(*amount_in, sudt_in, ckb_out)?;
+                after.sudt_reserve = after.sudt_reserve.checked_add(*amount_in)?;
+                after.ckb_reserve = after.ckb_reserve.checked_sub(amount_out)?;
+                amount_out
+            }
+        };
+        amounts_out.push(amount_out);
+    }
+
+    Some((amounts_out, after))
+}
+
+/// The first liquidity of a pool, which must be empty.
+pub fn initial_mint(
+    state: &PoolState,
+    ckb_injected: u128,
+    sudt_injected: u128,
+) -> Option<MintQuote> {
+    if state.ckb_reserve != 0 || state.sudt_reserve != 0 || state.total_liquidity != 0 {
+        return None;
+    }
+
+    let liquidity = math::initial_liquidity(ckb_injected, sudt_injected)?;
+    if liquidity == 0 {
+        return None;
+    }
+
+    Some(MintQuote {
+        ckb_injected,
+        sudt_injected,
+        liquidity,
+        state: PoolState {
+            ckb_reserve:     ckb_injected,
+            sudt_reserve:    sudt_injected,
+            total_liquidity: liquidity,
+        },
+    })
+}
+
+/// Injects all of `sudt_injected`, the request gets its CKB change back.
+pub fn mint_with_sudt(state: &PoolState, sudt_injected: u128) -> Option<MintQuote> {
+    let ckb_injected = math::ckb_for_sudt_injected(sudt_injected, state)?;
+    let liquidity = math::liquidity_for_sudt_injected(sudt_injected, state)?;
+    mint_quote(state, ckb_injected, sudt_injected, liquidity)
+}
+
+/// Injects all of `ckb_injected`, the request gets its sUDT change back.
+pub fn mint_with_ckb(state: &PoolState, ckb_injected: u128) -> Option<MintQuote> {
+    let sudt_injected = math::sudt_for_ckb_injected(ckb_injected, state)?;
+    let liquidity = math::liquidity_for_ckb_injected(ckb_injected, state)?;
+    mint_quote(state, ckb_injected, sudt_injected, liquidity)
+}
+
+/// Injects as much as the available amounts allow, CKB change first.
+pub fn mint(state: &PoolState, ckb_available: u128, sudt_available: u128) -> Option<MintQuote> {
+    if state.total_liquidity == 0 {
+        return initial_mint(state, ckb_available, sudt_available);
+    }
+
+    mint_with_sudt(state, sudt_available)
+        .filter(|quote| quote.ckb_injected <= ckb_available)
+        .or_else(|| {
+            mint_with_ckb(state, ckb_available)
+                .filter(|quote| quote.sudt_injected <= sudt_available)
+        })
+}
+
+fn mint_quote(
+    state: &PoolState,
+    ckb_injected: u128,
+    sudt_injected: u128,
+    liquidity: u128,
+) -> Option<MintQuote> {
+    if state.total_liquidity == 0 || ckb_injected == 0 || sudt_injected == 0 {
+        return None;
+    }
+
+    Some(MintQuote {
+        ckb_injected,
+        sudt_injected,
+        liquidity,
+        state: PoolState {
+            ckb_reserve:     state.ckb_reserve.checked_add(ckb_injected)?,
+            sudt_reserve:    state.sudt_reserve.checked_add(sudt_injected)?,
+            total_liquidity: state.total_liquidity.checked_add(liquidity)?,
+        },
+    })
+}
+
+pub fn burn(state: &PoolState, liquidity: u128) -> Option<BurnQuote> {
+    let (ckb_removed, sudt_removed) = math::removed_amounts(liquidity, state)?;
+
+    Some(BurnQuote {
+        ckb_removed,
+        sudt_removed,
+        state: PoolState {
+            ckb_reserve:     state.ckb_reserve.checked_sub(ckb_removed)?,
+            sudt_reserve:    state.sudt_reserve.checked_sub(sudt_removed)?,
+            total_liquidity: state.total_liquidity - liquidity,
+        },
+    })
+}
+
+/// A request of a batch, with the amounts reaching the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Swap {
+        asset_in:     Asset,
+        amount_in:    u128,
+        referral_bps: u16,
+    },
+    AddLiquidity {
+        ckb_available:  u128,
+        sudt_available: u128,
+    },
+    RemoveLiquidity {
+        liquidity: u128,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    Swap(SwapQuote),
+    Mint(MintQuote),
+    Burn(BurnQuote),
+}
+
+/// Simulates a batch the way the info type script verifies it, all the swaps first and then the
+/// liquidity requests, each in the given order. The fills are returned in the order of
+/// `requests`, `None` if any of them can not be filled.
+pub fn simulate_batch(state: &PoolState, requests: &[Request]) -> Option<(Vec<Fill>, PoolState)> {
+    let is_swap = |request: &Request| matches!(request, Request::Swap { .. });
+    let swaps = requests.iter().enumerate().filter(|(_, req)| is_swap(req));
+    let liquidity = requests.iter().enumerate().filter(|(_, req)| !is_swap(req));
+
+    // An empty pool can only be filled with a single add liquidity request
+    if state.total_liquidity == 0
+        && (requests.len() != 1 || !matches!(requests[0], Request::AddLiquidity { .. }))
+    {
+        return None;
+    }
+
+    let mut current = *state;
+    let mut fills = vec![None; requests.len()];
+    for (idx, request) in swaps.chain(liquidity) {
+        let fill = match *request {
+            Request::Swap {
+                asset_in,
+                amount_in,
+                referral_bps,
+            } => Fill::Swap(swap(&current, asset_in, amount_in, referral_bps)?),
+            Request::AddLiquidity {
+                ckb_available,
+                sudt_available,
+            } => Fill::Mint(mint(&current, ckb_available, sudt_available)?),
+            Request::RemoveLiquidity { liquidity } => Fill::Burn(burn(&current, liquidity)?),
+        };
+
+        current = match fill {
+            Fill::Swap(quote) => quote.state,
+            Fill::Mint(quote) => quote.state,
+            Fill::Burn(quote) => quote.state,
+        };
+        fills[idx] = Some(fill);
+    }
+
+    Some((fills.into_iter().map(Option::unwrap).collect(), current))
+}
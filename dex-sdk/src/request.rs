@@ -0,0 +1,132 @@
+use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::packed::Script;
+use ckb_tool::ckb_types::prelude::*;
+use share::cell::{LiquidityRequestLockArgs, SwapRequestLockArgs};
+
+use crate::cell::{decode_sudt_amount, Deployment, LiveCell};
+use crate::error::Error;
+
+/// A swap request cell, along with the user lock receiving its output. A CKB request swaps its
+/// capacity above `SUDT_CAPACITY`, a sUDT request swaps its whole amount.
+#[derive(Debug, Clone)]
+pub struct SwapRequest {
+    pub cell:          LiveCell,
+    pub args:          SwapRequestLockArgs,
+    pub user_lock:     Script,
+    pub referrer_lock: Option<Script>,
+}
+
+impl SwapRequest {
+    pub fn new(cell: LiveCell, user_lock: Script) -> Result<Self, Error> {
+        let raw_args: Vec<u8> = cell.output.lock().args().unpack();
+        let args = SwapRequestLockArgs::from_raw(&raw_args)?;
+        let user_lock_hash: [u8; 32] = user_lock.calc_script_hash().unpack();
+        if user_lock_hash != args.user_lock_hash {
+            return Err(Error::UserLockMismatch);
+        }
+
+        if cell.output.type_().is_some() {
+            decode_sudt_amount(&cell.data)?;
+        }
+
+        Ok(SwapRequest {
+            cell,
+            args,
+            user_lock,
+            referrer_lock: None,
+        })
+    }
+
+    /// The referrer receiving its share of the fee, only needed when the request has one.
+    pub fn referrer_lock(mut self, lock: Script) -> Result<Self, Error> {
+        let lock_hash: [u8; 32] = lock.calc_script_hash().unpack();
+        match &self.args.referral {
+            Some(referral) if referral.lock_hash == lock_hash => {
+                self.referrer_lock = Some(lock);
+                Ok(self)
+            }
+            _ => Err(Error::ReferrerLockMismatch),
+        }
+    }
+
+    pub fn is_ckb_in(&self) -> bool {
+        self.cell.output.type_().is_none()
+    }
+}
+
+/// An add liquidity request holds pool sUDT, a remove liquidity request holds liquidity sUDT.
+#[derive(Debug, Clone)]
+pub struct LiquidityRequest {
+    pub cell:      LiveCell,
+    pub args:      LiquidityRequestLockArgs,
+    pub user_lock: Script,
+}
+
+impl LiquidityRequest {
+    pub fn new(cell: LiveCell, user_lock: Script) -> Result<Self, Error> {
+        let raw_args: Vec<u8> = cell.output.lock().args().unpack();
+        let args = LiquidityRequestLockArgs::from_raw(&raw_args)?;
+        let user_lock_hash: [u8; 32] = user_lock.calc_script_hash().unpack();
+        if user_lock_hash != args.user_lock_hash {
+            return Err(Error::UserLockMismatch);
+        }
+
+        decode_sudt_amount(&cell.data)?;
+
+        Ok(LiquidityRequest {
+            cell,
+            args,
+            user_lock,
+        })
+    }
+
+    pub fn amount(&self) -> u128 {
+        decode_sudt_amount(&self.cell.data).unwrap()
+    }
+}
+
+pub fn encode_swap_args(args: &SwapRequestLockArgs) -> Bytes {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&args.sudt_type_hash);
+    buf.extend_from_slice(&args.user_lock_hash);
+    buf.push(args.version);
+    buf.extend_from_slice(&args.min_amount_out.to_le_bytes());
+    buf.extend_from_slice(&args.tips.to_le_bytes());
+    buf.extend_from_slice(&args.tips_sudt.to_le_bytes());
+
+    if let Some(info_type_hash) = &args.info_type_hash {
+        buf.extend_from_slice(info_type_hash);
+    }
+
+    if let Some(referral) = &args.referral {
+        buf.extend_from_slice(&referral.lock_hash);
+        buf.extend_from_slice(&referral.bps.to_le_bytes());
+    }
+
+    Bytes::from(buf)
+}
+
+pub fn encode_liquidity_args(args: &LiquidityRequestLockArgs) -> Bytes {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&args.info_type_hash);
+    buf.extend_from_slice(&args.user_lock_hash);
+    buf.push(args.version);
+    buf.extend_from_slice(&args.amount_1.to_le_bytes());
+    buf.extend_from_slice(&args.amount_0.to_le_bytes());
+    buf.extend_from_slice(&args.tips.to_le_bytes());
+    buf.extend_from_slice(&args.tips_sudt.to_le_bytes());
+
+    Bytes::from(buf)
+}
+
+/// The lock of a new swap request cell.
+pub fn swap_request_lock(deployment: &Deployment, args: &SwapRequestLockArgs) -> Script {
+    deployment.swap_lock.script(encode_swap_args(args))
+}
+
+/// The lock of a new liquidity request cell.
+pub fn liquidity_request_lock(deployment: &Deployment, args: &LiquidityRequestLockArgs) -> Script {
+    deployment
+        .liquidity_lock
+        .script(encode_liquidity_args(args))
+}
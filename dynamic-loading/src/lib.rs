@@ -1,10 +1,20 @@
 #![no_std]
 
+use ckb_std::ckb_constants::Source;
+use ckb_std::ckb_types::{
+    packed::{Byte, Script},
+    prelude::*,
+};
 use ckb_std::dynamic_loading::{CKBDLContext, Symbol};
+use ckb_std::error::SysError;
+use ckb_std::high_level::load_witness_args;
+use share::error::{LiquidityLockError, SwapLockError};
+use share::hash::blake2b_256;
 
 type Validate = unsafe extern "C" fn(args: *const u8, len: u64) -> i32;
 
 const VALIDATE: &[u8; 8] = b"validate";
+const DATA_HASH_TYPE: u8 = 0;
 
 #[derive(Debug)]
 pub enum Error {
@@ -39,3 +49,79 @@ impl DynLock {
         Ok(())
     }
 }
+
+#[derive(Debug)]
+pub enum CancelError {
+    Sys(SysError),
+    InvalidLockScript,
+    UnsupportedHashType,
+    DynLock(Error),
+}
+
+impl From<SysError> for CancelError {
+    fn from(err: SysError) -> Self {
+        CancelError::Sys(err)
+    }
+}
+
+impl From<Error> for CancelError {
+    fn from(err: Error) -> Self {
+        CancelError::DynLock(err)
+    }
+}
+
+macro_rules! impl_from_cancel_error {
+    ($($error:ident),*) => {
+        $(
+            impl From<CancelError> for $error {
+                fn from(err: CancelError) -> Self {
+                    match err {
+                        CancelError::Sys(err) => err.into(),
+                        CancelError::InvalidLockScript => $error::InvalidCancelLockScript,
+                        CancelError::UnsupportedHashType => $error::UnsupportedCancelHashType,
+                        CancelError::DynLock(Error::ValidateFailure(_)) => {
+                            $error::InvalidCancelSignature
+                        }
+                        CancelError::DynLock(_) => $error::DynLockLoadFailed,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_cancel_error!(SwapLockError, LiquidityLockError);
+
+/// Verifies a request cancelled by the user lock itself. The user lock script is carried in the
+/// `input_type` field of the request's own witness, it must hash to `user_lock_hash` and be
+/// referenced by data hash, since only the cell deps are searched for its code. The lock is then
+/// dynamically loaded to validate the signature in the `lock` field. Returns `false` when the
+/// witness carries no lock script.
+///
+/// Never inlined so the loading context only takes stack space on the cancel path.
+#[inline(never)]
+pub fn verify_cancel(user_lock_hash: &[u8]) -> Result<bool, CancelError> {
+    let raw_lock_script = match load_witness_args(0, Source::GroupInput)
+        .ok()
+        .and_then(|witness| witness.input_type().to_opt())
+    {
+        Some(raw) => raw.raw_data(),
+        None => return Ok(false),
+    };
+
+    let user_lock = Script::from_slice(&raw_lock_script).map_err(|_| SysError::Encoding)?;
+    if blake2b_256(&raw_lock_script)[..] != user_lock_hash[..] {
+        return Err(CancelError::InvalidLockScript);
+    }
+    if user_lock.hash_type() != Byte::new(DATA_HASH_TYPE) {
+        return Err(CancelError::UnsupportedHashType);
+    }
+
+    let code_hash: [u8; 32] = user_lock.code_hash().unpack();
+    let lock_args = user_lock.args().raw_data();
+    let mut context = unsafe { CKBDLContext::<[u8; 128 * 1024]>::new() };
+    let dyn_lock = DynLock::load(&mut context, &code_hash)?;
+    dyn_lock.validate(&lock_args, lock_args.len() as u64)?;
+
+    Ok(true)
+}
@@ -0,0 +1,237 @@
+//! Decodes a failed transaction verification into the contract error and the cell whose
+//! script failed.
+
+use std::fmt;
+
+use ckb_tool::ckb_error::Error;
+use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::core::TransactionView;
+use ckb_tool::ckb_types::packed::{Byte, CellOutput, OutPoint, Script};
+use ckb_tool::ckb_types::prelude::*;
+use dex_sdk::Deployment;
+use share::error::{
+    lookup_error, ErrorEntry, InfoLockError, InfoTypeError, LiquidityLockError, RegistryTypeError,
+    SwapLockError, COMMON_CODES,
+};
+
+const MAX_DATA_SHOWN: usize = 32;
+
+/// The script group which failed, identified by the index of its first cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptGroup {
+    InputLock(usize),
+    InputType(usize),
+    OutputType(usize),
+}
+
+impl ScriptGroup {
+    /// Parses the source of a script error, e.g. `Inputs[0].Lock`.
+    fn parse(source: &str) -> Option<Self> {
+        let open = source.find('[')?;
+        let close = source.find(']')?;
+        let index = source.get(open + 1..close)?.parse().ok()?;
+
+        match (&source[..open], &source[close + 1..]) {
+            ("Inputs", ".Lock") => Some(ScriptGroup::InputLock(index)),
+            ("Inputs", ".Type") => Some(ScriptGroup::InputType(index)),
+            ("Outputs", ".Type") => Some(ScriptGroup::OutputType(index)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScriptGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptGroup::InputLock(index) => write!(f, "the lock script of input {}", index),
+            ScriptGroup::InputType(index) => write!(f, "the type script of input {}", index),
+            ScriptGroup::OutputType(index) => write!(f, "the type script of output {}", index),
+        }
+    }
+}
+
+pub struct Failure {
+    pub group:    ScriptGroup,
+    pub code:     i8,
+    pub contract: Option<&'static str>,
+    pub error:    Option<&'static ErrorEntry>,
+    pub cell:     Option<(CellOutput, Bytes)>,
+}
+
+impl Failure {
+    pub fn name(&self) -> Option<&'static str> {
+        self.error.map(|entry| entry.name)
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.contract, self.error) {
+            (Some(contract), Some(entry)) => write!(
+                f,
+                "{} failed with {} ({}) in {}",
+                contract, entry.name, self.code, self.group
+            )?,
+            (Some(contract), None) => write!(
+                f,
+                "{} failed with unknown error {} in {}",
+                contract, self.code, self.group
+            )?,
+            (None, _) => write!(f, "unknown error {} in {}", self.code, self.group)?,
+        }
+
+        if let Some((cell, data)) = &self.cell {
+            let capacity: u64 = cell.capacity().unpack();
+            let type_hash = cell
+                .type_()
+                .to_opt()
+                .map(|script| format!("{:#x}", script.calc_script_hash()))
+                .unwrap_or_else(|| "none".to_owned());
+
+            write!(
+                f,
+                ": capacity {} shannons, lock hash {:#x}, type hash {}, data {} bytes 0x{}",
+                capacity,
+                cell.lock().calc_script_hash(),
+                type_hash,
+                data.len(),
+                hex::encode(&data[..data.len().min(MAX_DATA_SHOWN)])
+            )?;
+
+            if data.len() > MAX_DATA_SHOWN {
+                write!(f, "..")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the script group and exit code of a failed verification, `load_input` resolves the
+/// input cells, e.g. `|out_point| context.get_cell(out_point)` with a `ckb-testtool` context.
+/// The error is looked up in the contract the failed script is deployed as, a script which isn't
+/// one of `deployment` has no error. Returns `None` if the failure is not raised by a script.
+pub fn decode_failure<F>(
+    err: &Error,
+    tx: &TransactionView,
+    deployment: &Deployment,
+    load_input: F,
+) -> Option<Failure>
+where
+    F: Fn(&OutPoint) -> Option<(CellOutput, Bytes)>,
+{
+    let (group, code) = parse_script_error(&err.to_string())?;
+
+    let (cell, script) = match group {
+        ScriptGroup::InputLock(index) | ScriptGroup::InputType(index) => {
+            let cell = tx
+                .inputs()
+                .get(index)
+                .and_then(|input| load_input(&input.previous_output()));
+            let script = cell.as_ref().and_then(|(output, _)| match group {
+                ScriptGroup::InputLock(_) => Some(output.lock()),
+                _ => output.type_().to_opt(),
+            });
+            (cell, script)
+        }
+        ScriptGroup::OutputType(index) => {
+            let cell = tx.outputs().get(index).map(|output| {
+                let data: Bytes = tx.outputs_data().get(index).unwrap_or_default().unpack();
+                (output, data)
+            });
+            let script = cell
+                .as_ref()
+                .and_then(|(output, _)| output.type_().to_opt());
+            (cell, script)
+        }
+    };
+
+    let contract = script.and_then(|script| contract_of(deployment, &script));
+    let error = contract.and_then(|contract| {
+        lookup_error(code)
+            .filter(|entry| entry.contract == contract || COMMON_CODES.contains(&code))
+    });
+
+    Some(Failure {
+        group,
+        code,
+        contract,
+        error,
+        cell,
+    })
+}
+
+/// Script errors keep their source private, parse the message as `assert_error_eq` compares it,
+/// e.g. `TransactionScriptError { source: Inputs[0].Lock, cause: ValidationFailure(-115) }`.
+fn parse_script_error(message: &str) -> Option<(ScriptGroup, i8)> {
+    let group = ScriptGroup::parse(text_after(message, "source: ", ',')?)?;
+    let code = text_after(message, "ValidationFailure(", ')')?
+        .parse()
+        .ok()?;
+    Some((group, code))
+}
+
+/// The contract `script` is deployed as, matched by code hash and hash type.
+fn contract_of(deployment: &Deployment, script: &Script) -> Option<&'static str> {
+    let contracts = [
+        (&deployment.info_type, InfoTypeError::CONTRACT),
+        (&deployment.info_lock, InfoLockError::CONTRACT),
+        (&deployment.swap_lock, SwapLockError::CONTRACT),
+        (&deployment.liquidity_lock, LiquidityLockError::CONTRACT),
+        (&deployment.registry_type, RegistryTypeError::CONTRACT),
+    ];
+
+    contracts
+        .iter()
+        .find(|(script_ref, _)| {
+            let hash_type: Byte = script_ref.hash_type.into();
+            script.code_hash() == script_ref.code_hash && script.hash_type() == hash_type
+        })
+        .map(|(_, contract)| *contract)
+}
+
+/// The text following `prefix` in `message`, up to `end`.
+fn text_after<'a>(message: &'a str, prefix: &str, end: char) -> Option<&'a str> {
+    let start = message.find(prefix)? + prefix.len();
+    let len = message[start..].find(end)?;
+    Some(&message[start..start + len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ckb_tool::ckb_script::ScriptError;
+
+    #[test]
+    fn parse_script_errors() {
+        let errors = vec![
+            (
+                ScriptGroup::InputLock(0),
+                -115,
+                ScriptError::ValidationFailure(-115).input_lock_script(0),
+            ),
+            (
+                ScriptGroup::InputType(2),
+                23,
+                ScriptError::ValidationFailure(23).input_type_script(2),
+            ),
+            (
+                ScriptGroup::OutputType(11),
+                5,
+                ScriptError::ValidationFailure(5).output_type_script(11),
+            ),
+        ];
+
+        for (group, code, err) in errors {
+            let message = Error::from(err).to_string();
+            assert_eq!(parse_script_error(&message), Some((group, code)));
+        }
+    }
+
+    #[test]
+    fn parse_other_errors() {
+        let err = Error::from(ScriptError::InvalidCodeHash.input_lock_script(0));
+        assert_eq!(parse_script_error(&err.to_string()), None);
+    }
+}
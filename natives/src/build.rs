@@ -0,0 +1,231 @@
+//! Unsigned transactions built out of JSON files. Scripts, cells and cell deps are written as in
+//! the node RPC, the built transaction too, ready to be signed.
+
+use std::fs;
+
+use ckb_jsonrpc_types::{
+    CellDep, CellOutput, JsonBytes, OutPoint, Script, ScriptHashType, TransactionView, Uint64,
+};
+use ckb_tool::ckb_types::core;
+use ckb_tool::ckb_types::{packed, prelude::*, H256};
+use dex_sdk::{
+    BatchBuilder, CreatePool, Deployment, LiquidityRequest, LiveCell, Operation, Pool, ScriptRef,
+    Settlement, SwapRequest,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::quote::state_json;
+use crate::Error;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScriptRefJson {
+    code_hash: H256,
+    hash_type: ScriptHashType,
+    cell_dep:  CellDep,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeploymentJson {
+    info_type:          ScriptRefJson,
+    info_lock:          ScriptRefJson,
+    swap_lock:          ScriptRefJson,
+    liquidity_lock:     ScriptRefJson,
+    registry_type:      ScriptRefJson,
+    sudt:               ScriptRefJson,
+    registry_type_args: JsonBytes,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CellJson {
+    out_point: OutPoint,
+    output:    CellOutput,
+    data:      JsonBytes,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CreatePoolJson {
+    sudt_type:         Script,
+    change_lock:       Script,
+    funding:           Vec<CellJson>,
+    /// The live registry cells, the pool is inserted after one of them.
+    registry:          Vec<CellJson>,
+    creator_lock_hash: Option<H256>,
+    admin_lock_hash:   Option<H256>,
+    #[serde(default)]
+    cell_deps:         Vec<CellDep>,
+    fee:               Option<Uint64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SwapJson {
+    cell:          CellJson,
+    user_lock:     Script,
+    referrer_lock: Option<Script>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LiquidityJson {
+    cell:      CellJson,
+    user_lock: Script,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SettlementJson {
+    Sequential,
+    UniformPrice,
+    MidPrice,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OperationJson {
+    Swap,
+    Liquidity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchJson {
+    info:       CellJson,
+    pool:       CellJson,
+    matchers:   Vec<CellJson>,
+    #[serde(default)]
+    swaps:      Vec<SwapJson>,
+    #[serde(default)]
+    liquidity:  Vec<LiquidityJson>,
+    #[serde(default)]
+    cell_deps:  Vec<CellDep>,
+    fee:        Option<Uint64>,
+    settlement: Option<SettlementJson>,
+    #[serde(default)]
+    operations: Vec<OperationJson>,
+}
+
+pub fn create_pool(deployment_path: &str, request_path: &str) -> Result<Value, Error> {
+    let deployment = deployment(read_json(deployment_path)?);
+    let request: CreatePoolJson = read_json(request_path)?;
+    let registry = request
+        .registry
+        .into_iter()
+        .map(live_cell)
+        .collect::<Vec<_>>();
+
+    let mut builder = CreatePool::new(
+        &deployment,
+        request.sudt_type.into(),
+        request.change_lock.into(),
+    )
+    .registry(&registry)?
+    .fee(request.fee.map_or(0, |fee| fee.value()));
+    for cell in request.funding {
+        builder = builder.funding(live_cell(cell));
+    }
+    for cell_dep in request.cell_deps {
+        builder = builder.cell_dep(cell_dep.into());
+    }
+    if let Some(lock_hash) = request.creator_lock_hash {
+        builder = builder.creator(lock_hash.0);
+    }
+    if let Some(lock_hash) = request.admin_lock_hash {
+        builder = builder.admin(lock_hash.0);
+    }
+
+    Ok(json!({ "transaction": transaction_json(builder.build()?) }))
+}
+
+/// Fills swap and liquidity requests, initial mint included.
+pub fn batch(deployment_path: &str, request_path: &str) -> Result<Value, Error> {
+    let deployment = deployment(read_json(deployment_path)?);
+    let request: BatchJson = read_json(request_path)?;
+    let pool = Pool::from_cells(live_cell(request.info), live_cell(request.pool))?;
+
+    let operations = request
+        .operations
+        .into_iter()
+        .map(|operation| match operation {
+            OperationJson::Swap => Operation::Swap,
+            OperationJson::Liquidity => Operation::Liquidity,
+        })
+        .collect();
+    let settlement = match request.settlement {
+        None | Some(SettlementJson::Sequential) => Settlement::Sequential,
+        Some(SettlementJson::UniformPrice) => Settlement::UniformPrice,
+        Some(SettlementJson::MidPrice) => Settlement::MidPrice,
+    };
+
+    let mut builder = BatchBuilder::new(&deployment, &pool)
+        .fee(request.fee.map_or(0, |fee| fee.value()))
+        .settlement(settlement)
+        .operations(operations);
+    for cell in request.matchers {
+        builder = builder.matcher(live_cell(cell));
+    }
+    for swap in request.swaps {
+        let mut request = SwapRequest::new(live_cell(swap.cell), swap.user_lock.into())?;
+        if let Some(lock) = swap.referrer_lock {
+            request = request.referrer_lock(lock.into())?;
+        }
+        builder = builder.swap(request);
+    }
+    for liquidity in request.liquidity {
+        builder = builder.liquidity(LiquidityRequest::new(
+            live_cell(liquidity.cell),
+            liquidity.user_lock.into(),
+        )?);
+    }
+    for cell_dep in request.cell_deps {
+        builder = builder.cell_dep(cell_dep.into());
+    }
+
+    let batch = builder.build()?;
+    Ok(json!({
+        "transaction": transaction_json(batch.tx),
+        "state": state_json(&batch.state),
+    }))
+}
+
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, Error> {
+    let content = fs::read_to_string(path).map_err(|err| Error::Io(path.to_owned(), err))?;
+    serde_json::from_str(&content).map_err(|err| Error::Json(path.to_owned(), err))
+}
+
+fn deployment(json: DeploymentJson) -> Deployment {
+    Deployment {
+        info_type:          script_ref(json.info_type),
+        info_lock:          script_ref(json.info_lock),
+        swap_lock:          script_ref(json.swap_lock),
+        liquidity_lock:     script_ref(json.liquidity_lock),
+        registry_type:      script_ref(json.registry_type),
+        sudt:               script_ref(json.sudt),
+        registry_type_args: json.registry_type_args.into_bytes(),
+    }
+}
+
+fn script_ref(json: ScriptRefJson) -> ScriptRef {
+    ScriptRef {
+        code_hash: json.code_hash.pack(),
+        hash_type: core::ScriptHashType::from(json.hash_type),
+        cell_dep:  json.cell_dep.into(),
+    }
+}
+
+fn live_cell(json: CellJson) -> LiveCell {
+    LiveCell::new(
+        packed::OutPoint::from(json.out_point),
+        packed::CellOutput::from(json.output),
+        json.data.into_bytes(),
+    )
+}
+
+fn transaction_json(tx: core::TransactionView) -> Value {
+    serde_json::to_value(TransactionView::from(tx)).expect("serialize to json")
+}
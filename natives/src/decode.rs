@@ -0,0 +1,65 @@
+use serde_json::{json, Value};
+use share::blake2b;
+use share::cell::{InfoCellData, LiquidityRequestLockArgs, SwapRequestLockArgs};
+
+use crate::{to_hex, Error};
+
+pub fn info(data: &[u8]) -> Result<Value, Error> {
+    let info = InfoCellData::from_raw(data).map_err(|_| Error::Encoding)?;
+
+    Ok(json!({
+        "ckb_reserve": info.ckb_reserve.to_string(),
+        "sudt_reserve": info.sudt_reserve.to_string(),
+        "total_liquidity": info.total_liquidity.to_string(),
+        "liquidity_sudt_type_hash": to_hex(&info.liquidity_sudt_type_hash),
+        "creator_lock_hash": info.creator_lock_hash.as_ref().map(|hash| to_hex(hash)),
+        "admin_lock_hash": info.admin_lock_hash.as_ref().map(|hash| to_hex(hash)),
+        "paused": info.paused,
+        "pending_admin_lock_hash": info.pending_admin_lock_hash.as_ref().map(|hash| to_hex(hash)),
+        "activation_timestamp": info.activation_timestamp,
+        "migration_code_hash": info.migration_code_hash.as_ref().map(|hash| to_hex(hash)),
+    }))
+}
+
+pub fn swap_args(lock_args: &[u8]) -> Result<Value, Error> {
+    let args = SwapRequestLockArgs::from_raw(lock_args).map_err(|_| Error::Encoding)?;
+    let referral = args.referral.map(|referral| {
+        json!({
+            "lock_hash": to_hex(&referral.lock_hash),
+            "bps": referral.bps,
+        })
+    });
+
+    Ok(json!({
+        "sudt_type_hash": to_hex(&args.sudt_type_hash),
+        "user_lock_hash": to_hex(&args.user_lock_hash),
+        "version": args.version,
+        "min_amount_out": args.min_amount_out.to_string(),
+        "tips": args.tips.to_string(),
+        "tips_sudt": args.tips_sudt.to_string(),
+        "info_type_hash": args.info_type_hash.as_ref().map(|hash| to_hex(hash)),
+        "referral": referral,
+    }))
+}
+
+pub fn liquidity_args(lock_args: &[u8]) -> Result<Value, Error> {
+    let args = LiquidityRequestLockArgs::from_raw(lock_args).map_err(|_| Error::Encoding)?;
+
+    Ok(json!({
+        "info_type_hash": to_hex(&args.info_type_hash),
+        "user_lock_hash": to_hex(&args.user_lock_hash),
+        "version": args.version,
+        "amount_0": args.amount_0.to_string(),
+        "amount_1": args.amount_1.to_string(),
+        "tips": args.tips.to_string(),
+        "tips_sudt": args.tips_sudt.to_string(),
+    }))
+}
+
+/// The info lock args bind the info and pool cells to the pool sUDT and to the info cell.
+pub fn info_lock_args(pool_type_hash: [u8; 32], info_type_hash: [u8; 32]) -> Value {
+    let mut args = blake2b!("ckb", pool_type_hash).to_vec();
+    args.extend_from_slice(&info_type_hash);
+
+    json!({ "info_lock_args": to_hex(&args) })
+}
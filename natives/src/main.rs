@@ -1,3 +1,232 @@
+//! Offline tooling for the DEX: decodes cell data and request lock args, computes the info lock
+//! args of a pool, quotes trades and builds unsigned transactions. Nothing is sent to a node, the
+//! cells a transaction spends are given in JSON files and the transaction is printed as JSON.
+
+mod build;
+mod decode;
+mod quote;
+
+use std::env;
+use std::fmt;
+use std::process;
+
+const USAGE: &str = "Usage:
+    natives decode-info <info_data>
+    natives decode-swap-args <lock_args>
+    natives decode-liquidity-args <lock_args>
+    natives info-lock-args <pool_type_hash> <info_type_hash>
+    natives quote swap <info_data> <ckb|sudt> <amount_in> [referral_bps]
+    natives quote swap-for-output <info_data> <ckb|sudt> <amount_out> [referral_bps]
+    natives quote mint <info_data> <ckb_available> <sudt_available>
+    natives quote burn <info_data> <liquidity>
+    natives build create-pool <deployment.json> <create_pool.json>
+    natives build batch <deployment.json> <batch.json>
+
+Bytes and hashes are hex, with or without the 0x prefix.";
+
+#[derive(Debug)]
+pub enum Error {
+    Usage,
+    InvalidHex(String),
+    InvalidNumber(String),
+    /// Cell data or lock args can not be decoded.
+    Encoding,
+    /// The amounts overflow or drain the pool.
+    NoQuote,
+    Io(String, std::io::Error),
+    Json(String, serde_json::Error),
+    Sdk(dex_sdk::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Usage => write!(f, "{}", USAGE),
+            Error::InvalidHex(value) => write!(f, "invalid hex: {}", value),
+            Error::InvalidNumber(value) => write!(f, "invalid number: {}", value),
+            Error::Encoding => write!(f, "can not decode the cell data or lock args"),
+            Error::NoQuote => write!(f, "the trade can not be filled by the pool"),
+            Error::Io(path, err) => write!(f, "{}: {}", path, err),
+            Error::Json(path, err) => write!(f, "{}: {}", path, err),
+            Error::Sdk(err) => write!(f, "can not build the transaction: {}", err),
+        }
+    }
+}
+
+impl From<dex_sdk::Error> for Error {
+    fn from(err: dex_sdk::Error) -> Self {
+        Error::Sdk(err)
+    }
+}
+
 fn main() {
-    println!("Hello, world!");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match run(&args) {
+        Ok(output) => println!(
+            "{}",
+            serde_json::to_string_pretty(&output).expect("serialize to json")
+        ),
+        Err(Error::Usage) => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+fn run(args: &[&str]) -> Result<serde_json::Value, Error> {
+    match args {
+        ["decode-info", data] => decode::info(&parse_hex(data)?),
+        ["decode-swap-args", lock_args] => decode::swap_args(&parse_hex(lock_args)?),
+        ["decode-liquidity-args", lock_args] => decode::liquidity_args(&parse_hex(lock_args)?),
+        ["info-lock-args", pool_type_hash, info_type_hash] => Ok(decode::info_lock_args(
+            parse_hash(pool_type_hash)?,
+            parse_hash(info_type_hash)?,
+        )),
+        ["quote", args @ ..] => quote::run(args),
+        ["build", "create-pool", deployment, request] => build::create_pool(deployment, request),
+        ["build", "batch", deployment, request] => build::batch(deployment, request),
+        _ => Err(Error::Usage),
+    }
+}
+
+pub fn parse_hex(value: &str) -> Result<Vec<u8>, Error> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    hex::decode(digits).map_err(|_| Error::InvalidHex(value.to_owned()))
+}
+
+pub fn parse_hash(value: &str) -> Result<[u8; 32], Error> {
+    let bytes = parse_hex(value)?;
+    if bytes.len() != 32 {
+        return Err(Error::InvalidHex(value.to_owned()));
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&bytes);
+    Ok(hash)
+}
+
+pub fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::InvalidNumber(value.to_owned()))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dex_sdk::quote::{self, Asset};
+    use dex_sdk::request::encode_swap_args;
+    use dex_sdk::PoolState;
+    use share::blake2b;
+    use share::cell::SwapRequestLockArgs;
+
+    fn info_data(ckb_reserve: u128, sudt_reserve: u128, total_liquidity: u128) -> String {
+        let mut data = ckb_reserve.to_le_bytes().to_vec();
+        data.extend_from_slice(&sudt_reserve.to_le_bytes());
+        data.extend_from_slice(&total_liquidity.to_le_bytes());
+        data.extend_from_slice(&[7u8; 32]);
+        to_hex(&data)
+    }
+
+    #[test]
+    fn decode_info() {
+        let output = run(&["decode-info", &info_data(1_000, 2_000, 3_000)]).unwrap();
+
+        assert_eq!(output["ckb_reserve"], "1000");
+        assert_eq!(output["sudt_reserve"], "2000");
+        assert_eq!(output["total_liquidity"], "3000");
+        assert_eq!(output["liquidity_sudt_type_hash"], to_hex(&[7u8; 32]));
+        assert_eq!(output["admin_lock_hash"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn decode_swap_args() {
+        let args = SwapRequestLockArgs {
+            sudt_type_hash: [1u8; 32],
+            user_lock_hash: [2u8; 32],
+            version:        1,
+            min_amount_out: 100,
+            tips:           5,
+            tips_sudt:      0,
+            info_type_hash: None,
+            referral:       None,
+        };
+        let output = run(&["decode-swap-args", &to_hex(&encode_swap_args(&args))]).unwrap();
+
+        assert_eq!(output["sudt_type_hash"], to_hex(&[1u8; 32]));
+        assert_eq!(output["user_lock_hash"], to_hex(&[2u8; 32]));
+        assert_eq!(output["min_amount_out"], "100");
+        assert_eq!(output["tips"], "5");
+        assert_eq!(output["referral"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn info_lock_args() {
+        let output = run(&["info-lock-args", &to_hex(&[3u8; 32]), &to_hex(&[4u8; 32])]).unwrap();
+
+        let mut args = blake2b!("ckb", [3u8; 32]).to_vec();
+        args.extend_from_slice(&[4u8; 32]);
+        assert_eq!(output["info_lock_args"], to_hex(&args));
+    }
+
+    #[test]
+    fn quote_swap() {
+        let state = PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        };
+        let info = info_data(10_000_000, 10_000_000, 10_000_000);
+        let output = run(&["quote", "swap", &info, "ckb", "1000000", "500"]).unwrap();
+
+        let quote = quote::swap(&state, Asset::Ckb, 1_000_000, 500).unwrap();
+        assert_eq!(output["amount_out"], quote.amount_out.to_string());
+        assert_eq!(output["referral"], quote.referral.to_string());
+        assert_eq!(
+            output["state"]["sudt_reserve"],
+            quote.state.sudt_reserve.to_string()
+        );
+    }
+
+    #[test]
+    fn quote_swap_with_invalid_referral_bps() {
+        let info = info_data(10_000_000, 10_000_000, 10_000_000);
+
+        assert!(run(&["quote", "swap", &info, "ckb", "1000000", "10000"]).is_ok());
+        assert!(matches!(
+            run(&["quote", "swap", &info, "ckb", "1000000", "10001"]),
+            Err(Error::InvalidNumber(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_hex() {
+        assert!(matches!(
+            run(&["decode-info", "0xzz"]),
+            Err(Error::InvalidHex(_))
+        ));
+        assert!(matches!(
+            run(&["decode-info", "0x123"]),
+            Err(Error::InvalidHex(_))
+        ));
+        assert!(matches!(
+            parse_hash(&to_hex(&[0u8; 31])),
+            Err(Error::InvalidHex(_))
+        ));
+        assert!(matches!(
+            run(&["decode-info", &to_hex(&[0u8; 79])]),
+            Err(Error::Encoding)
+        ));
+    }
 }
@@ -0,0 +1,116 @@
+use dex_sdk::quote::{self, Asset, SwapQuote};
+use dex_sdk::{PoolState, BPS_BASE};
+use serde_json::{json, Value};
+use share::cell::InfoCellData;
+
+use crate::{parse_hex, parse_number, Error};
+
+pub fn run(args: &[&str]) -> Result<Value, Error> {
+    match args {
+        ["swap", info_data, asset_in, amount_in, referral_bps @ ..] => {
+            let state = pool_state(info_data)?;
+            let quote = quote::swap(
+                &state,
+                parse_asset(asset_in)?,
+                parse_number(amount_in)?,
+                parse_referral_bps(referral_bps)?,
+            )
+            .ok_or(Error::NoQuote)?;
+            Ok(swap_json(&quote))
+        }
+        ["swap-for-output", info_data, asset_in, amount_out, referral_bps @ ..] => {
+            let state = pool_state(info_data)?;
+            let quote = quote::swap_for_output(
+                &state,
+                parse_asset(asset_in)?,
+                parse_number(amount_out)?,
+                parse_referral_bps(referral_bps)?,
+            )
+            .ok_or(Error::NoQuote)?;
+            Ok(swap_json(&quote))
+        }
+        ["mint", info_data, ckb_available, sudt_available] => {
+            let state = pool_state(info_data)?;
+            let quote = quote::mint(
+                &state,
+                parse_number(ckb_available)?,
+                parse_number(sudt_available)?,
+            )
+            .ok_or(Error::NoQuote)?;
+
+            Ok(json!({
+                "ckb_injected": quote.ckb_injected.to_string(),
+                "sudt_injected": quote.sudt_injected.to_string(),
+                "liquidity": quote.liquidity.to_string(),
+                "state": state_json(&quote.state),
+            }))
+        }
+        ["burn", info_data, liquidity] => {
+            let state = pool_state(info_data)?;
+            let quote = quote::burn(&state, parse_number(liquidity)?).ok_or(Error::NoQuote)?;
+
+            Ok(json!({
+                "ckb_removed": quote.ckb_removed.to_string(),
+                "sudt_removed": quote.sudt_removed.to_string(),
+                "state": state_json(&quote.state),
+            }))
+        }
+        _ => Err(Error::Usage),
+    }
+}
+
+fn pool_state(info_data: &str) -> Result<PoolState, Error> {
+    let info = InfoCellData::from_raw(&parse_hex(info_data)?).map_err(|_| Error::Encoding)?;
+
+    Ok(PoolState {
+        ckb_reserve:     info.ckb_reserve,
+        sudt_reserve:    info.sudt_reserve,
+        total_liquidity: info.total_liquidity,
+    })
+}
+
+fn parse_asset(value: &str) -> Result<Asset, Error> {
+    match value {
+        "ckb" => Ok(Asset::Ckb),
+        "sudt" => Ok(Asset::Sudt),
+        _ => Err(Error::Usage),
+    }
+}
+
+/// The referral share is taken out of the fee, at most all of it.
+fn parse_referral_bps(args: &[&str]) -> Result<u16, Error> {
+    match args {
+        [] => Ok(0),
+        [value] => {
+            let bps: u16 = parse_number(value)?;
+            if bps as u128 > BPS_BASE {
+                return Err(Error::InvalidNumber((*value).to_owned()));
+            }
+
+            Ok(bps)
+        }
+        _ => Err(Error::Usage),
+    }
+}
+
+fn swap_json(quote: &SwapQuote) -> Value {
+    json!({
+        "asset_in": match quote.asset_in {
+            Asset::Ckb => "ckb",
+            Asset::Sudt => "sudt",
+        },
+        "amount_in": quote.amount_in.to_string(),
+        "amount_out": quote.amount_out.to_string(),
+        "referral": quote.referral.to_string(),
+        "price_impact": quote.price_impact,
+        "state": state_json(&quote.state),
+    })
+}
+
+pub fn state_json(state: &PoolState) -> Value {
+    json!({
+        "ckb_reserve": state.ckb_reserve.to_string(),
+        "sudt_reserve": state.sudt_reserve.to_string(),
+        "total_liquidity": state.total_liquidity.to_string(),
+    })
+}
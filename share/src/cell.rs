@@ -2,14 +2,22 @@ use core::result::Result;
 
 use ckb_std::error::SysError as Error;
 
-use crate::{check_args_len, decode_u128, decode_u64, decode_u8};
+use crate::{check_args_len, decode_u128, decode_u16, decode_u64, decode_u8};
 
 const LIQUIDITY_ORDER_ARGS_LEN: usize = 113;
 const SWAP_ORDER_ARGS_LEN: usize = 105;
+const SWAP_ORDER_BOUND_ARGS_LEN: usize = 137;
+const SWAP_ORDER_REFERRAL_ARGS_LEN: usize = 139;
+const SWAP_ORDER_BOUND_REFERRAL_ARGS_LEN: usize = 171;
+const REFERRAL_ARGS_LEN: usize = 34;
+const MAX_REFERRAL_BPS: u16 = 10_000;
 const INFO_CELL_DATA_LEN: usize = 80;
+const EXTENDED_INFO_CELL_DATA_LEN: usize = 145;
+const GOVERNED_INFO_CELL_DATA_LEN: usize = 217;
 const SUDT_AMOUNT_DATA_LEN: usize = 16;
+const REGISTRY_CELL_DATA_LEN: usize = 96;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LiquidityRequestLockArgs {
     pub info_type_hash: [u8; 32],
     pub user_lock_hash: [u8; 32],
@@ -46,7 +54,7 @@ impl LiquidityRequestLockArgs {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SwapRequestLockArgs {
     pub sudt_type_hash: [u8; 32],
     pub user_lock_hash: [u8; 32],
@@ -54,11 +62,36 @@ pub struct SwapRequestLockArgs {
     pub min_amount_out: u128,
     pub tips:           u64,
     pub tips_sudt:      u128,
+    /// The info type hash of the only pool allowed to fill the request, any
+    /// pool trading `sudt_type_hash` can fill it if not set.
+    pub info_type_hash: Option<[u8; 32]>,
+    pub referral:       Option<Referral>,
 }
 
 impl SwapRequestLockArgs {
     pub fn from_raw(cell_raw_data: &[u8]) -> Result<Self, Error> {
-        check_args_len(cell_raw_data.len(), SWAP_ORDER_ARGS_LEN)?;
+        let (is_bound, has_referral) = match cell_raw_data.len() {
+            SWAP_ORDER_ARGS_LEN => (false, false),
+            SWAP_ORDER_BOUND_ARGS_LEN => (true, false),
+            SWAP_ORDER_REFERRAL_ARGS_LEN => (false, true),
+            SWAP_ORDER_BOUND_REFERRAL_ARGS_LEN => (true, true),
+            _ => return Err(Error::Encoding),
+        };
+
+        let info_type_hash = if is_bound {
+            let mut info_type_hash = [0u8; 32];
+            info_type_hash.copy_from_slice(&cell_raw_data[105..137]);
+            Some(info_type_hash)
+        } else {
+            None
+        };
+
+        let referral = if has_referral {
+            let referral_start = cell_raw_data.len() - REFERRAL_ARGS_LEN;
+            Some(Referral::from_raw(&cell_raw_data[referral_start..])?)
+        } else {
+            None
+        };
 
         let mut sudt_type_hash = [0u8; 32];
         sudt_type_hash.copy_from_slice(&cell_raw_data[0..32]);
@@ -76,21 +109,103 @@ impl SwapRequestLockArgs {
             min_amount_out,
             tips,
             tips_sudt,
+            info_type_hash,
+            referral,
         })
     }
+
+    /// Tips are paid to the matcher, only the rest of `amount_sent` is swapped. The CKB tips
+    /// apply to CKB sent, the sUDT tips to sUDT sent.
+    pub fn amount_swapped(&self, is_ckb_in: bool, amount_sent: u128) -> Option<u128> {
+        let tips = if is_ckb_in {
+            self.tips as u128
+        } else {
+            self.tips_sudt
+        };
+
+        amount_sent.checked_sub(tips)
+    }
+}
+
+/// A front-end taking a share of the pool fee paid by a swap request.
+#[derive(Debug, Clone)]
+pub struct Referral {
+    pub lock_hash: [u8; 32],
+    /// Basis points of the pool fee paid to the referrer.
+    pub bps:       u16,
 }
 
-#[derive(Debug)]
+impl Referral {
+    fn from_raw(cell_raw_data: &[u8]) -> Result<Self, Error> {
+        check_args_len(cell_raw_data.len(), REFERRAL_ARGS_LEN)?;
+
+        let mut lock_hash = [0u8; 32];
+        lock_hash.copy_from_slice(&cell_raw_data[0..32]);
+        let bps = decode_u16(&cell_raw_data[32..34])?;
+
+        if bps > MAX_REFERRAL_BPS {
+            return Err(Error::Encoding);
+        }
+
+        Ok(Referral { lock_hash, bps })
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct InfoCellData {
     pub ckb_reserve:              u128,
     pub sudt_reserve:             u128,
     pub total_liquidity:          u128,
     pub liquidity_sudt_type_hash: [u8; 32],
+    /// The lock hash receiving the info and pool cell capacity when the pool is
+    /// closed, pools created without it can never be closed.
+    pub creator_lock_hash:        Option<[u8; 32]>,
+    /// The lock hash allowed to pause and unpause the pool.
+    pub admin_lock_hash:          Option<[u8; 32]>,
+    pub paused:                   bool,
+    /// The admin lock hash proposed by the current admin, it can only replace
+    /// the current one once the governance delay has passed.
+    pub pending_admin_lock_hash:  Option<[u8; 32]>,
+    /// The median time in seconds from which the pending admin can be installed or the pool
+    /// migrated, zero when nothing is pending.
+    pub activation_timestamp:     u64,
+    /// The info type code hash the admin proposed to migrate the pool to.
+    pub migration_code_hash:      Option<[u8; 32]>,
 }
 
 impl InfoCellData {
     pub fn from_raw(cell_raw_data: &[u8]) -> Result<InfoCellData, Error> {
-        check_args_len(cell_raw_data.len(), INFO_CELL_DATA_LEN)?;
+        let data_len = cell_raw_data.len();
+        if data_len != EXTENDED_INFO_CELL_DATA_LEN && data_len != GOVERNED_INFO_CELL_DATA_LEN {
+            check_args_len(data_len, INFO_CELL_DATA_LEN)?;
+        }
+
+        let (creator_lock_hash, admin_lock_hash, paused) = if data_len > INFO_CELL_DATA_LEN {
+            let paused = match decode_u8(&cell_raw_data[144..145])? {
+                0 => false,
+                1 => true,
+                _ => return Err(Error::Encoding),
+            };
+
+            (
+                decode_optional_hash(&cell_raw_data[80..112]),
+                decode_optional_hash(&cell_raw_data[112..144]),
+                paused,
+            )
+        } else {
+            (None, None, false)
+        };
+
+        let (pending_admin_lock_hash, activation_timestamp, migration_code_hash) =
+            if data_len == GOVERNED_INFO_CELL_DATA_LEN {
+                (
+                    decode_optional_hash(&cell_raw_data[145..177]),
+                    decode_u64(&cell_raw_data[177..185])?,
+                    decode_optional_hash(&cell_raw_data[185..217]),
+                )
+            } else {
+                (None, 0, None)
+            };
 
         let ckb_reserve = decode_u128(&cell_raw_data[..16])?;
         let sudt_reserve = decode_u128(&cell_raw_data[16..32])?;
@@ -103,11 +218,17 @@ impl InfoCellData {
             sudt_reserve,
             total_liquidity,
             liquidity_sudt_type_hash,
+            creator_lock_hash,
+            admin_lock_hash,
+            paused,
+            pending_admin_lock_hash,
+            activation_timestamp,
+            migration_code_hash,
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SUDTAmountData {
     pub sudt_amount: u128,
 }
@@ -120,3 +241,42 @@ impl SUDTAmountData {
         Ok(SUDTAmountData { sudt_amount })
     }
 }
+
+/// A node of the sorted pool registry list, the head node has an all zero key and
+/// the tail node has an all zero next key.
+#[derive(Debug, Clone)]
+pub struct RegistryCellData {
+    pub key:                 [u8; 32],
+    pub next_key:            [u8; 32],
+    pub info_type_code_hash: [u8; 32],
+}
+
+impl RegistryCellData {
+    pub fn from_raw(cell_raw_data: &[u8]) -> Result<Self, Error> {
+        check_args_len(cell_raw_data.len(), REGISTRY_CELL_DATA_LEN)?;
+
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&cell_raw_data[0..32]);
+        let mut next_key = [0u8; 32];
+        next_key.copy_from_slice(&cell_raw_data[32..64]);
+        let mut info_type_code_hash = [0u8; 32];
+        info_type_code_hash.copy_from_slice(&cell_raw_data[64..96]);
+
+        Ok(RegistryCellData {
+            key,
+            next_key,
+            info_type_code_hash,
+        })
+    }
+}
+
+/// An all zero hash in the extended info cell data means the field is not set.
+fn decode_optional_hash(data: &[u8]) -> Option<[u8; 32]> {
+    if data.iter().all(|byte| *byte == 0) {
+        return None;
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(data);
+    Some(hash)
+}
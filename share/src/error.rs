@@ -1,5 +1,253 @@
+//! The error catalog of all the contracts.
+//!
+//! Every contract owns a range of exit codes, so an exit code identifies the error whichever
+//! script failed. The codes 1 to 9 are shared by all the contracts for the syscall errors.
+
+use core::ops::RangeInclusive;
+
+use ckb_std::error::SysError;
+
 #[repr(i8)]
 #[derive(Debug)]
 pub enum HelperError {
     MissingTypeScript = 1,
 }
+
+pub struct ErrorEntry {
+    pub contract: &'static str,
+    pub code:     i8,
+    pub name:     &'static str,
+}
+
+pub const COMMON_CODES: RangeInclusive<i8> = 1..=9;
+
+const COMMON_TABLE: &[ErrorEntry] = &[
+    common_entry(1, "IndexOutOfBound"),
+    common_entry(2, "ItemMissing"),
+    common_entry(3, "LengthNotEnough"),
+    common_entry(4, "Encoding"),
+    common_entry(5, "MissingTypeScript"),
+    common_entry(6, "UnknownSysError"),
+];
+
+const fn common_entry(code: i8, name: &'static str) -> ErrorEntry {
+    ErrorEntry {
+        contract: "common",
+        code,
+        name,
+    }
+}
+
+macro_rules! error_catalog {
+    ($(
+        $(#[$meta:meta])*
+        $error:ident($contract:literal, $codes:expr) {
+            $($variant:ident $(= $code:expr)?,)*
+        }
+    )*) => {
+        $(
+            $(#[$meta])*
+            #[repr(i8)]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum $error {
+                IndexOutOfBound = 1,
+                ItemMissing,
+                LengthNotEnough,
+                Encoding,
+                MissingTypeScript = 5,
+                UnknownSysError,
+                $($variant $(= $code)?,)*
+            }
+
+            impl $error {
+                pub const CONTRACT: &'static str = $contract;
+                pub const CODES: RangeInclusive<i8> = $codes;
+                pub const TABLE: &'static [ErrorEntry] = &[$(ErrorEntry {
+                    contract: $contract,
+                    code:     $error::$variant as i8,
+                    name:     stringify!($variant),
+                },)*];
+            }
+
+            impl From<SysError> for $error {
+                fn from(err: SysError) -> Self {
+                    match err {
+                        SysError::IndexOutOfBound => Self::IndexOutOfBound,
+                        SysError::ItemMissing => Self::ItemMissing,
+                        SysError::LengthNotEnough(_) => Self::LengthNotEnough,
+                        SysError::Encoding => Self::Encoding,
+                        SysError::Unknown(_) => Self::UnknownSysError,
+                    }
+                }
+            }
+
+            impl From<HelperError> for $error {
+                fn from(err: HelperError) -> Self {
+                    match err {
+                        HelperError::MissingTypeScript => Self::MissingTypeScript,
+                    }
+                }
+            }
+        )*
+
+        /// The shared codes followed by the codes of every contract.
+        pub const ERROR_TABLE: &[&[ErrorEntry]] = &[COMMON_TABLE, $($error::TABLE,)*];
+    };
+}
+
+error_catalog! {
+    /// Errors of the info type script.
+    InfoTypeError("info-type-script", 10..=99) {
+        MoreThanOneLiquidityPool = 10,
+        MintInitialLiquidityFailed,
+        LiquidityArgsUserLockHashMismatch,
+        InvalidTypeID,
+        VersionDiff,
+        SUDTTypeHashMismatch = 15,
+        UnknownLiquidity,
+        SwapAmountLessThanMin,
+        SellSUDTFailed,
+        BuySUDTFailed,
+        InvalidChangeCell = 20,
+        InvalidTotalLiquidity,
+        InvalidSwapOutputData,
+        BurnLiquidityFailed,
+        SUDTGotAmountDiff,
+        CKBGotAmountDiff = 25,
+        SwapInputSUDTAmountEqZero,
+        CKBReserveAmountDiff,
+        SUDTReserveAmountDiff,
+        InvalidCKBReserve,
+        InvalidSUDTReserve = 30,
+        CKBInjectAmountDiff,
+        SUDTInjectAmountDiff,
+        LiquidityPoolTokenDiff,
+        InfoLockArgsFrontHalfMismatch,
+        InfoLockArgsSecondHalfMismatch = 35,
+        InvalidInfoLockCountInCellDeps,
+        InvalidInfoLockCountInOutput,
+        CellDataLenTooShort,
+        InfoCreationCellLockHashMismatch,
+        LiquidityArgsInfoTypeHashMismatch = 40,
+        InfoCapacityDiff,
+        InvalidOutputPoolCapacity,
+        InvalidInfoInData,
+        LiquiditySUDTTypeHashMismatch,
+        AddLiquiditySUDTOutLockHashMismatch = 45,
+        InvalidMinCkbInject,
+        InvalidMinSUDTInject,
+        InvalidMinCkbGot,
+        InvalidMinSUDTGot,
+        InvalidInfoTypeArgsLen = 50,
+        InputCellMoreThanOne,
+        AddLiquidityCkbOutLockHashMismatch,
+        SUDTCellDataLenTooShort,
+        CKBCellDataIsNotEmpty,
+        InvalidOutputLockHash = 55,
+        RequestCapcityEqSUDTCapcity,
+        InvalidOutputTypeHash,
+        InvalidSwapOutputCapacity,
+        InvalidPoolOutputData,
+        NoInfoLockInCellDeps = 60,
+        MintLiquidityEqZero,
+        InjectAmountEqZero,
+        SwapArgsInfoTypeHashMismatch,
+        PoolNotEmpty,
+        MissingCreatorLockHash = 65,
+        PoolCellNotDestroyed,
+        CloseNotAuthorized,
+        InfoParamsChanged,
+        MigrationNotAuthorized,
+        InvalidMigratedInfoData = 70,
+        InvalidMigratedPoolCell,
+        PoolPaused,
+        MissingAdminLockHash,
+        AdminNotAuthorized,
+        InfoLockHashChanged = 75,
+        InvalidGovernanceChange,
+        GovernanceDelayNotPassed,
+        PoolNotRegistered,
+        PoolAlreadyRegistered,
+        RegistryInfoTypeMismatch = 80,
+        InvalidReferralOutput,
+        InvalidTips,
+        InvalidCKBSurplus,
+        InvalidSUDTSurplus,
+        InvalidDirectSwap = 85,
+        UnorderedRequests,
+        UniformPriceMismatch,
+        UniformSwapWithReferral,
+        InvalidOperations,
+        PoolNotUnregistered = 90,
+        InvalidProposalTimestamp,
+        InvalidMigrationTarget,
+        SwapAmountAfterTipsEqZero,
+        PendingGovernanceChange,
+    }
+
+    /// Errors of the info lock script.
+    InfoLockError("info-lock-script", 100..=109) {
+        InvalidInfoCellCount = 100,
+        InfoLockArgsFrontHalfMismatch,
+        InfoLockArgsSecondHalfMismatch,
+    }
+
+    /// Errors of the swap request lock script.
+    SwapLockError("swap-lock-script", 110..=127) {
+        InvalidOutputLockHash = 110,
+        RequestCapcityEqSUDTCapcity,
+        InvalidOutputTypeHash,
+        InvalidCapacity,
+        SwapAmountLessThanMin,
+        InputSUDTAmountEqZero = 115,
+        InvalidOutputData,
+        InfoTypeHashMismatch,
+        InvalidCancelLockScript,
+        DynLockLoadFailed,
+        InvalidCancelSignature = 120,
+        UnknownSwap,
+        UnsupportedCancelHashType,
+    }
+
+    /// Errors of the liquidity request lock script.
+    LiquidityLockError("liquidity-lock-script", -128..=-101) {
+        NoInfoCell = -128,
+        UnknownLiquidity,
+        InvalidOutputLockHash,
+        InvalidOutputTypeHash = -125,
+        InvalidCapacity,
+        InvalidOutputData,
+        MintLiquidityEqZero,
+        InvalidMinCkbInject,
+        InvalidMinSUDTInject = -120,
+        InvalidMinCkbGot,
+        InvalidMinSUDTGot,
+        InvalidCancelLockScript,
+        DynLockLoadFailed,
+        InvalidCancelSignature = -115,
+        UnsupportedCancelHashType,
+    }
+
+    /// Errors of the registry type script.
+    RegistryTypeError("registry-type-script", -100..=-81) {
+        InvalidRegistryCellCount = -100,
+        InvalidRegistryTypeArgsLen,
+        InvalidTypeID,
+        InvalidRegistryHead,
+        InvalidRegistryLink,
+        InfoTypeCodeHashChanged = -95,
+        RegistryKeyNotSorted,
+        RegistryLockHashChanged,
+        InvalidRegistryCapacity,
+        NoPoolCreation,
+        NoPoolClose,
+    }
+}
+
+/// Maps an exit code back to its error, the contract is `common` for the shared codes.
+pub fn lookup_error(code: i8) -> Option<&'static ErrorEntry> {
+    ERROR_TABLE
+        .iter()
+        .flat_map(|table| table.iter())
+        .find(|entry| entry.code == code)
+}
@@ -48,6 +48,98 @@ pub fn decode_u64(data: &[u8]) -> Result<u64, SysError> {
     Ok(u64::from_le_bytes(buf))
 }
 
+/// The witness of the info input holds the swap request count, optionally followed by the
+/// matcher cell count which defaults to one, the settlement of the swaps and the operations. A
+/// matcher count of zero marks a direct swap, which has no request cells. Returns
+/// `(swap_count, matcher_count)`.
+pub fn decode_batch_witness(data: &[u8]) -> Result<(usize, usize), SysError> {
+    match data.len() {
+        8 => Ok((decode_u64(data)? as usize, 1)),
+        len if len == 16 || len >= 24 => Ok((
+            decode_u64(&data[0..8])? as usize,
+            decode_u64(&data[8..16])? as usize,
+        )),
+        _ => Err(SysError::Encoding),
+    }
+}
+
+/// How the swaps of a batch are settled, sequentially by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Settlement {
+    /// Each swap trades against the pool in turn.
+    Sequential,
+    /// All the swaps clear at a single price, only their imbalance trades against the pool.
+    UniformPrice,
+    /// The swaps are matched against each other at the pool mid price, without fee, and only
+    /// the residual trades against the pool.
+    MidPrice,
+}
+
+pub fn decode_batch_settlement(data: &[u8]) -> Result<Settlement, SysError> {
+    if data.len() < 24 {
+        return Ok(Settlement::Sequential);
+    }
+
+    match decode_u64(&data[16..24])? {
+        0 => Ok(Settlement::Sequential),
+        1 => Ok(Settlement::UniformPrice),
+        2 => Ok(Settlement::MidPrice),
+        _ => Err(SysError::Encoding),
+    }
+}
+
+/// A step of a batch, filling the next swap request or the next liquidity request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Operation {
+    Swap = 0,
+    Liquidity = 1,
+}
+
+impl Operation {
+    pub fn from_u8(value: u8) -> Result<Self, SysError> {
+        match value {
+            0 => Ok(Operation::Swap),
+            1 => Ok(Operation::Liquidity),
+            _ => Err(SysError::Encoding),
+        }
+    }
+}
+
+/// The operations follow the settlement in the witness of the info input, one byte each. The
+/// requests of each group are still filled in their canonical order, the operations only
+/// interleave the groups. Empty when all the swaps are filled before the liquidity requests.
+pub fn decode_batch_operations(data: &[u8]) -> &[u8] {
+    if data.len() <= 24 {
+        return &[];
+    }
+
+    &data[24..]
+}
+
+/// The position of a request in a batch is fixed by the hash of the info input out point
+/// followed by the request out point, both molecule encoded. The swap requests and the liquidity
+/// requests are each sorted by this key in ascending order.
+pub fn request_order_key(info_out_point: &[u8], request_out_point: &[u8]) -> [u8; 32] {
+    let mut res = [0u8; 32];
+    let mut blake2b = hash::new_blake2b();
+
+    blake2b.update(info_out_point);
+    blake2b.update(request_out_point);
+    blake2b.finalize(&mut res);
+    res
+}
+
+pub fn decode_u16(data: &[u8]) -> Result<u16, SysError> {
+    if data.len() != 2 {
+        return Err(SysError::Encoding);
+    }
+
+    let mut buf = [0u8; 2];
+    buf.copy_from_slice(data);
+    Ok(u16::from_le_bytes(buf))
+}
+
 pub fn decode_u8(data: &[u8]) -> Result<u8, SysError> {
     if data.len() != 1 {
         return Err(SysError::Encoding);
@@ -190,13 +190,19 @@ impl SwapRequestLockArgsBuilder {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct InfoCellBuilder {
     capacity:                 u64,
     ckb_reserve:              u128,
     sudt_reserve:             u128,
     total_liquidity:          u128,
     liquidity_sudt_type_hash: [u8; 32],
+    creator_lock_hash:        Option<[u8; 32]>,
+    admin_lock_hash:          Option<[u8; 32]>,
+    paused:                   bool,
+    pending_admin_lock_hash:  Option<[u8; 32]>,
+    activation_timestamp:     u64,
+    migration_code_hash:      Option<[u8; 32]>,
 }
 
 impl InfoCellBuilder {
@@ -225,6 +231,36 @@ impl InfoCellBuilder {
         self
     }
 
+    pub fn creator_lock_hash(mut self, creator_lock_hash: [u8; 32]) -> Self {
+        self.creator_lock_hash = Some(creator_lock_hash);
+        self
+    }
+
+    pub fn admin_lock_hash(mut self, admin_lock_hash: [u8; 32]) -> Self {
+        self.admin_lock_hash = Some(admin_lock_hash);
+        self
+    }
+
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    pub fn pending_admin_lock_hash(mut self, pending_admin_lock_hash: [u8; 32]) -> Self {
+        self.pending_admin_lock_hash = Some(pending_admin_lock_hash);
+        self
+    }
+
+    pub fn activation_timestamp(mut self, activation_timestamp: u64) -> Self {
+        self.activation_timestamp = activation_timestamp;
+        self
+    }
+
+    pub fn migration_code_hash(mut self, code_hash: [u8; 32]) -> Self {
+        self.migration_code_hash = Some(code_hash);
+        self
+    }
+
     pub fn build(self) -> InfoCell {
         let info_data = InfoCellData::new_builder()
             .sudt_reserve(self.sudt_reserve.pack())
@@ -233,9 +269,54 @@ impl InfoCellBuilder {
             .liquidity_sudt_type_hash(self.liquidity_sudt_type_hash.pack())
             .build();
 
+        // Keep the legacy data layout unless an extended field is set
+        let mut data = info_data.as_slice().to_vec();
+        if self.creator_lock_hash.is_some()
+            || self.admin_lock_hash.is_some()
+            || self.paused
+            || self.pending_admin_lock_hash.is_some()
+            || self.activation_timestamp != 0
+            || self.migration_code_hash.is_some()
+        {
+            data.extend_from_slice(&self.creator_lock_hash.unwrap_or_default());
+            data.extend_from_slice(&self.admin_lock_hash.unwrap_or_default());
+            data.push(self.paused as u8);
+        }
+        if self.pending_admin_lock_hash.is_some()
+            || self.activation_timestamp != 0
+            || self.migration_code_hash.is_some()
+        {
+            data.extend_from_slice(&self.pending_admin_lock_hash.unwrap_or_default());
+            data.extend_from_slice(&self.activation_timestamp.to_le_bytes());
+            data.extend_from_slice(&self.migration_code_hash.unwrap_or_default());
+        }
+
         InfoCell {
             capacity: Capacity::shannons(self.capacity),
-            data:     info_data.as_bytes(),
+            data:     Bytes::from(data),
+        }
+    }
+}
+
+pub struct RegistryCell {
+    pub capacity: Capacity,
+    pub data:     Bytes,
+}
+
+impl RegistryCell {
+    pub fn new(
+        capacity: u64,
+        key: [u8; 32],
+        next_key: [u8; 32],
+        info_type_code_hash: [u8; 32],
+    ) -> Self {
+        let mut data = key.to_vec();
+        data.extend_from_slice(&next_key);
+        data.extend_from_slice(&info_type_code_hash);
+
+        RegistryCell {
+            capacity: Capacity::shannons(capacity),
+            data:     Bytes::from(data),
         }
     }
 }
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+
+use share::error::{
+    lookup_error, InfoLockError, InfoTypeError, LiquidityLockError, RegistryTypeError,
+    SwapLockError, COMMON_CODES, ERROR_TABLE,
+};
+
+#[test]
+fn error_codes_in_contract_ranges() {
+    let ranges = vec![
+        (InfoTypeError::TABLE, InfoTypeError::CODES),
+        (InfoLockError::TABLE, InfoLockError::CODES),
+        (SwapLockError::TABLE, SwapLockError::CODES),
+        (LiquidityLockError::TABLE, LiquidityLockError::CODES),
+        (RegistryTypeError::TABLE, RegistryTypeError::CODES),
+    ];
+
+    for (idx, (table, codes)) in ranges.iter().enumerate() {
+        let mut others = vec![COMMON_CODES];
+        others.extend(ranges[idx + 1..].iter().map(|(_, codes)| codes.clone()));
+        for other in others {
+            assert!(codes.end() < other.start() || other.end() < codes.start());
+        }
+
+        for entry in table.iter() {
+            assert!(
+                codes.contains(&entry.code),
+                "{} {}",
+                entry.contract,
+                entry.name
+            );
+        }
+    }
+}
+
+#[test]
+fn error_codes_unique() {
+    let mut codes = HashSet::new();
+    for entry in ERROR_TABLE.iter().flat_map(|table| table.iter()) {
+        assert!(codes.insert(entry.code), "duplicated code {}", entry.code);
+    }
+}
+
+#[test]
+fn lookup_error_by_code() {
+    let entry = lookup_error(SwapLockError::SwapAmountLessThanMin as i8).unwrap();
+    assert_eq!(entry.contract, "swap-lock-script");
+    assert_eq!(entry.name, "SwapAmountLessThanMin");
+
+    let entry = lookup_error(InfoTypeError::Encoding as i8).unwrap();
+    assert_eq!(entry.contract, "common");
+    assert_eq!(entry.name, "Encoding");
+}
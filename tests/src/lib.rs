@@ -6,6 +6,11 @@ use std::str::FromStr;
 use ckb_tool::ckb_types::bytes::Bytes;
 
 pub mod cell_builder;
+#[cfg(test)]
+mod error_catalog_test;
+pub mod mock_chain;
+#[cfg(test)]
+mod quote_test;
 #[allow(dead_code)]
 mod schema;
 #[cfg(test)]
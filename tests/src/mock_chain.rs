@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use ckb_testtool::context::Context;
+use ckb_tool::ckb_error::Error as VerificationError;
+use ckb_tool::ckb_types::core::{Cycle, HeaderBuilder, HeaderView, TransactionView};
+use ckb_tool::ckb_types::packed::*;
+use ckb_tool::ckb_types::{bytes::Bytes, prelude::*};
+use dex_sdk::LiveCell;
+
+const SINCE_RELATIVE_FLAG: u64 = 1 << 63;
+const SINCE_METRIC_MASK: u64 = 0b0110_0000 << 56;
+const SINCE_METRIC_BLOCK_NUMBER: u64 = 0;
+const SINCE_METRIC_TIMESTAMP: u64 = 0b0100_0000 << 56;
+const SINCE_VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+
+#[derive(Debug)]
+pub enum MockChainError {
+    /// The input is not a live cell of the chain.
+    UnknownInput(OutPoint),
+    /// The input or cell dep has been consumed.
+    DeadCell(OutPoint),
+    /// The `since` of the input is not satisfied by the block the transaction would be
+    /// committed in.
+    Immature(OutPoint),
+    /// Epoch based `since` values are not modelled.
+    UnsupportedSince(u64),
+    Verification(VerificationError),
+}
+
+/// A chain stand-in on top of a `Context`. Every committed transaction is verified by the
+/// scripts and mined alone in a new block, its inputs are consumed and its outputs become live
+/// cells. Cells can be created out of thin air, they are committed in the tip block.
+pub struct MockChain {
+    context:        Context,
+    max_cycles:     Cycle,
+    block_interval: u64,
+    headers:        Vec<HeaderView>,
+    live_cells:     Vec<(LiveCell, u64)>,
+    dead_cells:     HashSet<OutPoint>,
+    transactions:   Vec<(u64, TransactionView)>,
+}
+
+impl MockChain {
+    pub fn new(max_cycles: Cycle) -> Self {
+        let genesis = HeaderBuilder::default().build();
+        let mut context = Context::default();
+        context.insert_header(genesis.clone());
+
+        MockChain {
+            context,
+            max_cycles,
+            block_interval: 8_000,
+            headers: vec![genesis],
+            live_cells: Vec::new(),
+            dead_cells: HashSet::new(),
+            transactions: Vec::new(),
+        }
+    }
+
+    /// The milliseconds between two blocks, 8 seconds by default.
+    pub fn set_block_interval(&mut self, block_interval: u64) {
+        self.block_interval = block_interval;
+    }
+
+    /// Scripts are deployed through the context, their cells can only be used as cell deps.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    pub fn tip(&self) -> &HeaderView {
+        self.headers.last().expect("genesis")
+    }
+
+    pub fn header(&self, number: u64) -> Option<&HeaderView> {
+        self.headers.get(number as usize)
+    }
+
+    /// The header of the block a live cell was committed in.
+    pub fn cell_header(&self, out_point: &OutPoint) -> Option<&HeaderView> {
+        self.live_cells
+            .iter()
+            .find(|(cell, _)| &cell.out_point == out_point)
+            .and_then(|(_, number)| self.header(*number))
+    }
+
+    pub fn create_cell(&mut self, output: CellOutput, data: Bytes) -> LiveCell {
+        let out_point = self.context.create_cell(output.clone(), data.clone());
+        let tip = self.tip().clone();
+        self.context
+            .link_cell_with_block(out_point.clone(), tip.hash(), 0);
+
+        let cell = LiveCell::new(out_point, output, data);
+        self.live_cells.push((cell.clone(), tip.number()));
+        cell
+    }
+
+    /// Mines `count` empty blocks.
+    pub fn mine(&mut self, count: u64) {
+        for _ in 0..count {
+            self.mine_block(self.block_interval);
+        }
+    }
+
+    /// Mines an empty block `duration` milliseconds after the tip.
+    pub fn advance_time(&mut self, duration: u64) {
+        self.mine_block(duration);
+    }
+
+    pub fn commit(&mut self, tx: &TransactionView) -> Result<Cycle, MockChainError> {
+        let tip = self.tip().clone();
+        let number = tip.number() + 1;
+        let timestamp = tip.timestamp() + self.block_interval;
+
+        let mut spent = HashSet::new();
+        for input in tx.inputs().into_iter() {
+            let out_point = input.previous_output();
+            if self.dead_cells.contains(&out_point) || !spent.insert(out_point.clone()) {
+                return Err(MockChainError::DeadCell(out_point));
+            }
+
+            let committed_in = self
+                .live_cells
+                .iter()
+                .find(|(cell, _)| cell.out_point == out_point)
+                .map(|(_, block)| *block)
+                .ok_or_else(|| MockChainError::UnknownInput(out_point.clone()))?;
+            let committed_at = self.headers[committed_in as usize].timestamp();
+            if !since_satisfied(
+                input.since().unpack(),
+                (committed_in, committed_at),
+                (number, timestamp),
+            )? {
+                return Err(MockChainError::Immature(out_point));
+            }
+        }
+        for cell_dep in tx.cell_deps().into_iter() {
+            if self.dead_cells.contains(&cell_dep.out_point()) {
+                return Err(MockChainError::DeadCell(cell_dep.out_point()));
+            }
+        }
+
+        let cycles = self
+            .context
+            .verify_tx(tx, self.max_cycles)
+            .map_err(MockChainError::Verification)?;
+
+        let header = self.mine_block(self.block_interval);
+        self.live_cells
+            .retain(|(cell, _)| !spent.contains(&cell.out_point));
+        self.dead_cells.extend(spent);
+
+        for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+            let out_point = OutPoint::new(tx.hash(), index as u32);
+            self.context.create_cell_with_out_point(
+                out_point.clone(),
+                output.clone(),
+                data.clone(),
+            );
+            self.context
+                .link_cell_with_block(out_point.clone(), header.hash(), 1);
+            self.live_cells
+                .push((LiveCell::new(out_point, output, data), number));
+        }
+        self.transactions.push((number, tx.clone()));
+
+        Ok(cycles)
+    }
+
+    pub fn live_cell(&self, out_point: &OutPoint) -> Option<&LiveCell> {
+        self.live_cells
+            .iter()
+            .map(|(cell, _)| cell)
+            .find(|cell| &cell.out_point == out_point)
+    }
+
+    /// The live cells locked by `lock`, oldest first.
+    pub fn cells_by_lock(&self, lock: &Script) -> Vec<LiveCell> {
+        self.live_cells
+            .iter()
+            .map(|(cell, _)| cell)
+            .filter(|cell| &cell.output.lock() == lock)
+            .cloned()
+            .collect()
+    }
+
+    /// The live cells typed by `type_`, oldest first.
+    pub fn cells_by_type(&self, type_: &Script) -> Vec<LiveCell> {
+        self.live_cells
+            .iter()
+            .map(|(cell, _)| cell)
+            .filter(|cell| cell.output.type_().to_opt().as_ref() == Some(type_))
+            .cloned()
+            .collect()
+    }
+
+    /// The committed transactions along with the header of their block, in order.
+    pub fn transactions(&self) -> impl Iterator<Item = (&HeaderView, &TransactionView)> {
+        self.transactions
+            .iter()
+            .map(move |(number, tx)| (&self.headers[*number as usize], tx))
+    }
+
+    fn mine_block(&mut self, duration: u64) -> HeaderView {
+        let tip = self.tip();
+        let header = HeaderBuilder::default()
+            .parent_hash(tip.hash())
+            .number((tip.number() + 1).pack())
+            .timestamp((tip.timestamp() + duration).pack())
+            .build();
+
+        self.context.insert_header(header.clone());
+        self.headers.push(header.clone());
+        header
+    }
+}
+
+/// Checks the block number or the timestamp of the committing block against `since`, the
+/// relative values count from the block of the input. Timestamps are in milliseconds in headers
+/// and in seconds in `since`.
+fn since_satisfied(
+    since: u64,
+    input: (u64, u64),
+    block: (u64, u64),
+) -> Result<bool, MockChainError> {
+    if since == 0 {
+        return Ok(true);
+    }
+
+    let value = since & SINCE_VALUE_MASK;
+    let relative = since & SINCE_RELATIVE_FLAG != 0;
+    let (start_number, start_timestamp) = if relative { input } else { (0, 0) };
+
+    match since & SINCE_METRIC_MASK {
+        SINCE_METRIC_BLOCK_NUMBER => Ok(block.0 >= start_number + value),
+        SINCE_METRIC_TIMESTAMP => Ok(block.1 / 1000 >= start_timestamp / 1000 + value),
+        _ => Err(MockChainError::UnsupportedSince(since)),
+    }
+}
@@ -0,0 +1,81 @@
+use dex_sdk::math::{ckb_in_for_sudt_out, ckb_out, sudt_in_for_ckb_out, sudt_out};
+use dex_sdk::quote::{self, Asset};
+use dex_sdk::PoolState;
+
+const MAX_RESERVE: u128 = 40;
+
+type AmountFn = fn(u128, &PoolState) -> Option<u128>;
+
+fn small_states() -> impl Iterator<Item = PoolState> {
+    (1..=MAX_RESERVE).flat_map(|ckb_reserve| {
+        (1..=MAX_RESERVE).map(move |sudt_reserve| PoolState {
+            ckb_reserve,
+            sudt_reserve,
+            total_liquidity: 1,
+        })
+    })
+}
+
+#[test]
+fn reverse_quote_is_minimal_input_for_small_reserves() {
+    for state in small_states() {
+        let directions: [(AmountFn, AmountFn, u128); 2] = [
+            (ckb_in_for_sudt_out, sudt_out, state.sudt_reserve),
+            (sudt_in_for_ckb_out, ckb_out, state.ckb_reserve),
+        ];
+
+        for (reverse, forward, reserve_out) in directions.iter() {
+            for target in 0..=reserve_out + 2 {
+                let amount_in = match reverse(target, &state) {
+                    Some(amount_in) => amount_in,
+                    None => {
+                        assert!(target > *reserve_out, "{:?} {}", state, target);
+                        continue;
+                    }
+                };
+
+                assert!(forward(amount_in, &state).unwrap() >= target);
+                if amount_in > 1 {
+                    assert!(
+                        forward(amount_in - 1, &state).unwrap() < target,
+                        "{:?} {} {}",
+                        state,
+                        target,
+                        amount_in
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn swap_for_output_matches_forward_quote() {
+    for state in small_states() {
+        for asset_in in [Asset::Ckb, Asset::Sudt].iter() {
+            for target in 1..=state.ckb_reserve.min(state.sudt_reserve) {
+                let reverse = quote::swap_for_output(&state, *asset_in, target, 0).unwrap();
+                let forward = quote::swap(&state, *asset_in, reverse.amount_in, 0).unwrap();
+
+                assert_eq!(reverse, forward);
+                assert!(reverse.amount_out >= target);
+            }
+        }
+    }
+}
+
+#[test]
+fn reverse_quote_for_large_reserves() {
+    let state = PoolState {
+        ckb_reserve:     1_000_000_000_000_000_000,
+        sudt_reserve:    u128::max_value() / 1_000,
+        total_liquidity: 1,
+    };
+
+    assert_eq!(ckb_in_for_sudt_out(0, &state), Some(1));
+    assert_eq!(ckb_in_for_sudt_out(state.sudt_reserve + 1, &state), None);
+
+    let amount_in = sudt_in_for_ckb_out(state.ckb_reserve / 2, &state).unwrap();
+    assert!(ckb_out(amount_in, &state).unwrap() >= state.ckb_reserve / 2);
+    assert!(ckb_out(amount_in - 1, &state).unwrap() < state.ckb_reserve / 2);
+}
@@ -1,57 +1,208 @@
 use super::*;
 
-const ERR_OUTPUT_INFO_LOCK_ARGS_FIRST_HALF_DIFF: i8 = 30;
-const ERR_OUTPUT_INFO_LOCK_ARGS_SECOND_HALF_DIFF: i8 = 31;
-const ERR_INVALID_INFO_LOCK_COUNT_IN_OUTPUT: i8 = 33;
-const ERR_OUTPUT_POOL_CELL_DATA_LEN_TOO_SHORT: i8 = 34;
-const ERR_OUTPUT_CELLS_LOCK_HASH_DIFF: i8 = 35;
+use dex_sdk::builder::batch_witness;
+use dex_sdk::Settlement;
+use error_decoder::{decode_failure, ScriptGroup};
+use share::error::InfoTypeError;
+
+const ERR_BUY_SUDT_FAILED: i8 = InfoTypeError::BuySUDTFailed as i8;
+const ERR_OUTPUT_INFO_LOCK_ARGS_FIRST_HALF_DIFF: i8 =
+    InfoTypeError::InfoLockArgsFrontHalfMismatch as i8;
+const ERR_OUTPUT_INFO_LOCK_ARGS_SECOND_HALF_DIFF: i8 =
+    InfoTypeError::InfoLockArgsSecondHalfMismatch as i8;
+const ERR_INVALID_INFO_LOCK_COUNT_IN_OUTPUT: i8 = InfoTypeError::InvalidInfoLockCountInOutput as i8;
+const ERR_OUTPUT_POOL_CELL_DATA_LEN_TOO_SHORT: i8 = InfoTypeError::CellDataLenTooShort as i8;
+const ERR_OUTPUT_CELLS_LOCK_HASH_DIFF: i8 = InfoTypeError::InfoCreationCellLockHashMismatch as i8;
+const ERR_POOL_NOT_EMPTY: i8 = InfoTypeError::PoolNotEmpty as i8;
+const ERR_MISSING_CREATOR_LOCK_HASH: i8 = InfoTypeError::MissingCreatorLockHash as i8;
+const ERR_CLOSE_NOT_AUTHORIZED: i8 = InfoTypeError::CloseNotAuthorized as i8;
+const ERR_MIGRATION_NOT_AUTHORIZED: i8 = InfoTypeError::MigrationNotAuthorized as i8;
+const ERR_INVALID_MIGRATED_INFO_DATA: i8 = InfoTypeError::InvalidMigratedInfoData as i8;
+const ERR_INVALID_MIGRATION_TARGET: i8 = InfoTypeError::InvalidMigrationTarget as i8;
+const ERR_LIQUIDITY_SUDT_TYPE_HASH_MISMATCH: i8 =
+    InfoTypeError::LiquiditySUDTTypeHashMismatch as i8;
+const ERR_POOL_PAUSED: i8 = InfoTypeError::PoolPaused as i8;
+const ERR_ADMIN_NOT_AUTHORIZED: i8 = InfoTypeError::AdminNotAuthorized as i8;
+const ERR_GOVERNANCE_DELAY_NOT_PASSED: i8 = InfoTypeError::GovernanceDelayNotPassed as i8;
+const ERR_INVALID_PROPOSAL_TIMESTAMP: i8 = InfoTypeError::InvalidProposalTimestamp as i8;
+const ERR_POOL_NOT_REGISTERED: i8 = InfoTypeError::PoolNotRegistered as i8;
+const ERR_POOL_ALREADY_REGISTERED: i8 = InfoTypeError::PoolAlreadyRegistered as i8;
+const ERR_POOL_NOT_UNREGISTERED: i8 = InfoTypeError::PoolNotUnregistered as i8;
+const ERR_INVALID_REFERRAL_OUTPUT: i8 = InfoTypeError::InvalidReferralOutput as i8;
+const ERR_INVALID_CKB_SURPLUS: i8 = InfoTypeError::InvalidCKBSurplus as i8;
+const ERR_PENDING_GOVERNANCE_CHANGE: i8 = InfoTypeError::PendingGovernanceChange as i8;
+const ERR_SWAP_AMOUNT_AFTER_TIPS_EQ_ZERO: i8 = InfoTypeError::SwapAmountAfterTipsEqZero as i8;
+
+const SINCE_ABSOLUTE_TIMESTAMP: u64 = 0x4000_0000_0000_0000;
+const INFO_IN_TIMESTAMP: u64 = 1_600_000_000;
+const ACTIVATION_TIMESTAMP: u64 = INFO_IN_TIMESTAMP + 172_800;
 
 // #####################
 // Pool Creation Tests
 // #####################
+/// The registry before the test pool is inserted.
+fn empty_registry() -> Vec<RegistryCell> {
+    vec![RegistryCell::new(
+        REGISTRY_CAPACITY,
+        [0u8; 32],
+        [0u8; 32],
+        info_type_code_hash(),
+    )]
+}
+
+/// The registry after the test pool is inserted.
+fn registry_with_pool() -> Vec<RegistryCell> {
+    vec![
+        RegistryCell::new(
+            REGISTRY_CAPACITY,
+            [0u8; 32],
+            *SUDT_TYPE_HASH,
+            info_type_code_hash(),
+        ),
+        RegistryCell::new(
+            REGISTRY_CAPACITY,
+            *SUDT_TYPE_HASH,
+            [0u8; 32],
+            info_type_code_hash(),
+        ),
+    ]
+}
+
 test_contract!(
     info_creation_success,
     {
-        let sudt_data: Uint128 = 1500u128.pack();
-        let input_out_point =
-            sudt_input_out_point(21000, user_lock_args(0), None, sudt_data.as_bytes());
-        let input_out_point_tx_hash: [u8; 32] = input_out_point.tx_hash().unpack();
+        let (context, tx) = PoolTxBuilder::creation()
+            .registry(empty_registry(), registry_with_pool())
+            .build();
 
-        let input = Inputs::new_sudt(SudtCell::new_with_out_point(21000, 1500, input_out_point));
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
 
-        let hash = blake2b!(input_out_point_tx_hash, 0u64.to_le_bytes());
-        let type_id = Bytes::from(hash.to_vec());
+        println!("cycle used {:?}", cycle);
 
-        let mut hash = blake2b!("ckb", *SUDT_TYPE_HASH).to_vec();
-        let mut hash_1 = info_cell_type_hash(type_id.clone()).to_vec();
-        hash.append(&mut hash_1);
-        assert_eq!(hash.len(), 64);
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
 
-        let output_0 = Outputs::new_info(
-            InfoCellBuilder::default()
-                .capacity(1000)
-                .ckb_reserve(500)
-                .sudt_reserve(500)
-                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
-                .build(),
-        )
-        .custom_lock_args(Bytes::from(hash.clone()))
-        .custom_type_args(type_id);
+test_contract!(
+    info_creation_with_pending_admin,
+    {
+        let (context, tx) = PoolTxBuilder::creation()
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .pending_admin_lock_hash(user_lock_hash(7)),
+            )
+            .registry(empty_registry(), registry_with_pool())
+            .build();
 
-        let output_1 =
-            Outputs::new_pool(SudtCell::new(21000, 1500)).custom_lock_args(Bytes::from(hash));
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_PENDING_GOVERNANCE_CHANGE, 0, false, false)
+        );
 
-        let (mut context, tx) = build_test_context(vec![input], vec![output_0, output_1]);
-        let tx = context.complete_tx(tx);
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
 
-        // let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
-        // assert_error_eq!(err, tx_error(, 0));
+test_contract!(
+    info_creation_with_migration_code_hash,
+    {
+        let (context, tx) = PoolTxBuilder::creation()
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .migration_code_hash(upgraded_info_code_hash()),
+            )
+            .registry(empty_registry(), registry_with_pool())
+            .build();
 
-        let cycle = context
-            .verify_tx(&tx, MAX_CYCLES)
-            .expect("pass verification");
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_PENDING_GOVERNANCE_CHANGE, 0, false, false)
+        );
 
-        println!("cycle used {:?}", cycle);
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    info_creation_with_activation_timestamp,
+    {
+        let (context, tx) = PoolTxBuilder::creation()
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP),
+            )
+            .registry(empty_registry(), registry_with_pool())
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_PENDING_GOVERNANCE_CHANGE, 0, false, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    info_creation_without_registry,
+    {
+        let (context, tx) = PoolTxBuilder::creation().build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_POOL_NOT_REGISTERED, 0, false, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    info_creation_pool_already_registered,
+    {
+        let (context, tx) = PoolTxBuilder::creation()
+            .registry(
+                vec![RegistryCell::new(
+                    REGISTRY_CAPACITY,
+                    *SUDT_TYPE_HASH,
+                    [0u8; 32],
+                    info_type_code_hash(),
+                )],
+                vec![
+                    RegistryCell::new(
+                        REGISTRY_CAPACITY,
+                        *SUDT_TYPE_HASH,
+                        [0xffu8; 32],
+                        info_type_code_hash(),
+                    ),
+                    RegistryCell::new(
+                        REGISTRY_CAPACITY,
+                        [0xffu8; 32],
+                        [0u8; 32],
+                        info_type_code_hash(),
+                    ),
+                ],
+            )
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_POOL_ALREADY_REGISTERED, 0, false, false));
 
         (context, tx)
     },
@@ -342,15 +493,15 @@ test_contract!(
                 .ckb_reserve(50)
                 .sudt_reserve(50)
                 .total_liquidity(50)
-                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
                 .build(),
         )
         .custom_lock_args(Bytes::from(hash.clone()));
         let output_1 = Outputs::new_pool(SudtCell::new(POOL_CAPACITY + 50, 50))
             .custom_lock_args(Bytes::from(hash));
         let output_2 = Outputs::new_matcher(FreeCell::new(150));
-        let output_3 =
-            Outputs::new_sudt(SudtCell::new(100, 50)).custom_type_args(liquidity_sudt_type_args());
+        let output_3 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 50))
+            .custom_type_args(liquidity_sudt_type_args());
 
         let (mut context, tx) = build_test_context(vec![input_0, input_1, input_2, input_3], vec![
             output_0, output_1, output_2, output_3,
@@ -413,14 +564,14 @@ test_contract!(
                 .ckb_reserve(101)
                 .sudt_reserve(100)
                 .total_liquidity(201)
-                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
                 .build(),
         )
         .custom_lock_args(Bytes::from(hash.clone()));
         let output_1 = Outputs::new_pool(SudtCell::new(POOL_CAPACITY + 101, 100))
             .custom_lock_args(Bytes::from(hash));
         let output_2 = Outputs::new_matcher(FreeCell::new(150));
-        let output_3 = Outputs::new_sudt(SudtCell::new(100, 101))
+        let output_3 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 101))
             .custom_type_args(liquidity_sudt_type_args())
             .custom_lock_args(Bytes::from(9999usize.to_le_bytes().to_vec()));
         let output_4 = Outputs::new_ckb(FreeCell::new(49))
@@ -488,17 +639,17 @@ test_contract!(
                 .ckb_reserve(150)
                 .sudt_reserve(151)
                 .total_liquidity(301)
-                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
                 .build(),
         )
         .custom_lock_args(Bytes::from(hash.clone()));
         let output_1 = Outputs::new_pool(SudtCell::new(POOL_CAPACITY + 150, 151))
             .custom_lock_args(Bytes::from(hash));
         let output_2 = Outputs::new_matcher(FreeCell::new(150));
-        let output_3 = Outputs::new_sudt(SudtCell::new(100, 201))
+        let output_3 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 201))
             .custom_type_args(liquidity_sudt_type_args())
             .custom_lock_args(Bytes::from(9999usize.to_le_bytes().to_vec()));
-        let output_4 = Outputs::new_sudt(SudtCell::new(50, 201))
+        let output_4 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 201))
             .custom_lock_args(Bytes::from(9999usize.to_le_bytes().to_vec()));
 
         let (mut context, tx) = build_test_context(vec![input_0, input_1, input_2, input_3], vec![
@@ -564,7 +715,7 @@ test_contract!(
                 .ckb_reserve(49)
                 .sudt_reserve(49)
                 .total_liquidity(50)
-                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
                 .build(),
         )
         .custom_lock_args(Bytes::from(hash.clone()));
@@ -635,7 +786,7 @@ test_contract!(
                 .ckb_reserve(170)
                 .sudt_reserve(58)
                 .total_liquidity(100)
-                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
                 .build(),
         )
         .custom_lock_args(Bytes::from(hash.clone()));
@@ -704,7 +855,7 @@ test_contract!(
                 .ckb_reserve(66)
                 .sudt_reserve(150)
                 .total_liquidity(100)
-                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
                 .build(),
         )
         .custom_lock_args(Bytes::from(hash.clone()));
@@ -733,3 +884,1057 @@ test_contract!(
     false,
     "info-typescript-sim"
 );
+
+test_contract!(
+    burn_all_liquidity_success,
+    {
+        let mut hash = blake2b!("ckb", *SUDT_TYPE_HASH).to_vec();
+        let mut hash_1 = info_cell_type_hash(info_type_args(0)).to_vec();
+        hash.append(&mut hash_1);
+        assert_eq!(hash.len(), 64);
+
+        let input_0 = Inputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(1000)
+                .total_liquidity(100)
+                .sudt_reserve(100)
+                .ckb_reserve(100)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()))
+        .custom_witness(witness_args_input_type(0));
+
+        let input_1 = Inputs::new_pool(SudtCell::new(POOL_CAPACITY + 100, 100))
+            .custom_lock_args(Bytes::from(hash.clone()));
+        let input_2 = Inputs::new_matcher(FreeCell::new(100));
+
+        let liquidity_in_lock_args = LiquidityRequestLockArgsBuilder::default()
+            .user_lock_hash(user_lock_hash(9999))
+            .version(1)
+            .sudt_min(100)
+            .ckb_min(100)
+            .info_type_hash(info_cell_type_hash(info_type_args(0)))
+            .tips(0)
+            .tips_sudt(0)
+            .build();
+        let input_3 = Inputs::new_liquidity(LiquidityRequestCell::new(SUDT_CAPACITY * 2, 100))
+            .custom_lock_args(liquidity_in_lock_args.as_bytes())
+            .custom_type_args(liquidity_sudt_type_args());
+
+        let output_0 = Outputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(INFO_CAPACITY)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()));
+        let output_1 =
+            Outputs::new_pool(SudtCell::new(POOL_CAPACITY, 0)).custom_lock_args(Bytes::from(hash));
+        let output_2 = Outputs::new_matcher(FreeCell::new(150));
+        let output_3 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 100))
+            .custom_lock_args(user_lock_args(9999));
+        let output_4 = Outputs::new_ckb(FreeCell::new(SUDT_CAPACITY + 100))
+            .custom_lock_args(user_lock_args(9999));
+
+        let (mut context, tx) = build_test_context(vec![input_0, input_1, input_2, input_3], vec![
+            output_0, output_1, output_2, output_3, output_4,
+        ]);
+        let tx = context.complete_tx(tx);
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    burn_all_liquidity_changes_liquidity_sudt_type_hash,
+    {
+        let mut hash = blake2b!("ckb", *SUDT_TYPE_HASH).to_vec();
+        let mut hash_1 = info_cell_type_hash(info_type_args(0)).to_vec();
+        hash.append(&mut hash_1);
+        assert_eq!(hash.len(), 64);
+
+        let input_0 = Inputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(1000)
+                .total_liquidity(100)
+                .sudt_reserve(100)
+                .ckb_reserve(100)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()))
+        .custom_witness(witness_args_input_type(0));
+
+        let input_1 = Inputs::new_pool(SudtCell::new(POOL_CAPACITY + 100, 100))
+            .custom_lock_args(Bytes::from(hash.clone()));
+        let input_2 = Inputs::new_matcher(FreeCell::new(100));
+
+        let liquidity_in_lock_args = LiquidityRequestLockArgsBuilder::default()
+            .user_lock_hash(user_lock_hash(9999))
+            .version(1)
+            .sudt_min(100)
+            .ckb_min(100)
+            .info_type_hash(info_cell_type_hash(info_type_args(0)))
+            .tips(0)
+            .tips_sudt(0)
+            .build();
+        let input_3 = Inputs::new_liquidity(LiquidityRequestCell::new(SUDT_CAPACITY * 2, 100))
+            .custom_lock_args(liquidity_in_lock_args.as_bytes())
+            .custom_type_args(liquidity_sudt_type_args());
+
+        let output_0 = Outputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(INFO_CAPACITY)
+                .liquidity_sudt_type_hash(*SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()));
+        let output_1 =
+            Outputs::new_pool(SudtCell::new(POOL_CAPACITY, 0)).custom_lock_args(Bytes::from(hash));
+        let output_2 = Outputs::new_matcher(FreeCell::new(150));
+        let output_3 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 100))
+            .custom_lock_args(user_lock_args(9999));
+        let output_4 = Outputs::new_ckb(FreeCell::new(SUDT_CAPACITY + 100))
+            .custom_lock_args(user_lock_args(9999));
+
+        let (mut context, tx) = build_test_context(vec![input_0, input_1, input_2, input_3], vec![
+            output_0, output_1, output_2, output_3, output_4,
+        ]);
+        let tx = context.complete_tx(tx);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_LIQUIDITY_SUDT_TYPE_HASH_MISMATCH, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+// #####################
+// Pool Close Tests
+// #####################
+/// The close of the test pool signed by `signer`, which gets the capacity back.
+fn close_pool_tx(reserve: u128, signer: usize) -> PoolTxBuilder {
+    let refund_capacity = INFO_CAPACITY + POOL_CAPACITY + reserve as u64;
+
+    PoolTxBuilder::new(reserve)
+        .close()
+        .inputs(vec![
+            Inputs::new_ckb(FreeCell::new(100)).custom_lock_args(user_lock_args(signer))
+        ])
+        .outputs(vec![Outputs::new_ckb(FreeCell::new(refund_capacity + 100))
+            .custom_lock_args(user_lock_args(signer))])
+}
+
+test_contract!(
+    close_pool_success,
+    {
+        let (context, tx) = close_pool_tx(0, 5)
+            .info(InfoCellBuilder::default().creator_lock_hash(user_lock_hash(5)))
+            .registry(registry_with_pool(), empty_registry())
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    close_pool_not_empty,
+    {
+        let (context, tx) = close_pool_tx(100, 5)
+            .info(InfoCellBuilder::default().creator_lock_hash(user_lock_hash(5)))
+            .registry(registry_with_pool(), empty_registry())
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_POOL_NOT_EMPTY, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    close_pool_without_creator_lock_hash,
+    {
+        let (context, tx) = close_pool_tx(0, 5)
+            .registry(registry_with_pool(), empty_registry())
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_MISSING_CREATOR_LOCK_HASH, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    close_pool_without_creator_signature,
+    {
+        let (context, tx) = close_pool_tx(0, 6)
+            .info(InfoCellBuilder::default().creator_lock_hash(user_lock_hash(5)))
+            .registry(registry_with_pool(), empty_registry())
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_CLOSE_NOT_AUTHORIZED, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    close_pool_without_unregistration,
+    {
+        let (context, tx) = close_pool_tx(0, 5)
+            .info(InfoCellBuilder::default().creator_lock_hash(user_lock_hash(5)))
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_POOL_NOT_UNREGISTERED, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+// #####################
+// Pool Migration Tests
+// #####################
+/// A transaction of the test pool signed by the cell at index 2, which pays the fee.
+fn admin_signed_tx(reserve: u128) -> PoolTxBuilder {
+    PoolTxBuilder::new(reserve)
+        .inputs(vec![Inputs::new_matcher(FreeCell::new(100))])
+        .outputs(vec![Outputs::new_matcher(FreeCell::new(50))])
+}
+
+/// The migration of the test pool to the always success type script with args 7.
+fn migrate_pool_tx(reserve: u128) -> PoolTxBuilder {
+    admin_signed_tx(reserve)
+        .info_out(InfoCellBuilder::default())
+        .migrate(user_lock_args(7))
+}
+
+fn upgraded_info_code_hash() -> [u8; 32] {
+    CellOutput::calc_data_hash(&ALWAYS_SUCCESS).unpack()
+}
+
+/// A migration proposed by the admin of `admin_idx`.
+fn migration_proposal(admin_idx: usize, code_hash: [u8; 32]) -> InfoCellBuilder {
+    InfoCellBuilder::default()
+        .admin_lock_hash(user_lock_hash(admin_idx))
+        .migration_code_hash(code_hash)
+        .activation_timestamp(ACTIVATION_TIMESTAMP)
+}
+
+test_contract!(
+    migrate_pool_success,
+    {
+        let (context, tx) = migrate_pool_tx(0)
+            .info(migration_proposal(2, upgraded_info_code_hash()))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | ACTIVATION_TIMESTAMP)
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    migrate_pool_without_admin_signature,
+    {
+        let (context, tx) = migrate_pool_tx(0)
+            .info(migration_proposal(5, upgraded_info_code_hash()))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | ACTIVATION_TIMESTAMP)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_MIGRATION_NOT_AUTHORIZED, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    migrate_pool_to_foreign_code,
+    {
+        let (context, tx) = migrate_pool_tx(0)
+            .info(migration_proposal(2, info_type_code_hash()))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | ACTIVATION_TIMESTAMP)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_MIGRATION_TARGET, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    migrate_pool_without_proposal,
+    {
+        let (context, tx) = migrate_pool_tx(0)
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(2)))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | ACTIVATION_TIMESTAMP)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_MIGRATION_TARGET, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    migrate_pool_before_delay,
+    {
+        let (context, tx) = migrate_pool_tx(0)
+            .info(migration_proposal(2, upgraded_info_code_hash()))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | (ACTIVATION_TIMESTAMP - 1))
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_GOVERNANCE_DELAY_NOT_PASSED, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    migrate_pool_with_liquidity,
+    {
+        let (context, tx) = migrate_pool_tx(100)
+            .info(migration_proposal(2, upgraded_info_code_hash()))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | ACTIVATION_TIMESTAMP)
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    migrate_pool_total_liquidity_changed,
+    {
+        let (context, tx) = migrate_pool_tx(0)
+            .info(migration_proposal(2, upgraded_info_code_hash()))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | ACTIVATION_TIMESTAMP)
+            .reserves_out(0, 0, 100)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_INVALID_MIGRATED_INFO_DATA, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+// #####################
+// Admin Tests
+// #####################
+test_contract!(
+    pause_pool_success,
+    {
+        let (context, tx) = admin_signed_tx(100)
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(2)))
+            .info_out(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .paused(true),
+            )
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    pause_pool_without_admin_signature,
+    {
+        let (context, tx) = admin_signed_tx(100)
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(5)))
+            .info_out(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(5))
+                    .paused(true),
+            )
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_ADMIN_NOT_AUTHORIZED, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    swap_when_pool_paused,
+    {
+        let swap_lock_args = SwapRequestLockArgsBuilder::default()
+            .user_lock_hash(user_lock_hash(0))
+            .version(1)
+            .amount_out_min(35)
+            .sudt_type_hash(*SUDT_TYPE_HASH)
+            .build();
+
+        let (context, tx) = PoolTxBuilder::new(100)
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(5))
+                    .paused(true),
+            )
+            .info_capacity(1000)
+            .witness(witness_args_input_type(1))
+            .reserves_out(170, 58, 100)
+            .inputs(vec![
+                Inputs::new_matcher(FreeCell::new(100)),
+                Inputs::new_swap(SwapRequestCell::new_ckb(SUDT_CAPACITY + 70))
+                    .custom_lock_args(swap_lock_args.as_bytes()),
+            ])
+            .outputs(vec![
+                Outputs::new_matcher(FreeCell::new(150)),
+                Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 42))
+                    .custom_lock_args(user_lock_args(0)),
+            ])
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_POOL_PAUSED, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    burn_liquidity_when_pool_paused_success,
+    {
+        let liquidity_in_lock_args = LiquidityRequestLockArgsBuilder::default()
+            .user_lock_hash(user_lock_hash(9999))
+            .version(1)
+            .sudt_min(50)
+            .ckb_min(30)
+            .info_type_hash(info_cell_type_hash(info_type_args(0)))
+            .tips(0)
+            .tips_sudt(0)
+            .build();
+
+        let (context, tx) = PoolTxBuilder::new(100)
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(5))
+                    .paused(true),
+            )
+            .info_capacity(1000)
+            .witness(witness_args_input_type(0))
+            .reserves_out(49, 49, 50)
+            .inputs(vec![
+                Inputs::new_matcher(FreeCell::new(100)),
+                Inputs::new_liquidity(LiquidityRequestCell::new(SUDT_CAPACITY * 2 + 100, 50))
+                    .custom_lock_args(liquidity_in_lock_args.as_bytes())
+                    .custom_type_args(liquidity_sudt_type_args()),
+            ])
+            .outputs(vec![
+                Outputs::new_matcher(FreeCell::new(150)),
+                Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY + 50, 51))
+                    .custom_lock_args(user_lock_args(9999)),
+                Outputs::new_ckb(FreeCell::new(SUDT_CAPACITY + 101))
+                    .custom_lock_args(user_lock_args(9999)),
+            ])
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+// #####################
+// Governance Tests
+// #####################
+/// The info input is committed in a block at `INFO_IN_TIMESTAMP`.
+fn governance_tx() -> PoolTxBuilder {
+    admin_signed_tx(100).info_header(INFO_IN_TIMESTAMP)
+}
+
+test_contract!(
+    propose_admin_change_success,
+    {
+        let (context, tx) = governance_tx()
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(2)))
+            .info_out(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .pending_admin_lock_hash(user_lock_hash(7))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP),
+            )
+            .since(SINCE_ABSOLUTE_TIMESTAMP | INFO_IN_TIMESTAMP)
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    propose_migration_success,
+    {
+        let (context, tx) = governance_tx()
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(2)))
+            .info_out(migration_proposal(2, upgraded_info_code_hash()))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | INFO_IN_TIMESTAMP)
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    propose_admin_change_without_admin_signature,
+    {
+        let (context, tx) = governance_tx()
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(5)))
+            .info_out(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(5))
+                    .pending_admin_lock_hash(user_lock_hash(2))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP),
+            )
+            .since(SINCE_ABSOLUTE_TIMESTAMP | INFO_IN_TIMESTAMP)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_ADMIN_NOT_AUTHORIZED, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    propose_admin_change_before_info_input,
+    {
+        let (context, tx) = governance_tx()
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(2)))
+            .info_out(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .pending_admin_lock_hash(user_lock_hash(7))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP - 1),
+            )
+            .since(SINCE_ABSOLUTE_TIMESTAMP | (INFO_IN_TIMESTAMP - 1))
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_INVALID_PROPOSAL_TIMESTAMP, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    propose_admin_change_with_short_delay,
+    {
+        let (context, tx) = governance_tx()
+            .info(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(2)))
+            .info_out(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .pending_admin_lock_hash(user_lock_hash(7))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP - 1),
+            )
+            .since(SINCE_ABSOLUTE_TIMESTAMP | INFO_IN_TIMESTAMP)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_INVALID_PROPOSAL_TIMESTAMP, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    execute_admin_change_success,
+    {
+        let (context, tx) = governance_tx()
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .pending_admin_lock_hash(user_lock_hash(7))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP),
+            )
+            .info_out(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(7)))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | ACTIVATION_TIMESTAMP)
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    execute_admin_change_before_delay,
+    {
+        let (context, tx) = governance_tx()
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .pending_admin_lock_hash(user_lock_hash(7))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP),
+            )
+            .info_out(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(7)))
+            .since(SINCE_ABSOLUTE_TIMESTAMP | (ACTIVATION_TIMESTAMP - 1))
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_GOVERNANCE_DELAY_NOT_PASSED, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    execute_admin_change_with_relative_since,
+    {
+        // A relative since only tells how long the info input has been untouched
+        let (context, tx) = governance_tx()
+            .info(
+                InfoCellBuilder::default()
+                    .admin_lock_hash(user_lock_hash(2))
+                    .pending_admin_lock_hash(user_lock_hash(7))
+                    .activation_timestamp(ACTIVATION_TIMESTAMP),
+            )
+            .info_out(InfoCellBuilder::default().admin_lock_hash(user_lock_hash(7)))
+            .since(0xC000_0000_0000_0000 | ACTIVATION_TIMESTAMP)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_GOVERNANCE_DELAY_NOT_PASSED, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+// #####################
+// Referral Tests
+// #####################
+/// A swap of 1_000_000 CKB which shares half of the fee with the referrer of lock 8, the case
+/// adds the referral output.
+fn referral_swap_tx() -> PoolTxBuilder {
+    let swap_lock_args = SwapRequestLockArgsBuilder::default()
+        .user_lock_hash(user_lock_hash(0))
+        .version(1)
+        .amount_out_min(900_000)
+        .sudt_type_hash(*SUDT_TYPE_HASH)
+        .build();
+
+    PoolTxBuilder::new(10_000_000)
+        .info_capacity(1000)
+        .witness(witness_args_input_type(1))
+        .reserves_out(10_998_500, 9_093_389, 10_000_000)
+        .inputs(vec![
+            Inputs::new_matcher(FreeCell::new(100)),
+            Inputs::new_swap(SwapRequestCell::new_ckb(SUDT_CAPACITY + 1_000_000)).custom_lock_args(
+                referral_swap_lock_args(swap_lock_args, user_lock_hash(8), 5000),
+            ),
+        ])
+        .outputs(vec![
+            Outputs::new_matcher(FreeCell::new(50)),
+            Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 906_611))
+                .custom_lock_args(user_lock_args(0)),
+        ])
+}
+
+test_contract!(
+    swap_with_referral_success,
+    {
+        let (context, tx) = referral_swap_tx()
+            .outputs(vec![
+                Outputs::new_ckb(FreeCell::new(6_100_000_000)).custom_lock_args(user_lock_args(8))
+            ])
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    swap_with_referral_output_to_other_lock,
+    {
+        let (context, tx) = referral_swap_tx()
+            .outputs(vec![
+                Outputs::new_ckb(FreeCell::new(6_100_000_000)).custom_lock_args(user_lock_args(9))
+            ])
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_REFERRAL_OUTPUT, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+// #####################
+// Matcher Tests
+// #####################
+/// A swap of 1_000_000 CKB paying `tips` CKB and 611 sUDT to two matchers, the second one gets
+/// `matcher_out` CKB.
+fn matcher_swap_tx(tips: u64, matcher_out: u64) -> PoolTxBuilder {
+    let swap_lock_args = SwapRequestLockArgsBuilder::default()
+        .user_lock_hash(user_lock_hash(0))
+        .version(1)
+        .amount_out_min(900_000)
+        .sudt_type_hash(*SUDT_TYPE_HASH)
+        .tips(tips)
+        .tips_sudt(611)
+        .build();
+
+    PoolTxBuilder::new(10_000_000)
+        .witness(witness_args_batch(1, 2))
+        .reserves_out(11_000_000, 9_093_389, 10_000_000)
+        .inputs(vec![
+            Inputs::new_matcher(FreeCell::new(SUDT_CAPACITY)),
+            Inputs::new_matcher(FreeCell::new(100)),
+            Inputs::new_swap(SwapRequestCell::new_ckb(SUDT_CAPACITY + 1_000_000 + tips))
+                .custom_lock_args(swap_lock_args.as_bytes()),
+        ])
+        .outputs(vec![
+            Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 611)),
+            Outputs::new_matcher(FreeCell::new(matcher_out)),
+            Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 906_000))
+                .custom_lock_args(user_lock_args(0)),
+        ])
+}
+
+test_contract!(
+    swap_with_tips_and_two_matchers_success,
+    {
+        let (context, tx) = matcher_swap_tx(10_000, 100 + 10_000).build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    swap_with_info_capacity_drained,
+    {
+        let (context, tx) = matcher_swap_tx(0, 100 + 100)
+            .info_capacity(INFO_CAPACITY + 100)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_CKB_SURPLUS, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    swap_with_info_capacity_drained_to_extra_output,
+    {
+        let (context, tx) = matcher_swap_tx(0, 100 + 100)
+            .info_capacity(INFO_CAPACITY + 100)
+            .build();
+
+        // The drained capacity is moved to an output after the ones filled by the batch
+        let mut outputs = tx.outputs().into_iter().collect::<Vec<_>>();
+        let mut outputs_data = tx.outputs_data().into_iter().collect::<Vec<_>>();
+        outputs[3] = outputs[3]
+            .clone()
+            .as_builder()
+            .capacity(100u64.pack())
+            .build();
+        outputs.push(outputs[3].clone());
+        outputs_data.push(Bytes::new().pack());
+        let tx = tx
+            .as_advanced_builder()
+            .set_outputs(outputs)
+            .set_outputs_data(outputs_data)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_CKB_SURPLUS, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    decode_info_capacity_drained_failure,
+    {
+        let (mut context, tx) = matcher_swap_tx(0, 100 + 100)
+            .info_capacity(INFO_CAPACITY + 100)
+            .build();
+        let deployment = sdk_deployment(&mut context);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        let failure = decode_failure(&err, &tx, &deployment, |out_point| {
+            context.get_cell(out_point)
+        })
+        .expect("script failure");
+        assert_eq!(failure.group, ScriptGroup::InputType(0));
+        assert_eq!(failure.contract, Some("info-type-script"));
+        assert_eq!(failure.name(), Some("InvalidCKBSurplus"));
+        assert!(failure
+            .to_string()
+            .contains(&format!("capacity {} shannons", INFO_CAPACITY + 100)));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+/// A swap request of 1_000_000 CKB which pays it all as tips, leaving nothing to swap.
+fn all_tips_swap_tx() -> PoolTxBuilder {
+    let swap_lock_args = SwapRequestLockArgsBuilder::default()
+        .user_lock_hash(user_lock_hash(0))
+        .version(1)
+        .sudt_type_hash(*SUDT_TYPE_HASH)
+        .tips(1_000_000)
+        .build();
+
+    PoolTxBuilder::new(10_000_000)
+        .inputs(vec![
+            Inputs::new_matcher(FreeCell::new(100)),
+            Inputs::new_swap(SwapRequestCell::new_ckb(SUDT_CAPACITY + 1_000_000))
+                .custom_lock_args(swap_lock_args.as_bytes()),
+        ])
+        .outputs(vec![
+            Outputs::new_matcher(FreeCell::new(100 + 1_000_000)),
+            Outputs::new_ckb(FreeCell::new(SUDT_CAPACITY)).custom_lock_args(user_lock_args(0)),
+        ])
+}
+
+test_contract!(
+    uniform_price_swap_with_all_tips,
+    {
+        let (context, tx) = all_tips_swap_tx()
+            .witness(batch_witness(1, 1, Settlement::UniformPrice, &[]))
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_SWAP_AMOUNT_AFTER_TIPS_EQ_ZERO, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    mid_price_swap_with_all_tips,
+    {
+        let (context, tx) = all_tips_swap_tx()
+            .witness(batch_witness(1, 1, Settlement::MidPrice, &[]))
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_SWAP_AMOUNT_AFTER_TIPS_EQ_ZERO, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+// #####################
+// Direct Swap Tests
+// #####################
+test_contract!(
+    direct_swap_success,
+    {
+        let (context, tx) = PoolTxBuilder::new(10_000_000)
+            .witness(witness_args_batch(0, 0))
+            .reserves_out(11_000_000, 10_000_000 - 906_611, 10_000_000)
+            .inputs(vec![Inputs::new_ckb(FreeCell::new(
+                SUDT_CAPACITY + 1_000_000,
+            ))])
+            .outputs(vec![Outputs::new_sudt(SudtCell::new(
+                SUDT_CAPACITY,
+                906_611,
+            ))
+            .custom_lock_args(user_lock_args(2))])
+            .build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    direct_swap_with_too_much_output,
+    {
+        let (context, tx) = PoolTxBuilder::new(10_000_000)
+            .witness(witness_args_batch(0, 0))
+            .reserves_out(11_000_000, 10_000_000 - 906_612, 10_000_000)
+            .inputs(vec![Inputs::new_ckb(FreeCell::new(
+                SUDT_CAPACITY + 1_000_000,
+            ))])
+            .outputs(vec![Outputs::new_sudt(SudtCell::new(
+                SUDT_CAPACITY,
+                906_612,
+            ))
+            .custom_lock_args(user_lock_args(2))])
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_BUY_SUDT_FAILED, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
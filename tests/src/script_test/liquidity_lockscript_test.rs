@@ -1,5 +1,11 @@
 use super::*;
 
+use share::error::LiquidityLockError;
+
+const ERR_INVALID_OUTPUT_LOCK_HASH: i8 = LiquidityLockError::InvalidOutputLockHash as i8;
+const ERR_INVALID_CAPACITY: i8 = LiquidityLockError::InvalidCapacity as i8;
+const ERR_INVALID_MIN_CKB_GOT: i8 = LiquidityLockError::InvalidMinCkbGot as i8;
+
 test_contract!(
     liquidity_request_cancel_success,
     {
@@ -38,3 +44,204 @@ test_contract!(
     false,
     "info-typescript-sim"
 );
+
+test_contract!(
+    liquidity_request_burn_less_than_min_ckb,
+    {
+        let mut hash = blake2b!("ckb", *SUDT_TYPE_HASH).to_vec();
+        let mut hash_1 = info_cell_type_hash(info_type_args(0)).to_vec();
+        hash.append(&mut hash_1);
+
+        let input_0 = Inputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(1000)
+                .total_liquidity(100)
+                .sudt_reserve(100)
+                .ckb_reserve(100)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()))
+        .custom_witness(witness_args_input_type(0));
+        let input_1 = Inputs::new_pool(SudtCell::new(POOL_CAPACITY + 100, 100))
+            .custom_lock_args(Bytes::from(hash.clone()));
+        let input_2 = Inputs::new_matcher(FreeCell::new(100));
+
+        let liquidity_in_lock_args = LiquidityRequestLockArgsBuilder::default()
+            .user_lock_hash(user_lock_hash(9999))
+            .version(1)
+            .sudt_min(50)
+            .ckb_min(60)
+            .info_type_hash(info_cell_type_hash(info_type_args(0)))
+            .tips(0)
+            .tips_sudt(0)
+            .build();
+        let input_3 = Inputs::new_liquidity(LiquidityRequestCell::new(SUDT_CAPACITY * 2 + 100, 50))
+            .custom_lock_args(liquidity_in_lock_args.as_bytes())
+            .custom_type_args(liquidity_sudt_type_args());
+
+        let output_0 = Outputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(INFO_CAPACITY)
+                .ckb_reserve(49)
+                .sudt_reserve(49)
+                .total_liquidity(50)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()));
+        let output_1 = Outputs::new_pool(SudtCell::new(POOL_CAPACITY + 49, 49))
+            .custom_lock_args(Bytes::from(hash));
+        let output_2 = Outputs::new_matcher(FreeCell::new(150));
+        let output_3 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY + 50, 51))
+            .custom_lock_args(user_lock_args(9999));
+        let output_4 = Outputs::new_ckb(FreeCell::new(SUDT_CAPACITY + 101))
+            .custom_lock_args(user_lock_args(9999));
+
+        let (mut context, tx) = build_test_context(vec![input_0, input_1, input_2, input_3], vec![
+            output_0, output_1, output_2, output_3, output_4,
+        ]);
+        let tx = context.complete_tx(tx);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_MIN_CKB_GOT, 3, true, true));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    liquidity_request_mint_output_lock_hash_diff,
+    {
+        let mut hash = blake2b!("ckb", *SUDT_TYPE_HASH).to_vec();
+        let mut hash_1 = info_cell_type_hash(info_type_args(0)).to_vec();
+        hash.append(&mut hash_1);
+
+        let input_0 = Inputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(1000)
+                .total_liquidity(100)
+                .sudt_reserve(50)
+                .ckb_reserve(50)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()))
+        .custom_witness(witness_args_input_type(0));
+        let input_1 = Inputs::new_pool(SudtCell::new(POOL_CAPACITY + 50, 50))
+            .custom_lock_args(Bytes::from(hash.clone()));
+        let input_2 = Inputs::new_matcher(FreeCell::new(100));
+
+        let liquidity_in_lock_args = LiquidityRequestLockArgsBuilder::default()
+            .user_lock_hash(user_lock_hash(9999))
+            .version(1)
+            .sudt_min(0)
+            .ckb_min(30)
+            .info_type_hash(info_cell_type_hash(info_type_args(0)))
+            .tips(0)
+            .tips_sudt(0)
+            .build();
+        let input_3 = Inputs::new_liquidity(LiquidityRequestCell::new(SUDT_CAPACITY + 100, 50))
+            .custom_lock_args(liquidity_in_lock_args.as_bytes());
+
+        let output_0 = Outputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(INFO_CAPACITY)
+                .ckb_reserve(101)
+                .sudt_reserve(100)
+                .total_liquidity(201)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()));
+        let output_1 = Outputs::new_pool(SudtCell::new(POOL_CAPACITY + 101, 100))
+            .custom_lock_args(Bytes::from(hash));
+        let output_2 = Outputs::new_matcher(FreeCell::new(150));
+        let output_3 = Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 101))
+            .custom_type_args(liquidity_sudt_type_args())
+            .custom_lock_args(user_lock_args(2));
+        let output_4 = Outputs::new_ckb(FreeCell::new(49)).custom_lock_args(user_lock_args(9999));
+
+        let (mut context, tx) = build_test_context(vec![input_0, input_1, input_2, input_3], vec![
+            output_0, output_1, output_2, output_3, output_4,
+        ]);
+        let tx = context.complete_tx(tx);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_OUTPUT_LOCK_HASH, 3, true, true));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    liquidity_request_mint_liquidity_capacity_too_small,
+    {
+        let mut hash = blake2b!("ckb", *SUDT_TYPE_HASH).to_vec();
+        let mut hash_1 = info_cell_type_hash(info_type_args(0)).to_vec();
+        hash.append(&mut hash_1);
+
+        let input_0 = Inputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(1000)
+                .total_liquidity(100)
+                .sudt_reserve(50)
+                .ckb_reserve(50)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()))
+        .custom_witness(witness_args_input_type(0));
+        let input_1 = Inputs::new_pool(SudtCell::new(POOL_CAPACITY + 50, 50))
+            .custom_lock_args(Bytes::from(hash.clone()));
+        let input_2 = Inputs::new_matcher(FreeCell::new(100));
+
+        let liquidity_in_lock_args = LiquidityRequestLockArgsBuilder::default()
+            .user_lock_hash(user_lock_hash(9999))
+            .version(1)
+            .sudt_min(80)
+            .ckb_min(30)
+            .info_type_hash(info_cell_type_hash(info_type_args(0)))
+            .tips(0)
+            .tips_sudt(0)
+            .build();
+        let input_3 =
+            Inputs::new_liquidity(LiquidityRequestCell::new(SUDT_CAPACITY * 2 + 100, 302))
+                .custom_lock_args(liquidity_in_lock_args.as_bytes());
+
+        let output_0 = Outputs::new_info(
+            InfoCellBuilder::default()
+                .capacity(INFO_CAPACITY)
+                .ckb_reserve(150)
+                .sudt_reserve(151)
+                .total_liquidity(301)
+                .liquidity_sudt_type_hash(*LIQUIDITY_SUDT_TYPE_HASH)
+                .build(),
+        )
+        .custom_lock_args(Bytes::from(hash.clone()));
+        let output_1 = Outputs::new_pool(SudtCell::new(POOL_CAPACITY + 150, 151))
+            .custom_lock_args(Bytes::from(hash));
+        let output_2 = Outputs::new_matcher(FreeCell::new(150));
+        let output_3 = Outputs::new_sudt(SudtCell::new(100, 201))
+            .custom_type_args(liquidity_sudt_type_args())
+            .custom_lock_args(user_lock_args(9999));
+        let output_4 =
+            Outputs::new_sudt(SudtCell::new(50, 201)).custom_lock_args(user_lock_args(9999));
+
+        let (mut context, tx) = build_test_context(vec![input_0, input_1, input_2, input_3], vec![
+            output_0, output_1, output_2, output_3, output_4,
+        ]);
+        let tx = context.complete_tx(tx);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_CAPACITY, 3, true, true));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
@@ -1,27 +1,31 @@
 mod info_lockscript_test;
 mod info_typescript_test;
 mod liquidity_lockscript_test;
+mod registry_typescript_test;
+mod sdk_test;
 mod swap_lockscript_test;
 
 use std::collections::HashMap;
 
 use ckb_testtool::builtin::ALWAYS_SUCCESS;
 use ckb_testtool::context::Context;
+use ckb_tool::ckb_crypto::secp::Generator;
 use ckb_tool::ckb_error::assert_error_eq;
 use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::core::TransactionView;
 use ckb_tool::ckb_types::packed::*;
 use ckb_tool::ckb_types::prelude::*;
 use ckb_x64_simulator::RunningSetup;
 use molecule::prelude::*;
 use share::blake2b;
 
+use crate::schema::cell::SwapRequestLockArgs;
 use crate::{cell_builder::*, tx_builder::*};
 use crate::{test_contract, Loader};
 
 const MAX_CYCLES: u64 = 10000_0000;
-const POOL_CAPACITY: u64 = 18_600_000_000;
 const SUDT_CAPACITY: u64 = 14_200_000_000;
-const INFO_CAPACITY: u64 = 25_000_000_000;
+const REGISTRY_CAPACITY: u64 = 15_000_000_000;
 
 lazy_static::lazy_static! {
     static ref SUDT_TYPE_HASH: [u8; 32] = {
@@ -135,3 +139,42 @@ fn witness_args_input_type(num: u64) -> Bytes {
         .build()
         .as_bytes()
 }
+
+fn witness_args_batch(swap_count: u64, matcher_count: u64) -> Bytes {
+    let mut input_type = swap_count.to_le_bytes().to_vec();
+    input_type.extend_from_slice(&matcher_count.to_le_bytes());
+    let byte_opt = Some(Bytes::from(input_type)).pack();
+    WitnessArgsBuilder::default()
+        .input_type(byte_opt)
+        .build()
+        .as_bytes()
+}
+
+fn witness_args_cancel(user_lock: Script, signature: Bytes) -> Bytes {
+    WitnessArgsBuilder::default()
+        .lock(Some(signature).pack())
+        .input_type(Some(user_lock.as_bytes()).pack())
+        .build()
+        .as_bytes()
+}
+
+fn bound_swap_lock_args(args: SwapRequestLockArgs, info_type_hash: [u8; 32]) -> Bytes {
+    let mut args = args.as_bytes().to_vec();
+    args.extend_from_slice(&info_type_hash);
+    Bytes::from(args)
+}
+
+fn referral_swap_lock_args(
+    args: SwapRequestLockArgs,
+    referrer_lock_hash: [u8; 32],
+    referral_bps: u16,
+) -> Bytes {
+    let mut args = args.as_bytes().to_vec();
+    args.extend_from_slice(&referrer_lock_hash);
+    args.extend_from_slice(&referral_bps.to_le_bytes());
+    Bytes::from(args)
+}
+
+fn info_type_code_hash() -> [u8; 32] {
+    CellOutput::calc_data_hash(&INFO_TYPE_SCRIPT).unpack()
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+use share::error::RegistryTypeError;
+
+const ERR_REGISTRY_KEY_NOT_SORTED: i8 = RegistryTypeError::RegistryKeyNotSorted as i8;
+const ERR_NO_POOL_CREATION: i8 = RegistryTypeError::NoPoolCreation as i8;
+const ERR_NO_POOL_CLOSE: i8 = RegistryTypeError::NoPoolClose as i8;
+
+test_contract!(
+    registry_insert_key_not_sorted,
+    {
+        let mut next_key = [0u8; 32];
+        next_key[31] = 1;
+
+        let (context, tx) = PoolTxBuilder::creation()
+            .registry(
+                vec![RegistryCell::new(
+                    REGISTRY_CAPACITY,
+                    [0u8; 32],
+                    next_key,
+                    info_type_code_hash(),
+                )],
+                vec![
+                    RegistryCell::new(
+                        REGISTRY_CAPACITY,
+                        [0u8; 32],
+                        *SUDT_TYPE_HASH,
+                        info_type_code_hash(),
+                    ),
+                    RegistryCell::new(
+                        REGISTRY_CAPACITY,
+                        *SUDT_TYPE_HASH,
+                        next_key,
+                        info_type_code_hash(),
+                    ),
+                ],
+            )
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_REGISTRY_KEY_NOT_SORTED, 1, true, false));
+
+        (context, tx)
+    },
+    false,
+    "registry-typescript-sim"
+);
+
+test_contract!(
+    registry_insert_without_pool_creation,
+    {
+        let input_0 = Inputs::new_registry(RegistryCell::new(
+            REGISTRY_CAPACITY,
+            [0u8; 32],
+            [0u8; 32],
+            info_type_code_hash(),
+        ));
+
+        let output_0 = Outputs::new_registry(RegistryCell::new(
+            REGISTRY_CAPACITY,
+            [0u8; 32],
+            *SUDT_TYPE_HASH,
+            info_type_code_hash(),
+        ));
+        let output_1 = Outputs::new_registry(RegistryCell::new(
+            REGISTRY_CAPACITY,
+            *SUDT_TYPE_HASH,
+            [0u8; 32],
+            info_type_code_hash(),
+        ));
+
+        let (mut context, tx) = build_test_context(vec![input_0], vec![output_0, output_1]);
+        let tx = context.complete_tx(tx);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_NO_POOL_CREATION, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "registry-typescript-sim"
+);
+
+test_contract!(
+    registry_remove_without_pool_close,
+    {
+        let input_0 = Inputs::new_registry(RegistryCell::new(
+            REGISTRY_CAPACITY,
+            [0u8; 32],
+            *SUDT_TYPE_HASH,
+            info_type_code_hash(),
+        ));
+        let input_1 = Inputs::new_registry(RegistryCell::new(
+            REGISTRY_CAPACITY,
+            *SUDT_TYPE_HASH,
+            [0u8; 32],
+            info_type_code_hash(),
+        ));
+
+        let output_0 = Outputs::new_registry(RegistryCell::new(
+            REGISTRY_CAPACITY,
+            [0u8; 32],
+            [0u8; 32],
+            info_type_code_hash(),
+        ));
+
+        let (mut context, tx) = build_test_context(vec![input_0, input_1], vec![output_0]);
+        let tx = context.complete_tx(tx);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_NO_POOL_CLOSE, 0, true, false));
+
+        (context, tx)
+    },
+    false,
+    "registry-typescript-sim"
+);
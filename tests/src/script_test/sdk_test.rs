@@ -0,0 +1,1413 @@
+use super::*;
+
+use ckb_standalone_debugger::transaction::ReprMockTransaction;
+use ckb_tool::ckb_types::core::TransactionBuilder;
+use dex_indexer::{Indexer, TxKind};
+use dex_sdk::builder::{batch_witness, request_order};
+use dex_sdk::cell::{decode_sudt_amount, encode_info_data};
+use dex_sdk::matcher::{CycleModel, Objective};
+use dex_sdk::quote::{self, Asset, Request};
+use dex_sdk::request::{encode_liquidity_args, encode_swap_args};
+use dex_sdk::{
+    BatchBuilder, Deployment, Error, LiquidityRequest, LiveCell, Matcher, Operation,
+    PendingRequest, Pool, PoolState, Settlement, SwapRequest, MATCHER_INDEX,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use share::cell::{
+    InfoCellData, LiquidityRequestLockArgs as LiquidityArgs, SwapRequestLockArgs as SwapArgs,
+};
+use share::error::InfoTypeError;
+
+use crate::mock_chain::{MockChain, MockChainError};
+use crate::tx_builder::build_mock_transaction;
+
+const PROPERTY_ROUNDS: usize = 32;
+const GOVERNED_INFO_DATA_LEN: usize = 217;
+const GOVERNANCE_DELAY: u64 = 172_800;
+const SINCE_ABSOLUTE_TIMESTAMP: u64 = 0x4000_0000_0000_0000;
+
+fn sudt_data(amount: u128) -> Bytes {
+    Bytes::from(amount.to_le_bytes().to_vec())
+}
+
+/// The info and pool cells of a pool trading the sUDT of `deployment`.
+fn pool_cells(
+    deployment: &Deployment,
+    state: &PoolState,
+    info: InfoCellBuilder,
+) -> ((CellOutput, Bytes), (CellOutput, Bytes)) {
+    let sudt_type = deployment.sudt.script(Bytes::new());
+    let sudt_type_hash: [u8; 32] = sudt_type.calc_script_hash().unpack();
+
+    let info_type = deployment.info_type.script(info_type_args(0));
+    let info_type_hash: [u8; 32] = info_type.calc_script_hash().unpack();
+    let mut info_lock_args = blake2b!("ckb", sudt_type_hash).to_vec();
+    info_lock_args.extend_from_slice(&info_type_hash);
+    let info_lock = deployment.info_lock.script(Bytes::from(info_lock_args));
+    let liquidity_type =
+        dex_sdk::pool::liquidity_sudt_type(&sudt_type, info_lock.calc_script_hash().unpack());
+
+    let info_data = info
+        .capacity(INFO_CAPACITY)
+        .ckb_reserve(state.ckb_reserve)
+        .sudt_reserve(state.sudt_reserve)
+        .total_liquidity(state.total_liquidity)
+        .liquidity_sudt_type_hash(liquidity_type.calc_script_hash().unpack())
+        .build()
+        .data;
+    let info = CellOutput::new_builder()
+        .capacity(INFO_CAPACITY.pack())
+        .lock(info_lock.clone())
+        .type_(Some(info_type).pack())
+        .build();
+    let pool = CellOutput::new_builder()
+        .capacity((POOL_CAPACITY + state.ckb_reserve as u64).pack())
+        .lock(info_lock)
+        .type_(Some(sudt_type).pack())
+        .build();
+
+    ((info, info_data), (pool, sudt_data(state.sudt_reserve)))
+}
+
+/// A pool with two matcher cells, the second one collecting the sUDT tips. The user and
+/// matcher locks are always success scripts.
+struct SdkFixture {
+    context:    Context,
+    deployment: Deployment,
+    pool:       Pool,
+    user_lock:  Script,
+    matchers:   Vec<LiveCell>,
+}
+
+impl SdkFixture {
+    fn new(state: PoolState) -> Self {
+        let mut context = Context::default();
+        let deployment = sdk_deployment(&mut context);
+        let sudt_type = deployment.sudt.script(Bytes::new());
+        let user_lock = deployment.sudt.script(user_lock_args(0));
+        let matcher_lock = deployment.sudt.script(user_lock_args(1));
+
+        let ((info_output, info_data), (pool_output, pool_data)) = pool_cells(&deployment, &state);
+        let info = Self::live_cell(&mut context, info_output, info_data);
+        let pool = Self::live_cell(&mut context, pool_output, pool_data);
+        let pool = Pool::from_cells(info, pool).unwrap();
+
+        let matcher_ckb = Self::live_cell(
+            &mut context,
+            CellOutput::new_builder()
+                .capacity(SUDT_CAPACITY.pack())
+                .lock(matcher_lock.clone())
+                .build(),
+            Bytes::new(),
+        );
+        let matcher_sudt = Self::live_cell(
+            &mut context,
+            CellOutput::new_builder()
+                .capacity(SUDT_CAPACITY.pack())
+                .lock(matcher_lock)
+                .type_(Some(sudt_type).pack())
+                .build(),
+            sudt_data(0),
+        );
+
+        SdkFixture {
+            context,
+            deployment,
+            pool,
+            user_lock,
+            matchers: vec![matcher_ckb, matcher_sudt],
+        }
+    }
+
+    fn live_cell(context: &mut Context, output: CellOutput, data: Bytes) -> LiveCell {
+        let out_point = context.create_cell(output.clone(), data.clone());
+        LiveCell::new(out_point, output, data)
+    }
+
+    fn swap_request(
+        &mut self,
+        is_ckb_in: bool,
+        amount: u128,
+        min_amount_out: u128,
+        tips: u64,
+        tips_sudt: u128,
+    ) -> SwapRequest {
+        let args = SwapArgs {
+            sudt_type_hash: self.pool.sudt_type_hash(),
+            user_lock_hash: self.user_lock.calc_script_hash().unpack(),
+            version: 1,
+            min_amount_out,
+            tips,
+            tips_sudt,
+            info_type_hash: None,
+            referral: None,
+        };
+        let lock = self.deployment.swap_lock.script(encode_swap_args(&args));
+
+        let cell = if is_ckb_in {
+            let capacity = SUDT_CAPACITY + amount as u64 + tips;
+            let output = CellOutput::new_builder()
+                .capacity(capacity.pack())
+                .lock(lock)
+                .build();
+            Self::live_cell(&mut self.context, output, Bytes::new())
+        } else {
+            let output = CellOutput::new_builder()
+                .capacity(SUDT_CAPACITY.pack())
+                .lock(lock)
+                .type_(Some(self.pool.sudt_type()).pack())
+                .build();
+            Self::live_cell(&mut self.context, output, sudt_data(amount + tips_sudt))
+        };
+
+        SwapRequest::new(cell, self.user_lock.clone()).unwrap()
+    }
+
+    /// A request with the same minimal amount of CKB and sUDT.
+    fn liquidity_request(
+        &mut self,
+        type_: Script,
+        capacity: u64,
+        amount: u128,
+        min_amount: u128,
+    ) -> LiquidityRequest {
+        let args = LiquidityArgs {
+            info_type_hash: self.pool.info_type_hash(),
+            user_lock_hash: self.user_lock.calc_script_hash().unpack(),
+            version:        1,
+            amount_0:       min_amount as u64,
+            amount_1:       min_amount,
+            tips:           0,
+            tips_sudt:      0,
+        };
+        let output = CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(
+                self.deployment
+                    .liquidity_lock
+                    .script(encode_liquidity_args(&args)),
+            )
+            .type_(Some(type_).pack())
+            .build();
+        let cell = Self::live_cell(&mut self.context, output, sudt_data(amount));
+
+        LiquidityRequest::new(cell, self.user_lock.clone()).unwrap()
+    }
+
+    fn batch(&self) -> BatchBuilder {
+        self.matchers.iter().fold(
+            BatchBuilder::new(&self.deployment, &self.pool),
+            |batch, cell| batch.matcher(cell.clone()),
+        )
+    }
+}
+
+fn random_state(rng: &mut StdRng) -> PoolState {
+    PoolState {
+        ckb_reserve:     rng.gen_range(10_000_000_000_000..1_000_000_000_000_000_000),
+        sudt_reserve:    rng.gen_range(100_000_000..1_000_000_000_000_000_000_000),
+        total_liquidity: rng.gen_range(100_000_000..1_000_000_000_000_000_000),
+    }
+}
+
+fn random_swap(rng: &mut StdRng, state: &PoolState) -> (Asset, u128) {
+    if rng.gen_bool(0.5) {
+        (Asset::Ckb, rng.gen_range(1..state.ckb_reserve / 2))
+    } else {
+        (Asset::Sudt, rng.gen_range(1..state.sudt_reserve / 2))
+    }
+}
+
+test_contract!(
+    sdk_swap_and_add_liquidity_batch_success,
+    {
+        let mut fixture = SdkFixture::new(PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        });
+        let swap = fixture.swap_request(true, 1_000_000, 900_000, 10_000, 611);
+        let sudt_type = fixture.pool.sudt_type();
+        let add_liquidity = fixture.liquidity_request(
+            sudt_type,
+            SUDT_CAPACITY * 2 + 2_000_000,
+            1_000_000,
+            1_000_000,
+        );
+        let batch = fixture
+            .batch()
+            .swap(swap)
+            .liquidity(add_liquidity)
+            .build()
+            .unwrap();
+        let SdkFixture { context, .. } = fixture;
+        let tx = batch.tx;
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        assert_eq!(batch.state, PoolState {
+            ckb_reserve:     12_209_671,
+            sudt_reserve:    10_093_389,
+            total_liquidity: 11_099_701,
+        });
+        assert_eq!(
+            tx.outputs_data().get(4).unwrap().raw_data(),
+            sudt_data(906_000)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    sdk_matcher_drops_unfillable_requests_success,
+    {
+        let mut fixture = SdkFixture::new(PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        });
+        let unfillable = fixture.swap_request(true, 1_000_000, 2_000_000, 0, 0);
+        let buy = fixture.swap_request(true, 1_000_000, 900_000, 0, 0);
+        let sell = fixture.swap_request(false, 1_000_000, 900_000, 0, 0);
+        let sudt_type = fixture.pool.sudt_type();
+        let add_liquidity = fixture.liquidity_request(
+            sudt_type,
+            SUDT_CAPACITY * 2 + 2_000_000,
+            1_000_000,
+            1_000_000,
+        );
+        let plan = fixture
+            .matcher()
+            .plan(vec![
+                PendingRequest::Liquidity(add_liquidity),
+                PendingRequest::Swap(unfillable),
+                PendingRequest::Swap(buy),
+                PendingRequest::Swap(sell),
+            ])
+            .unwrap();
+        let SdkFixture { context, .. } = fixture;
+
+        assert_eq!(plan.filled.len(), 3);
+        assert!(plan.filled[0].is_swap() && plan.filled[1].is_swap());
+        assert_eq!(plan.dropped.len(), 1);
+        assert_eq!(plan.dropped[0].1, Error::AmountLessThanMin);
+
+        (context, plan.batch.tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    sdk_batch_with_unordered_requests_failure,
+    {
+        let mut fixture = SdkFixture::new(PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        });
+        let buy = fixture.swap_request(true, 1_000_000, 0, 0, 0);
+        let sell = fixture.swap_request(false, 1_000_000, 0, 0, 0);
+        let batch = fixture.batch().swap(buy).swap(sell).build().unwrap();
+        let SdkFixture { context, .. } = fixture;
+
+        // Both swaps are still filled, in the other order
+        let mut inputs = batch.tx.inputs().into_iter().collect::<Vec<_>>();
+        let mut outputs = batch.tx.outputs().into_iter().collect::<Vec<_>>();
+        let mut outputs_data = batch.tx.outputs_data().into_iter().collect::<Vec<_>>();
+        inputs.swap(4, 5);
+        outputs.swap(4, 5);
+        outputs_data.swap(4, 5);
+        let tx = batch
+            .tx
+            .as_advanced_builder()
+            .set_inputs(inputs)
+            .set_outputs(outputs)
+            .set_outputs_data(outputs_data)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(InfoTypeError::UnorderedRequests as i8, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    sdk_uniform_price_batch_success,
+    {
+        let mut fixture = SdkFixture::new(PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        });
+        let buy = fixture.swap_request(true, 1_000_000, 950_000, 0, 0);
+        let sell = fixture.swap_request(false, 600_000, 600_000, 0, 0);
+        let batch = fixture
+            .batch()
+            .settlement(Settlement::UniformPrice)
+            .swap(buy)
+            .swap(sell)
+            .build()
+            .unwrap();
+        let SdkFixture { context, .. } = fixture;
+        let tx = batch.tx;
+
+        context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        // Only 375_655 CKB trade against the pool, both sides get about 1.0406 CKB per sUDT
+        assert_eq!(batch.state, PoolState {
+            ckb_reserve:     10_375_655,
+            sudt_reserve:    9_638_992,
+            total_liquidity: 10_000_000,
+        });
+        for idx in 4..6 {
+            let output = tx.outputs().get(idx).unwrap();
+            if output.type_().is_some() {
+                assert_eq!(
+                    tx.outputs_data().get(idx).unwrap().raw_data(),
+                    sudt_data(961_008)
+                );
+            } else {
+                let capacity: u64 = output.capacity().unpack();
+                assert_eq!(capacity, SUDT_CAPACITY + 624_345);
+            }
+        }
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    sdk_uniform_price_batch_failure,
+    {
+        let mut fixture = SdkFixture::new(PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        });
+        let buy = fixture.swap_request(true, 1_000_000, 0, 0, 0);
+        let sell = fixture.swap_request(false, 600_000, 0, 0, 0);
+        let batch = fixture
+            .batch()
+            .settlement(Settlement::UniformPrice)
+            .swap(buy)
+            .swap(sell)
+            .build()
+            .unwrap();
+        let SdkFixture { context, .. } = fixture;
+
+        // The buyer gets one sUDT more than its share
+        let mut outputs_data = batch.tx.outputs_data().into_iter().collect::<Vec<_>>();
+        let idx = (4..6)
+            .find(|idx| batch.tx.outputs().get(*idx).unwrap().type_().is_some())
+            .unwrap();
+        let amount = decode_sudt_amount(&outputs_data[idx].raw_data()).unwrap();
+        outputs_data[idx] = sudt_data(amount + 1).pack();
+        let tx = batch
+            .tx
+            .as_advanced_builder()
+            .set_outputs_data(outputs_data)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(InfoTypeError::UniformPriceMismatch as i8, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    sdk_mid_price_batch_success,
+    {
+        let mut fixture = SdkFixture::new(PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        });
+        let buy = fixture.swap_request(true, 1_000_000, 980_000, 0, 0);
+        let sell = fixture.swap_request(false, 600_000, 600_000, 0, 0);
+        let batch = fixture
+            .batch()
+            .settlement(Settlement::MidPrice)
+            .swap(buy)
+            .swap(sell)
+            .build()
+            .unwrap();
+        let SdkFixture { context, .. } = fixture;
+        let tx = batch.tx;
+
+        context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        // 600_000 CKB are matched at the mid price, only the 400_000 CKB left trade on the curve
+        assert_eq!(batch.state, PoolState {
+            ckb_reserve:     10_400_000,
+            sudt_reserve:    9_616_494,
+            total_liquidity: 10_000_000,
+        });
+        for idx in 4..6 {
+            let output = tx.outputs().get(idx).unwrap();
+            if output.type_().is_some() {
+                assert_eq!(
+                    tx.outputs_data().get(idx).unwrap().raw_data(),
+                    sudt_data(983_506)
+                );
+            } else {
+                let capacity: u64 = output.capacity().unpack();
+                assert_eq!(capacity, SUDT_CAPACITY + 600_000);
+            }
+        }
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    sdk_interleaved_batch_success,
+    {
+        let state = PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        };
+        let mut fixture = SdkFixture::new(state);
+        let swap = fixture.swap_request(true, 1_000_000, 0, 0, 0);
+        let liquidity_type = fixture.pool.liquidity_sudt_type().unwrap();
+        let remove_liquidity =
+            fixture.liquidity_request(liquidity_type, SUDT_CAPACITY + 10_000_000_000, 1_000_000, 0);
+        let batch = fixture
+            .batch()
+            .swap(swap)
+            .liquidity(remove_liquidity)
+            .operations(vec![Operation::Liquidity, Operation::Swap])
+            .build()
+            .unwrap();
+        let SdkFixture { context, .. } = fixture;
+        let tx = batch.tx;
+
+        context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        // The liquidity is removed at the price before the swap
+        let burned = quote::burn(&state, 1_000_000).unwrap();
+        let swapped = quote::swap(&burned.state, Asset::Ckb, 1_000_000, 0).unwrap();
+        assert_eq!(batch.state, swapped.state);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    sdk_interleaved_batch_failure,
+    {
+        let mut fixture = SdkFixture::new(PoolState {
+            ckb_reserve:     10_000_000,
+            sudt_reserve:    10_000_000,
+            total_liquidity: 10_000_000,
+        });
+        let swap = fixture.swap_request(true, 1_000_000, 0, 0, 0);
+        let liquidity_type = fixture.pool.liquidity_sudt_type().unwrap();
+        let remove_liquidity =
+            fixture.liquidity_request(liquidity_type, SUDT_CAPACITY + 10_000_000_000, 1_000_000, 0);
+        let batch = fixture
+            .batch()
+            .swap(swap)
+            .liquidity(remove_liquidity)
+            .operations(vec![Operation::Liquidity, Operation::Swap])
+            .build()
+            .unwrap();
+        let SdkFixture { context, .. } = fixture;
+
+        // One more operation than requests
+        let mut witnesses = batch.tx.witnesses().into_iter().collect::<Vec<_>>();
+        witnesses[0] = batch_witness(1, 2, Settlement::Sequential, &[
+            Operation::Liquidity,
+            Operation::Swap,
+            Operation::Liquidity,
+        ])
+        .pack();
+        let tx = batch
+            .tx
+            .as_advanced_builder()
+            .set_witnesses(witnesses)
+            .build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(InfoTypeError::InvalidOperations as i8, 0, true, false)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+#[test]
+fn matcher_prefers_tips_within_cycle_budget() {
+    let mut fixture = SdkFixture::new(PoolState {
+        ckb_reserve:     10_000_000,
+        sudt_reserve:    10_000_000,
+        total_liquidity: 10_000_000,
+    });
+    let cheap = fixture.swap_request(true, 1_000_000, 0, 0, 0);
+    let tipped = fixture.swap_request(true, 1_000_000, 0, 10_000, 0);
+    let model = CycleModel::default();
+
+    let plan = fixture
+        .matcher()
+        .objective(Objective::Tips)
+        .cycle_budget(model.cycles(1, 0))
+        .plan(vec![
+            PendingRequest::Swap(cheap.clone()),
+            PendingRequest::Swap(tipped.clone()),
+        ])
+        .unwrap();
+
+    assert_eq!(plan.tips, 10_000);
+    assert_eq!(plan.filled[0].cell().out_point, tipped.cell.out_point);
+    assert_eq!(plan.dropped.len(), 1);
+    assert_eq!(plan.dropped[0].1, Error::CycleBudgetExceeded);
+    fixture
+        .context
+        .verify_tx(&plan.batch.tx, MAX_CYCLES)
+        .expect("pass verification");
+
+    let plan = fixture
+        .matcher()
+        .cycle_budget(model.cycles(0, 0))
+        .plan(vec![PendingRequest::Swap(cheap)]);
+    assert_eq!(plan.err(), Some(Error::NoFillableRequest));
+}
+
+#[test]
+fn quote_matches_contracts_for_random_reserves() {
+    let mut rng = StdRng::seed_from_u64(41);
+
+    for round in 0..PROPERTY_ROUNDS {
+        let state = random_state(&mut rng);
+        let mut fixture = SdkFixture::new(state);
+
+        let (asset_in, swap_amount) = random_swap(&mut rng, &state);
+        let tips = rng.gen_range(0..1_000);
+        let swap = fixture.swap_request(asset_in == Asset::Ckb, swap_amount, 0, tips, 0);
+        let after_swap = quote::swap(&state, asset_in, swap_amount, 0).unwrap().state;
+
+        // Alternate between a CKB change and a sUDT change, the latter offering about twice the
+        // sUDT the injected CKB can match
+        let (add_ckb, add_sudt, add_capacity) = if round % 2 == 0 {
+            let sudt = rng.gen_range(1..after_swap.sudt_reserve / 10);
+            let ckb = quote::mint_with_sudt(&after_swap, sudt)
+                .unwrap()
+                .ckb_injected;
+            let capacity = SUDT_CAPACITY * 2 + ckb as u64;
+            (capacity as u128 - SUDT_CAPACITY as u128, sudt, capacity)
+        } else {
+            let ckb = rng.gen_range(after_swap.ckb_reserve / 100..after_swap.ckb_reserve / 10);
+            let sudt = quote::mint_with_ckb(&after_swap, ckb)
+                .unwrap()
+                .sudt_injected
+                * 2;
+            (ckb, sudt, SUDT_CAPACITY * 2 + ckb as u64)
+        };
+        let sudt_type = fixture.pool.sudt_type();
+        let add_liquidity = fixture.liquidity_request(sudt_type, add_capacity, add_sudt, 1);
+
+        let burned = rng.gen_range(1..state.total_liquidity / 10);
+        let liquidity_type = fixture.pool.liquidity_sudt_type().unwrap();
+        let remove_liquidity =
+            fixture.liquidity_request(liquidity_type, SUDT_CAPACITY + 10_000_000_000, burned, 0);
+
+        let info_out_point = fixture.pool.info.out_point.clone();
+        let add_first = request_order(&info_out_point, &add_liquidity.cell)
+            < request_order(&info_out_point, &remove_liquidity.cell);
+        let batch = fixture
+            .batch()
+            .swap(swap)
+            .liquidity(add_liquidity)
+            .liquidity(remove_liquidity)
+            .build()
+            .unwrap();
+
+        fixture
+            .context
+            .verify_tx(&batch.tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        let info_data = batch.tx.outputs_data().get(0).unwrap().raw_data();
+        let info_data = InfoCellData::from_raw(&info_data).unwrap();
+        assert_eq!(info_data.ckb_reserve, batch.state.ckb_reserve);
+        assert_eq!(info_data.sudt_reserve, batch.state.sudt_reserve);
+        assert_eq!(info_data.total_liquidity, batch.state.total_liquidity);
+
+        // Swaps are filled before the liquidity requests whatever their order
+        let mut liquidity = vec![
+            Request::AddLiquidity {
+                ckb_available:  add_ckb,
+                sudt_available: add_sudt,
+            },
+            Request::RemoveLiquidity { liquidity: burned },
+        ];
+        if !add_first {
+            liquidity.reverse();
+        }
+        let mut requests = liquidity;
+        requests.insert(1, Request::Swap {
+            asset_in,
+            amount_in: swap_amount,
+            referral_bps: 0,
+        });
+        let (_, simulated) = quote::simulate_batch(&state, &requests).unwrap();
+        assert_eq!(simulated, batch.state);
+    }
+}
+
+#[test]
+fn quote_off_by_one_rejected_for_random_reserves() {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    for _ in 0..PROPERTY_ROUNDS {
+        let state = random_state(&mut rng);
+        let mut fixture = SdkFixture::new(state);
+
+        let (asset_in, swap_amount) = random_swap(&mut rng, &state);
+        let swap = fixture.swap_request(asset_in == Asset::Ckb, swap_amount, 0, 0, 0);
+        let batch = fixture.batch().swap(swap).build().unwrap();
+
+        // Pays the user one more than the quote, the CKB taken from the matcher cell
+        let swap_index = 4;
+        let mut outputs = batch.tx.outputs().into_iter().collect::<Vec<_>>();
+        let mut outputs_data = batch.tx.outputs_data().into_iter().collect::<Vec<_>>();
+        if asset_in == Asset::Ckb {
+            let amount = decode_sudt_amount(&outputs_data[swap_index].raw_data()).unwrap();
+            outputs_data[swap_index] = sudt_data(amount + 1).pack();
+        } else {
+            let capacity: u64 = outputs[swap_index].capacity().unpack();
+            let matcher_capacity: u64 = outputs[MATCHER_INDEX].capacity().unpack();
+            outputs[swap_index] = outputs[swap_index]
+                .clone()
+                .as_builder()
+                .capacity((capacity + 1).pack())
+                .build();
+            outputs[MATCHER_INDEX] = outputs[MATCHER_INDEX]
+                .clone()
+                .as_builder()
+                .capacity((matcher_capacity - 1).pack())
+                .build();
+        }
+        let tx = batch
+            .tx
+            .as_advanced_builder()
+            .set_outputs(outputs)
+            .set_outputs_data(outputs_data)
+            .build();
+
+        fixture
+            .context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect_err("reject off by one output");
+    }
+}
+
+type SettleFn = fn(&PoolState, &[(Asset, u128)]) -> Option<(Vec<u128>, PoolState)>;
+
+fn assert_settlement_for_random_reserves(seed: u64, settlement: Settlement, settle: SettleFn) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for _ in 0..PROPERTY_ROUNDS {
+        let state = random_state(&mut rng);
+        let mut fixture = SdkFixture::new(state);
+
+        let mut swaps = Vec::new();
+        let mut builder_swaps = Vec::new();
+        for _ in 0..rng.gen_range(1..5) {
+            let (asset_in, amount) = random_swap(&mut rng, &state);
+            let amount = amount / 4 + 1;
+            builder_swaps.push(fixture.swap_request(asset_in == Asset::Ckb, amount, 0, 0, 0));
+            swaps.push((asset_in, amount));
+        }
+
+        let batch = builder_swaps
+            .into_iter()
+            .fold(fixture.batch().settlement(settlement), |batch, swap| {
+                batch.swap(swap)
+            })
+            .build()
+            .unwrap();
+
+        fixture
+            .context
+            .verify_tx(&batch.tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        let (_, after) = settle(&state, &swaps).unwrap();
+        assert_eq!(after, batch.state);
+    }
+}
+
+#[test]
+fn uniform_settlement_for_random_reserves() {
+    assert_settlement_for_random_reserves(45, Settlement::UniformPrice, quote::uniform_swaps);
+}
+
+#[test]
+fn mid_price_settlement_for_random_reserves() {
+    assert_settlement_for_random_reserves(46, Settlement::MidPrice, quote::mid_price_swaps);
+}
+
+fn chain_swap_request(
+    chain: &mut MockChain,
+    deployment: &Deployment,
+    pool: &Pool,
+    user_lock: &Script,
+    is_ckb_in: bool,
+    amount: u128,
+) -> SwapRequest {
+    let args = SwapArgs {
+        sudt_type_hash: pool.sudt_type_hash(),
+        user_lock_hash: user_lock.calc_script_hash().unpack(),
+        version:        1,
+        min_amount_out: 0,
+        tips:           0,
+        tips_sudt:      0,
+        info_type_hash: None,
+        referral:       None,
+    };
+    let lock = deployment.swap_lock.script(encode_swap_args(&args));
+
+    let cell = if is_ckb_in {
+        let output = CellOutput::new_builder()
+            .capacity((SUDT_CAPACITY + amount as u64).pack())
+            .lock(lock)
+            .build();
+        chain.create_cell(output, Bytes::new())
+    } else {
+        let output = CellOutput::new_builder()
+            .capacity(SUDT_CAPACITY.pack())
+            .lock(lock)
+            .type_(Some(pool.sudt_type()).pack())
+            .build();
+        chain.create_cell(output, sudt_data(amount))
+    };
+
+    SwapRequest::new(cell, user_lock.clone()).unwrap()
+}
+
+#[test]
+fn mock_chain_commits_consecutive_batches() {
+    let mut chain = MockChain::new(MAX_CYCLES);
+    let deployment = sdk_deployment(chain.context_mut());
+    let user_lock = deployment.sudt.script(user_lock_args(0));
+    let matcher_lock = deployment.sudt.script(user_lock_args(1));
+
+    let mut state = PoolState {
+        ckb_reserve:     10_000_000,
+        sudt_reserve:    10_000_000,
+        total_liquidity: 10_000_000,
+    };
+    let ((info_output, info_data), (pool_output, pool_data)) =
+        pool_cells(&deployment, &state, InfoCellBuilder::default());
+    let info_type = info_output.type_().to_opt().unwrap();
+    let info_lock = info_output.lock();
+    let info = chain.create_cell(info_output, info_data);
+    let pool = chain.create_cell(pool_output, pool_data);
+    let mut pool = Pool::from_cells(info, pool).unwrap();
+    chain.create_cell(
+        CellOutput::new_builder()
+            .capacity(SUDT_CAPACITY.pack())
+            .lock(matcher_lock.clone())
+            .build(),
+        Bytes::new(),
+    );
+
+    let mut first_tx = None;
+    let swaps = [(true, 1_000_000), (false, 500_000), (true, 200_000)];
+    for (round, &(is_ckb_in, amount)) in swaps.iter().enumerate() {
+        let swap = chain_swap_request(
+            &mut chain,
+            &deployment,
+            &pool,
+            &user_lock,
+            is_ckb_in,
+            amount,
+        );
+        let matcher = chain.cells_by_lock(&matcher_lock).remove(0);
+        let batch = BatchBuilder::new(&deployment, &pool)
+            .matcher(matcher)
+            .swap(swap)
+            .build()
+            .unwrap();
+
+        // Each batch trades against the pool left by the previous one
+        let asset_in = if is_ckb_in { Asset::Ckb } else { Asset::Sudt };
+        state = quote::swap(&state, asset_in, amount, 0).unwrap().state;
+        assert_eq!(batch.state, state);
+
+        chain.commit(&batch.tx).expect("commit batch");
+        assert_eq!(chain.tip().number(), round as u64 + 1);
+        first_tx.get_or_insert(batch.tx);
+
+        let info = chain.cells_by_type(&info_type).remove(0);
+        let pool_cell = chain
+            .cells_by_lock(&info_lock)
+            .into_iter()
+            .find(|cell| cell.out_point != info.out_point)
+            .unwrap();
+        pool = Pool::from_cells(info, pool_cell).unwrap();
+        assert_eq!(pool.state(), state);
+    }
+
+    // The user got an output for every swap
+    assert_eq!(chain.cells_by_lock(&user_lock).len(), 3);
+    assert_eq!(chain.transactions().count(), 3);
+
+    // The inputs of a committed transaction are spent
+    match chain.commit(&first_tx.unwrap()) {
+        Err(MockChainError::DeadCell(_)) => {}
+        res => panic!("unexpected commit result: {:?}", res),
+    }
+}
+
+/// A pool on a mock chain, filled by a single matcher cell.
+struct ChainFixture {
+    chain:        MockChain,
+    deployment:   Deployment,
+    pool:         Pool,
+    user_lock:    Script,
+    matcher_lock: Script,
+}
+
+impl ChainFixture {
+    fn new(state: PoolState) -> Self {
+        Self::with_info(state, InfoCellBuilder::default())
+    }
+
+    fn with_info(state: PoolState, info: InfoCellBuilder) -> Self {
+        let mut chain = MockChain::new(MAX_CYCLES);
+        let deployment = sdk_deployment(chain.context_mut());
+        let user_lock = deployment.sudt.script(user_lock_args(0));
+        let matcher_lock = deployment.sudt.script(user_lock_args(1));
+
+        let ((info_output, info_data), (pool_output, pool_data)) =
+            pool_cells(&deployment, &state, info);
+        let info = chain.create_cell(info_output, info_data);
+        let pool = chain.create_cell(pool_output, pool_data);
+        let pool = Pool::from_cells(info, pool).unwrap();
+        chain.create_cell(
+            CellOutput::new_builder()
+                .capacity(SUDT_CAPACITY.pack())
+                .lock(matcher_lock.clone())
+                .build(),
+            Bytes::new(),
+        );
+
+        ChainFixture {
+            chain,
+            deployment,
+            pool,
+            user_lock,
+            matcher_lock,
+        }
+    }
+
+    fn swap_request(&mut self, is_ckb_in: bool, amount: u128) -> SwapRequest {
+        chain_swap_request(
+            &mut self.chain,
+            &self.deployment,
+            &self.pool,
+            &self.user_lock,
+            is_ckb_in,
+            amount,
+        )
+    }
+
+    /// Fills a single swap, commits the batch and follows the pool to its new cells.
+    fn commit_swap(&mut self, is_ckb_in: bool, amount: u128) -> TransactionView {
+        let swap = self.swap_request(is_ckb_in, amount);
+        let matcher = self.chain.cells_by_lock(&self.matcher_lock).remove(0);
+        let batch = BatchBuilder::new(&self.deployment, &self.pool)
+            .matcher(matcher)
+            .swap(swap)
+            .build()
+            .unwrap();
+        self.chain.commit(&batch.tx).expect("commit batch");
+        self.follow_pool();
+        assert_eq!(self.pool.state(), batch.state);
+
+        batch.tx
+    }
+
+    /// Rewrites the governance fields of the info cell, signed by an admin cell. The info input
+    /// carries `since` and its block is given in the header deps.
+    fn commit_governance(
+        &mut self,
+        info_data: &InfoCellData,
+        admin_lock: Script,
+        since: u64,
+    ) -> Result<(), MockChainError> {
+        let admin = self.chain.create_cell(
+            CellOutput::new_builder()
+                .capacity(SUDT_CAPACITY.pack())
+                .lock(admin_lock)
+                .build(),
+            Bytes::new(),
+        );
+        let info_header = self.chain.cell_header(&self.pool.info.out_point).unwrap();
+
+        let inputs = vec![
+            CellInput::new(self.pool.info.out_point.clone(), since),
+            CellInput::new(self.pool.pool.out_point.clone(), 0),
+            CellInput::new(admin.out_point.clone(), 0),
+        ];
+        let outputs = vec![
+            (
+                self.pool.info.output.clone(),
+                encode_info_data(info_data, GOVERNED_INFO_DATA_LEN).unwrap(),
+            ),
+            (self.pool.pool.output.clone(), self.pool.pool.data.clone()),
+            (admin.output, admin.data),
+        ];
+        let (outputs, outputs_data): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+        let tx = TransactionBuilder::default()
+            .inputs(inputs)
+            .outputs(outputs)
+            .outputs_data(outputs_data.pack())
+            .cell_dep(self.deployment.info_type.cell_dep.clone())
+            .cell_dep(self.deployment.info_lock.cell_dep.clone())
+            .cell_dep(self.deployment.sudt.cell_dep.clone())
+            .header_dep(info_header.hash())
+            .witnesses(vec![Bytes::new(); 3].pack())
+            .build();
+
+        self.chain.commit(&tx)?;
+        self.follow_pool();
+        Ok(())
+    }
+
+    /// Moves the pool to the info type `migrated_type`, signed by an admin cell. The new info
+    /// and pool cells are locked by the info lock of the new info type.
+    fn commit_migration(
+        &mut self,
+        migrated_type: Script,
+        admin_lock: Script,
+        since: u64,
+    ) -> Result<(), MockChainError> {
+        let admin = self.chain.create_cell(
+            CellOutput::new_builder()
+                .capacity(SUDT_CAPACITY.pack())
+                .lock(admin_lock)
+                .build(),
+            Bytes::new(),
+        );
+        let migrated_type_hash: [u8; 32] = migrated_type.calc_script_hash().unpack();
+        let mut info_lock_args = blake2b!("ckb", self.pool.sudt_type_hash()).to_vec();
+        info_lock_args.extend_from_slice(&migrated_type_hash);
+        let info_lock = self
+            .deployment
+            .info_lock
+            .script(Bytes::from(info_lock_args));
+
+        // The new version starts without any pending governance change
+        let mut info_data = self.pool.info_data.clone();
+        info_data.migration_code_hash = None;
+        info_data.activation_timestamp = 0;
+
+        let inputs = vec![
+            CellInput::new(self.pool.info.out_point.clone(), since),
+            CellInput::new(self.pool.pool.out_point.clone(), 0),
+            CellInput::new(admin.out_point.clone(), 0),
+        ];
+        let outputs = vec![
+            (
+                self.pool
+                    .info
+                    .output
+                    .clone()
+                    .as_builder()
+                    .lock(info_lock.clone())
+                    .type_(Some(migrated_type.clone()).pack())
+                    .build(),
+                encode_info_data(&info_data, GOVERNED_INFO_DATA_LEN).unwrap(),
+            ),
+            (
+                self.pool
+                    .pool
+                    .output
+                    .clone()
+                    .as_builder()
+                    .lock(info_lock)
+                    .build(),
+                self.pool.pool.data.clone(),
+            ),
+            (admin.output, admin.data),
+        ];
+        let (outputs, outputs_data): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+        let tx = TransactionBuilder::default()
+            .inputs(inputs)
+            .outputs(outputs)
+            .outputs_data(outputs_data.pack())
+            .cell_dep(self.deployment.info_type.cell_dep.clone())
+            .cell_dep(self.deployment.info_lock.cell_dep.clone())
+            .cell_dep(self.deployment.sudt.cell_dep.clone())
+            .witnesses(vec![Bytes::new(); 3].pack())
+            .build();
+
+        self.chain.commit(&tx)?;
+        self.follow_info(&migrated_type);
+        Ok(())
+    }
+
+    fn follow_pool(&mut self) {
+        let info_type = self.pool.info.output.type_().to_opt().unwrap();
+        self.follow_info(&info_type);
+    }
+
+    fn follow_info(&mut self, info_type: &Script) {
+        let info = self.chain.cells_by_type(info_type).remove(0);
+        let pool = self
+            .chain
+            .cells_by_lock(&info.output.lock())
+            .into_iter()
+            .find(|cell| cell.out_point != info.out_point)
+            .unwrap();
+        self.pool = Pool::from_cells(info, pool).unwrap();
+    }
+}
+
+#[test]
+fn admin_change_activates_across_batches() {
+    let state = PoolState {
+        ckb_reserve:     10_000_000,
+        sudt_reserve:    10_000_000,
+        total_liquidity: 10_000_000,
+    };
+    let mut fixture = ChainFixture::with_info(
+        state,
+        InfoCellBuilder::default().admin_lock_hash(user_lock_hash(2)),
+    );
+    let admin_lock = fixture.deployment.sudt.script(user_lock_args(2));
+
+    fixture.chain.advance_time(60_000);
+    let proposed_at = fixture.chain.tip().timestamp() / 1000;
+    let mut proposal = fixture.pool.info_data.clone();
+    proposal.pending_admin_lock_hash = Some(user_lock_hash(7));
+    proposal.activation_timestamp = proposed_at + GOVERNANCE_DELAY;
+    fixture
+        .commit_governance(
+            &proposal,
+            admin_lock.clone(),
+            SINCE_ABSOLUTE_TIMESTAMP | proposed_at,
+        )
+        .expect("commit proposal");
+
+    // A batch between the proposal and its execution carries the proposal over
+    fixture.commit_swap(true, 1_000_000);
+    let info_data = fixture.pool.info_data.clone();
+    assert_eq!(info_data.pending_admin_lock_hash, Some(user_lock_hash(7)));
+    assert_eq!(
+        info_data.activation_timestamp,
+        proposal.activation_timestamp
+    );
+
+    // Execute as soon as the activation is reached, although the info cell has been updated by
+    // the batch less than the governance delay ago
+    let now = fixture.chain.tip().timestamp() / 1000;
+    fixture
+        .chain
+        .advance_time((proposal.activation_timestamp - now) * 1000);
+    let mut execution = info_data;
+    execution.admin_lock_hash = Some(user_lock_hash(7));
+    execution.pending_admin_lock_hash = None;
+    execution.activation_timestamp = 0;
+
+    let early_since = SINCE_ABSOLUTE_TIMESTAMP | (proposal.activation_timestamp - 1);
+    match fixture.commit_governance(&execution, admin_lock.clone(), early_since) {
+        Err(MockChainError::Verification(err)) => assert_error_eq!(
+            err,
+            tx_error(
+                InfoTypeError::GovernanceDelayNotPassed as i8,
+                0,
+                true,
+                false
+            )
+        ),
+        res => panic!("unexpected commit result: {:?}", res),
+    }
+
+    let since = SINCE_ABSOLUTE_TIMESTAMP | proposal.activation_timestamp;
+    fixture
+        .commit_governance(&execution, admin_lock, since)
+        .expect("commit execution");
+    assert_eq!(
+        fixture.pool.info_data.admin_lock_hash,
+        Some(user_lock_hash(7))
+    );
+    assert_eq!(fixture.pool.info_data.pending_admin_lock_hash, None);
+}
+
+#[test]
+fn migrated_pool_burns_liquidity() {
+    let state = PoolState {
+        ckb_reserve:     10_000_000,
+        sudt_reserve:    10_000_000,
+        total_liquidity: 10_000_000,
+    };
+    // The new version of the info type script is the always success sUDT code
+    let migrated_code_hash: [u8; 32] = CellOutput::calc_data_hash(&ALWAYS_SUCCESS).unpack();
+    let mut fixture = ChainFixture::with_info(
+        state,
+        InfoCellBuilder::default()
+            .admin_lock_hash(user_lock_hash(2))
+            .migration_code_hash(migrated_code_hash)
+            .activation_timestamp(GOVERNANCE_DELAY),
+    );
+    let admin_lock = fixture.deployment.sudt.script(user_lock_args(2));
+    let liquidity_type = fixture.pool.liquidity_sudt_type().unwrap();
+    let migrated_type = fixture.deployment.sudt.script(info_type_args(7));
+
+    fixture.chain.advance_time(GOVERNANCE_DELAY * 1000);
+    fixture
+        .commit_migration(
+            migrated_type.clone(),
+            admin_lock,
+            SINCE_ABSOLUTE_TIMESTAMP | GOVERNANCE_DELAY,
+        )
+        .expect("commit migration");
+    assert_eq!(
+        fixture.pool.info.output.type_().to_opt(),
+        Some(migrated_type)
+    );
+    assert_eq!(fixture.pool.state(), state);
+
+    // The old liquidity sUDT is burnt against the migrated pool, but no more can be minted
+    assert_eq!(
+        fixture.pool.liquidity_sudt_type().unwrap_err(),
+        Error::LiquiditySUDTTypeMismatch
+    );
+    let args = LiquidityArgs {
+        info_type_hash: fixture.pool.info_type_hash(),
+        user_lock_hash: fixture.user_lock.calc_script_hash().unpack(),
+        version:        1,
+        amount_0:       0,
+        amount_1:       0,
+        tips:           0,
+        tips_sudt:      0,
+    };
+    let request = fixture.chain.create_cell(
+        CellOutput::new_builder()
+            .capacity(SUDT_CAPACITY.pack())
+            .lock(
+                fixture
+                    .deployment
+                    .liquidity_lock
+                    .script(encode_liquidity_args(&args)),
+            )
+            .type_(Some(liquidity_type).pack())
+            .build(),
+        sudt_data(1_000_000),
+    );
+    let request = LiquidityRequest::new(request, fixture.user_lock.clone()).unwrap();
+    let matcher = fixture.chain.cells_by_lock(&fixture.matcher_lock).remove(0);
+    let batch = BatchBuilder::new(&fixture.deployment, &fixture.pool)
+        .matcher(matcher)
+        .liquidity(request)
+        .build()
+        .unwrap();
+    fixture.chain.commit(&batch.tx).expect("commit burn");
+    fixture.follow_pool();
+
+    assert_eq!(fixture.pool.state(), batch.state);
+    assert_eq!(fixture.pool.state().total_liquidity, 9_000_000);
+}
+
+#[test]
+fn indexer_rebuilds_pool_history() {
+    let state = PoolState {
+        ckb_reserve:     10_000_000,
+        sudt_reserve:    10_000_000,
+        total_liquidity: 10_000_000,
+    };
+    let mut fixture = ChainFixture::new(state);
+    fixture.commit_swap(true, 1_000_000);
+    fixture.chain.advance_time(60_000);
+    fixture.commit_swap(false, 500_000);
+
+    let info_type_hash = fixture.pool.info_type_hash();
+    let code_hash = fixture.deployment.info_type.code_hash.unpack();
+    let mut indexer = Indexer::new(code_hash);
+    let mut dump_indexer = Indexer::new(code_hash);
+    for (sequence, (header, tx)) in fixture.chain.transactions().enumerate() {
+        let inputs = tx
+            .inputs()
+            .into_iter()
+            .map(|input| {
+                let out_point = input.previous_output();
+                fixture.chain.context().get_cell(&out_point).unwrap()
+            })
+            .collect::<Vec<_>>();
+        indexer.index(header.timestamp(), tx, &inputs).unwrap();
+
+        let mock_tx = build_mock_transaction(tx, fixture.chain.context());
+        let json = serde_json::to_string(&ReprMockTransaction::from(mock_tx)).unwrap();
+        dump_indexer.index_json(sequence as u64, &json).unwrap();
+    }
+
+    let history = indexer.pool(&info_type_hash).unwrap();
+    let after_buy = quote::swap(&state, Asset::Ckb, 1_000_000, 0).unwrap().state;
+    let after_sell = quote::swap(&after_buy, Asset::Sudt, 500_000, 0)
+        .unwrap()
+        .state;
+    let points = history
+        .points
+        .iter()
+        .map(|point| {
+            (
+                point.timestamp,
+                point.kind,
+                point.state,
+                (point.ckb_volume, point.sudt_volume),
+                (point.ckb_fees, point.sudt_fees),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(points, vec![
+        (8_000, TxKind::Swap, after_buy, (1_000_000, 0), (3_000, 0)),
+        (76_000, TxKind::Swap, after_sell, (0, 500_000), (0, 1_500)),
+    ]);
+    assert!(history.points[0].price().unwrap() > 1.0);
+    assert!(history.points[1].price().unwrap() < history.points[0].price().unwrap());
+
+    // The dumped transactions give the same series
+    let dumped = dump_indexer.pool(&info_type_hash).unwrap();
+    for (point, dumped) in history.points.iter().zip(dumped.points.iter()) {
+        assert_eq!((point.kind, point.state), (dumped.kind, dumped.state));
+        assert_eq!(point.tx_hash, dumped.tx_hash);
+    }
+    assert_eq!(dumped.points.len(), 2);
+}
+
+/// Indexes a transaction of the pool cells, which isn't verified.
+fn index_pool_tx(
+    indexer: &mut Indexer,
+    timestamp: u64,
+    inputs: Vec<(CellOutput, Bytes)>,
+    outputs: Vec<(CellOutput, Bytes)>,
+) {
+    let (outputs, outputs_data): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+    let tx = TransactionBuilder::default()
+        .inputs(vec![CellInput::new(OutPoint::default(), 0); inputs.len()])
+        .outputs(outputs)
+        .outputs_data(outputs_data.pack())
+        .build();
+
+    indexer.index(timestamp, &tx, &inputs).unwrap();
+}
+
+#[test]
+fn indexer_records_close_and_migration() {
+    let mut context = Context::default();
+    let deployment = sdk_deployment(&mut context);
+    let before = PoolState {
+        ckb_reserve:     10_000_000,
+        sudt_reserve:    10_000_000,
+        total_liquidity: 10_000_000,
+    };
+    let after = PoolState {
+        ckb_reserve:     0,
+        sudt_reserve:    0,
+        total_liquidity: 0,
+    };
+    let (info, pool) = pool_cells(&deployment, &before, InfoCellBuilder::default());
+    let (empty_info, empty_pool) = pool_cells(&deployment, &after, InfoCellBuilder::default());
+    let info_type_hash: [u8; 32] = info.0.type_().to_opt().unwrap().calc_script_hash().unpack();
+    let code_hash = deployment.info_type.code_hash.unpack();
+
+    // The new version of the info type script, the pool cell keeps its type
+    let migrated_type = deployment.sudt.script(info_type_args(0));
+    let migrated_type_hash: [u8; 32] = migrated_type.calc_script_hash().unpack();
+    let migrated_info = (
+        empty_info
+            .0
+            .clone()
+            .as_builder()
+            .type_(Some(migrated_type).pack())
+            .build(),
+        empty_info.1.clone(),
+    );
+    let refund = (
+        CellOutput::new_builder()
+            .capacity(INFO_CAPACITY.pack())
+            .lock(deployment.sudt.script(user_lock_args(0)))
+            .build(),
+        Bytes::new(),
+    );
+
+    for (migrated, outputs) in vec![
+        (false, vec![refund]),
+        (true, vec![migrated_info, empty_pool.clone()]),
+    ] {
+        let mut indexer = Indexer::new(code_hash);
+
+        // The liquidity is removed without a batch witness
+        index_pool_tx(&mut indexer, 1_000, vec![info.clone(), pool.clone()], vec![
+            empty_info.clone(),
+            empty_pool.clone(),
+        ]);
+        index_pool_tx(
+            &mut indexer,
+            2_000,
+            vec![empty_info.clone(), empty_pool.clone()],
+            outputs,
+        );
+
+        let history = indexer.pool(&info_type_hash).unwrap();
+        let kinds = history
+            .points
+            .iter()
+            .map(|point| (point.kind, point.state))
+            .collect::<Vec<_>>();
+        if migrated {
+            assert_eq!(kinds, vec![
+                (TxKind::Other, after),
+                (TxKind::Migration, after)
+            ]);
+            assert_eq!(history.migrated_to, Some(migrated_type_hash));
+        } else {
+            assert_eq!(kinds, vec![
+                (TxKind::Other, after),
+                (TxKind::ClosePool, after)
+            ]);
+            assert_eq!(history.migrated_to, None);
+        }
+    }
+}
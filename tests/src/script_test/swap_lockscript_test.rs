@@ -1 +1,140 @@
+use super::*;
 
+use ckb_tool::ckb_types::core::ScriptHashType;
+use share::error::SwapLockError;
+
+const ERR_INFO_TYPE_HASH_MISMATCH: i8 = SwapLockError::InfoTypeHashMismatch as i8;
+const ERR_INVALID_CANCEL_LOCK_SCRIPT: i8 = SwapLockError::InvalidCancelLockScript as i8;
+const ERR_INVALID_CANCEL_SIGNATURE: i8 = SwapLockError::InvalidCancelSignature as i8;
+const ERR_UNSUPPORTED_CANCEL_HASH_TYPE: i8 = SwapLockError::UnsupportedCancelHashType as i8;
+
+/// A swap of 70 CKB in the test pool by a request bound to the pool of `info_type_hash`.
+fn bound_swap_tx(info_type_hash: [u8; 32]) -> PoolTxBuilder {
+    let swap_lock_args = SwapRequestLockArgsBuilder::default()
+        .user_lock_hash(user_lock_hash(0))
+        .version(1)
+        .amount_out_min(35)
+        .sudt_type_hash(*SUDT_TYPE_HASH)
+        .build();
+
+    PoolTxBuilder::new(100)
+        .info_capacity(1000)
+        .witness(witness_args_input_type(1))
+        .reserves_out(170, 58, 100)
+        .inputs(vec![
+            Inputs::new_matcher(FreeCell::new(100)),
+            Inputs::new_swap(SwapRequestCell::new_ckb(SUDT_CAPACITY + 70))
+                .custom_lock_args(bound_swap_lock_args(swap_lock_args, info_type_hash)),
+        ])
+        .outputs(vec![
+            Outputs::new_matcher(FreeCell::new(150)),
+            Outputs::new_sudt(SudtCell::new(SUDT_CAPACITY, 42)).custom_lock_args(user_lock_args(0)),
+        ])
+}
+
+test_contract!(
+    swap_request_bound_to_pool_success,
+    {
+        let (context, tx) = bound_swap_tx(info_cell_type_hash(info_type_args(0))).build();
+
+        let cycle = context
+            .verify_tx(&tx, MAX_CYCLES)
+            .expect("pass verification");
+
+        println!("cycle used {:?}", cycle);
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    swap_request_bound_to_other_pool,
+    {
+        let (context, tx) = bound_swap_tx(info_cell_type_hash(info_type_args(1))).build();
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INFO_TYPE_HASH_MISMATCH, 3, true, true));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+fn dyn_cancel_test_context(user_lock: Script, cancel_lock: Script) -> (Context, TransactionView) {
+    let swap_lock_args = SwapRequestLockArgsBuilder::default()
+        .user_lock_hash(user_lock.calc_script_hash().unpack())
+        .version(1)
+        .amount_out_min(35)
+        .sudt_type_hash(*SUDT_TYPE_HASH)
+        .build();
+    let input_0 = Inputs::new_swap(SwapRequestCell::new_ckb(SUDT_CAPACITY + 70))
+        .custom_lock_args(swap_lock_args.as_bytes())
+        .custom_witness(witness_args_cancel(cancel_lock, Bytes::from(vec![0u8; 65])));
+
+    let output_0 = Outputs::new_ckb(FreeCell::new(SUDT_CAPACITY + 70));
+
+    let (mut context, tx) = build_test_context(vec![input_0], vec![output_0]);
+    let tx = context.complete_tx(tx);
+
+    (context, tx)
+}
+
+test_contract!(
+    cancel_swap_with_invalid_signature,
+    {
+        let (_, pubkey) = Generator::random_keypair();
+        let user_lock = DynLock::lock_script(pubkey);
+        let (context, tx) = dyn_cancel_test_context(user_lock.clone(), user_lock);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_CANCEL_SIGNATURE, 0, true, true));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    cancel_swap_with_other_lock_script,
+    {
+        let (_, pubkey) = Generator::random_keypair();
+        let (_, other_pubkey) = Generator::random_keypair();
+        let (context, tx) = dyn_cancel_test_context(
+            DynLock::lock_script(pubkey),
+            DynLock::lock_script(other_pubkey),
+        );
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(err, tx_error(ERR_INVALID_CANCEL_LOCK_SCRIPT, 0, true, true));
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
+
+test_contract!(
+    cancel_swap_with_type_hash_lock_script,
+    {
+        let (_, pubkey) = Generator::random_keypair();
+        let user_lock = DynLock::lock_script(pubkey)
+            .as_builder()
+            .hash_type(ScriptHashType::Type.into())
+            .build();
+        let (context, tx) = dyn_cancel_test_context(user_lock.clone(), user_lock);
+
+        let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+        assert_error_eq!(
+            err,
+            tx_error(ERR_UNSUPPORTED_CANCEL_HASH_TYPE, 0, true, true)
+        );
+
+        (context, tx)
+    },
+    false,
+    "info-typescript-sim"
+);
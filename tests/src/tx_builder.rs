@@ -10,31 +10,42 @@ use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
 use ckb_tool::ckb_crypto::secp::Pubkey;
 // use ckb_tool::ckb_hash::{blake2b_256, new_blake2b};
 use ckb_tool::ckb_script::{ScriptError, TransactionScriptError};
-use ckb_tool::ckb_types::core::{DepType, TransactionBuilder, TransactionView};
+use ckb_tool::ckb_types::core::{
+    DepType, HeaderBuilder, ScriptHashType, TransactionBuilder, TransactionView,
+};
 use ckb_tool::ckb_types::packed::*;
 use ckb_tool::ckb_types::{bytes::Bytes, prelude::*};
 use ckb_x64_simulator::RunningSetup;
+use dex_sdk::{Deployment, ScriptRef};
 use molecule::prelude::*;
 use serde_json::to_string_pretty;
+use share::{blake2b, request_order_key};
 
-use crate::cell_builder::{FreeCell, InfoCell, LiquidityRequestCell, SudtCell, SwapRequestCell};
+use crate::cell_builder::{
+    FreeCell, InfoCell, InfoCellBuilder, LiquidityRequestCell, RegistryCell, SudtCell,
+    SwapRequestCell,
+};
 use crate::{Loader, TX_FOLDER};
 
 pub enum InputCell {
     Sudt(SudtCell),
+    Ckb(FreeCell),
     Info(InfoCell),
     Matcher(FreeCell),
     Liquidity(LiquidityRequestCell),
     Swap(SwapRequestCell),
     Pool(SudtCell),
+    Registry(RegistryCell),
 }
 
 pub enum OutputCell {
     Info(InfoCell),
+    UpgradedInfo(InfoCell),
     Sudt(SudtCell),
     Ckb(FreeCell),
     Matcher(FreeCell),
     Pool(SudtCell),
+    Registry(RegistryCell),
 }
 
 pub struct Inputs {
@@ -43,6 +54,7 @@ pub struct Inputs {
     custom_type_args: Option<Bytes>,
     custom_lock_args: Option<Bytes>,
     witness:          Option<Bytes>,
+    since:            Option<u64>,
 }
 
 impl Inputs {
@@ -54,6 +66,10 @@ impl Inputs {
         Self::inner_new(InputCell::Sudt(cell))
     }
 
+    pub fn new_ckb(cell: FreeCell) -> Self {
+        Self::inner_new(InputCell::Ckb(cell))
+    }
+
     pub fn new_matcher(cell: FreeCell) -> Self {
         Self::inner_new(InputCell::Matcher(cell))
     }
@@ -70,6 +86,10 @@ impl Inputs {
         Self::inner_new(InputCell::Swap(cell))
     }
 
+    pub fn new_registry(cell: RegistryCell) -> Self {
+        Self::inner_new(InputCell::Registry(cell))
+    }
+
     fn inner_new(cell: InputCell) -> Self {
         Inputs {
             cell,
@@ -77,6 +97,7 @@ impl Inputs {
             custom_type_args: None,
             custom_lock_args: None,
             witness: None,
+            since: None,
         }
     }
 
@@ -99,6 +120,11 @@ impl Inputs {
         self.cell_deps = Some(cell_deps);
         self
     }
+
+    pub fn custom_since(mut self, since: u64) -> Self {
+        self.since = Some(since);
+        self
+    }
 }
 
 pub struct Outputs {
@@ -112,6 +138,10 @@ impl Outputs {
         Self::inner_new(OutputCell::Info(cell))
     }
 
+    pub fn new_upgraded_info(cell: InfoCell) -> Self {
+        Self::inner_new(OutputCell::UpgradedInfo(cell))
+    }
+
     pub fn new_sudt(cell: SudtCell) -> Self {
         Self::inner_new(OutputCell::Sudt(cell))
     }
@@ -128,6 +158,10 @@ impl Outputs {
         Self::inner_new(OutputCell::Pool(cell))
     }
 
+    pub fn new_registry(cell: RegistryCell) -> Self {
+        Self::inner_new(OutputCell::Registry(cell))
+    }
+
     fn inner_new(cell: OutputCell) -> Self {
         Outputs {
             cell,
@@ -176,6 +210,14 @@ fn build_tx(
         .out_point(swap_lock_out_point.clone())
         .build();
 
+    let registry_type_bin: Bytes = Loader::default().load_binary("registry-type-script");
+    let registry_type_out_point = context.deploy_cell(registry_type_bin);
+    let registry_type_dep = CellDep::new_builder()
+        .out_point(registry_type_out_point.clone())
+        .build();
+
+    let (_, dyn_lock_deps) = DynLock::deploy(context);
+
     // Deploy always sucess script
     let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
     let always_success_dep = CellDep::new_builder()
@@ -203,6 +245,9 @@ fn build_tx(
     };
 
     // Prepare inputs
+    let mut info_out_point = None;
+    let mut swap_position = 0;
+    let mut liquidity_position = 0;
     let mut inputs = vec![];
     let mut witnesses = vec![];
     let mut cell_deps: Vec<CellDep> = vec![];
@@ -242,9 +287,11 @@ fn build_tx(
                         .build(),
                     cell.data,
                 );
+                info_out_point = Some(input_out_point.clone());
 
                 let input_cell = CellInput::new_builder()
                     .previous_output(input_out_point)
+                    .since(input.since.unwrap_or_default().pack())
                     .build();
 
                 cell_deps.extend(input.cell_deps.unwrap_or_default());
@@ -304,7 +351,7 @@ fn build_tx(
                 inputs.push(input_cell);
                 witnesses.push(input.witness.unwrap_or_default());
             }
-            InputCell::Matcher(cell) => {
+            InputCell::Ckb(cell) | InputCell::Matcher(cell) => {
                 let input_out_point = context.create_cell(
                     CellOutput::new_builder()
                         .capacity(cell.capacity.pack())
@@ -327,7 +374,11 @@ fn build_tx(
                     .build_script(&liquidity_lock_out_point, lock_args)
                     .expect("liquidity lock script");
 
-                let input_out_point = context.create_cell(
+                let input_out_point =
+                    ordered_out_point(info_out_point.as_ref(), liquidity_position);
+                liquidity_position += 1;
+                context.create_cell_with_out_point(
+                    input_out_point.clone(),
                     CellOutput::new_builder()
                         .capacity(cell.capacity.pack())
                         .lock(liquidity_lock)
@@ -344,6 +395,34 @@ fn build_tx(
                 inputs.push(input_cell);
                 witnesses.push(input.witness.unwrap_or_default());
             }
+            InputCell::Registry(cell) => {
+                let registry_type_args = input
+                    .custom_type_args
+                    .unwrap_or_else(|| Bytes::from(vec![0u8; 32]));
+                let registry_type_script = context
+                    .build_script(&registry_type_out_point, registry_type_args)
+                    .expect("registry type script");
+                let registry_lock_script = context
+                    .build_script(&always_success_out_point, Default::default())
+                    .expect("registry lock script");
+
+                let input_out_point = context.create_cell(
+                    CellOutput::new_builder()
+                        .capacity(cell.capacity.pack())
+                        .lock(registry_lock_script)
+                        .type_(Some(registry_type_script).pack())
+                        .build(),
+                    cell.data,
+                );
+
+                let input_cell = CellInput::new_builder()
+                    .previous_output(input_out_point)
+                    .build();
+
+                cell_deps.extend(input.cell_deps.unwrap_or_default());
+                inputs.push(input_cell);
+                witnesses.push(input.witness.unwrap_or_default());
+            }
             InputCell::Swap(cell) => {
                 let lock_args = input.custom_lock_args.expect("swap input lock args");
                 let swap_lock = context
@@ -356,7 +435,10 @@ fn build_tx(
                     None
                 };
 
-                let input_out_point = context.create_cell(
+                let input_out_point = ordered_out_point(info_out_point.as_ref(), swap_position);
+                swap_position += 1;
+                context.create_cell_with_out_point(
+                    input_out_point.clone(),
                     CellOutput::new_builder()
                         .capacity(cell.capacity.pack())
                         .lock(swap_lock)
@@ -417,6 +499,21 @@ fn build_tx(
 
                 (output, cell.data)
             }
+            OutputCell::UpgradedInfo(cell) => {
+                // Use always success type script to mock a new version of info type script
+                let args = output.custom_lock_args.expect("info out lock args");
+                let info_lock_script = context
+                    .build_script(&info_lock_out_point, args)
+                    .expect("info lock script");
+
+                let output = CellOutput::new_builder()
+                    .capacity(cell.capacity.pack())
+                    .type_(Some(sudt_type_script).pack())
+                    .lock(info_lock_script)
+                    .build();
+
+                (output, cell.data)
+            }
             OutputCell::Sudt(cell) => {
                 let output = CellOutput::new_builder()
                     .capacity(cell.capacity.pack())
@@ -455,6 +552,25 @@ fn build_tx(
 
                 (output, cell.data)
             }
+            OutputCell::Registry(cell) => {
+                let registry_type_args = output
+                    .custom_type_args
+                    .unwrap_or_else(|| Bytes::from(vec![0u8; 32]));
+                let registry_type_script = context
+                    .build_script(&registry_type_out_point, registry_type_args)
+                    .expect("registry type script");
+                let registry_lock_script = context
+                    .build_script(&always_success_out_point, Default::default())
+                    .expect("registry lock script");
+
+                let output = CellOutput::new_builder()
+                    .capacity(cell.capacity.pack())
+                    .type_(Some(registry_type_script).pack())
+                    .lock(registry_lock_script)
+                    .build();
+
+                (output, cell.data)
+            }
         };
 
         outputs.push(output);
@@ -469,12 +585,33 @@ fn build_tx(
         .cell_dep(info_type_dep)
         .cell_dep(liquidity_lock_dep)
         .cell_dep(swap_lock_dep)
+        .cell_dep(registry_type_dep)
         .cell_dep(always_success_dep)
+        .cell_deps(dyn_lock_deps)
         .cell_deps(cell_deps)
         .witnesses(witnesses.pack())
         .build()
 }
 
+/// A random out point whose order key starts with `position`, so the requests of a kind are
+/// created in the order the info type script requires.
+fn ordered_out_point(info_out_point: Option<&OutPoint>, position: usize) -> OutPoint {
+    assert!(position < 256, "too many requests to order");
+
+    loop {
+        let out_point = OutPoint::new_builder()
+            .tx_hash(rand::random::<[u8; 32]>().pack())
+            .build();
+        let key = info_out_point.map_or([position as u8; 32], |info| {
+            request_order_key(info.as_slice(), out_point.as_slice())
+        });
+
+        if key[0] as usize == position {
+            return out_point;
+        }
+    }
+}
+
 pub fn build_test_context(
     input_orders: Vec<Inputs>,
     output_results: Vec<Outputs>,
@@ -484,6 +621,276 @@ pub fn build_test_context(
     (context, tx)
 }
 
+pub const POOL_CAPACITY: u64 = 18_600_000_000;
+pub const INFO_CAPACITY: u64 = 25_000_000_000;
+
+/// A transaction of the test pool, whose sUDT type is the always success script. The info and
+/// pool cells come first, the cells of the case after them. The outputs keep the reserves and the
+/// extended fields of the inputs unless set otherwise, so a case only sets what it changes.
+pub struct PoolTxBuilder {
+    reserve:       u128,
+    reserves_out:  Option<(u128, u128, u128)>,
+    info:          InfoCellBuilder,
+    info_out:      Option<InfoCellBuilder>,
+    info_capacity: u64,
+    since:         u64,
+    witness:       Bytes,
+    header:        Option<u64>,
+    migrated_to:   Option<Bytes>,
+    is_creation:   bool,
+    is_close:      bool,
+    inputs:        Vec<Inputs>,
+    outputs:       Vec<Outputs>,
+}
+
+impl PoolTxBuilder {
+    /// A pool whose CKB reserve, sUDT reserve and total liquidity are all `reserve`.
+    pub fn new(reserve: u128) -> Self {
+        PoolTxBuilder {
+            reserve,
+            reserves_out: None,
+            info: InfoCellBuilder::default(),
+            info_out: None,
+            info_capacity: INFO_CAPACITY,
+            since: 0,
+            witness: Bytes::new(),
+            header: None,
+            migrated_to: None,
+            is_creation: false,
+            is_close: false,
+            inputs: vec![],
+            outputs: vec![],
+        }
+    }
+
+    /// The creation of an empty pool, its info and pool cells are outputs only. The type id of
+    /// the info cell comes from a funding sUDT input at index 0.
+    pub fn creation() -> Self {
+        PoolTxBuilder {
+            is_creation: true,
+            ..Self::new(0)
+        }
+    }
+
+    /// Sets the extended fields of the info input, and of the output unless set by `info_out`.
+    pub fn info(mut self, info: InfoCellBuilder) -> Self {
+        self.info = info;
+        self
+    }
+
+    pub fn info_out(mut self, info: InfoCellBuilder) -> Self {
+        self.info_out = Some(info);
+        self
+    }
+
+    /// The capacity of the info input, the output always has `INFO_CAPACITY`.
+    pub fn info_capacity(mut self, capacity: u64) -> Self {
+        self.info_capacity = capacity;
+        self
+    }
+
+    pub fn reserves_out(mut self, ckb_reserve: u128, sudt_reserve: u128, total: u128) -> Self {
+        self.reserves_out = Some((ckb_reserve, sudt_reserve, total));
+        self
+    }
+
+    pub fn since(mut self, since: u64) -> Self {
+        self.since = since;
+        self
+    }
+
+    pub fn witness(mut self, witness: Bytes) -> Self {
+        self.witness = witness;
+        self
+    }
+
+    /// Commits the info input in a block at `timestamp` in seconds, given in the header deps.
+    pub fn info_header(mut self, timestamp: u64) -> Self {
+        self.header = Some(timestamp);
+        self
+    }
+
+    /// Moves the pool to the always success type script with `type_args`, which mocks the new
+    /// version of the info type script.
+    pub fn migrate(mut self, type_args: Bytes) -> Self {
+        self.migrated_to = Some(type_args);
+        self
+    }
+
+    /// Destroys the info and pool cells.
+    pub fn close(mut self) -> Self {
+        self.is_close = true;
+        self
+    }
+
+    /// Spends the registry cells of `registry_in` and creates the ones of `registry_out`.
+    pub fn registry(
+        mut self,
+        registry_in: Vec<RegistryCell>,
+        registry_out: Vec<RegistryCell>,
+    ) -> Self {
+        self.inputs
+            .extend(registry_in.into_iter().map(Inputs::new_registry));
+        self.outputs
+            .extend(registry_out.into_iter().map(Outputs::new_registry));
+        self
+    }
+
+    pub fn inputs(mut self, inputs: Vec<Inputs>) -> Self {
+        self.inputs.extend(inputs);
+        self
+    }
+
+    pub fn outputs(mut self, outputs: Vec<Outputs>) -> Self {
+        self.outputs.extend(outputs);
+        self
+    }
+
+    pub fn build(self) -> (Context, TransactionView) {
+        let PoolTxBuilder {
+            reserve,
+            reserves_out,
+            info,
+            info_out,
+            info_capacity,
+            since,
+            witness,
+            header,
+            migrated_to,
+            is_creation,
+            is_close,
+            inputs: case_inputs,
+            outputs: case_outputs,
+        } = self;
+        let (ckb_out, sudt_out, total_out) = reserves_out.unwrap_or((reserve, reserve, reserve));
+        let info_out = info_out.unwrap_or_else(|| info.clone());
+        let liquidity_sudt_type_hash: [u8; 32] =
+            always_success_script(Bytes::from(9999u64.to_le_bytes().to_vec()))
+                .calc_script_hash()
+                .unpack();
+        let mut inputs = vec![];
+        let mut outputs = vec![];
+
+        // The info type args are the hash of the lock of input 0, unless created with a type id
+        let info_type_args = if is_creation {
+            let funding_out_point = OutPoint::new_builder()
+                .tx_hash(rand::random::<[u8; 32]>().pack())
+                .build();
+            let funding_tx_hash: [u8; 32] = funding_out_point.tx_hash().unpack();
+            inputs.push(Inputs::new_sudt(SudtCell::new_with_out_point(
+                INFO_CAPACITY + POOL_CAPACITY,
+                0,
+                funding_out_point,
+            )));
+            Bytes::from(blake2b!(funding_tx_hash, 0u64.to_le_bytes()).to_vec())
+        } else {
+            always_success_script(Bytes::from(0usize.to_le_bytes().to_vec()))
+                .calc_script_hash()
+                .as_bytes()
+        };
+        let info_lock_args = pool_info_lock_args(&info_type_args);
+
+        if !is_creation {
+            let info_in = info
+                .capacity(info_capacity)
+                .ckb_reserve(reserve)
+                .sudt_reserve(reserve)
+                .total_liquidity(reserve)
+                .liquidity_sudt_type_hash(liquidity_sudt_type_hash)
+                .build();
+            let pool_in = SudtCell::new(POOL_CAPACITY + reserve as u64, reserve);
+
+            inputs.push(
+                Inputs::new_info(info_in)
+                    .custom_lock_args(info_lock_args.clone())
+                    .custom_since(since)
+                    .custom_witness(witness),
+            );
+            inputs.push(Inputs::new_pool(pool_in).custom_lock_args(info_lock_args.clone()));
+        }
+        inputs.extend(case_inputs);
+
+        if !is_close {
+            let info_out = info_out
+                .capacity(INFO_CAPACITY)
+                .ckb_reserve(ckb_out)
+                .sudt_reserve(sudt_out)
+                .total_liquidity(total_out)
+                .liquidity_sudt_type_hash(liquidity_sudt_type_hash)
+                .build();
+            let pool_out = SudtCell::new(POOL_CAPACITY + ckb_out as u64, sudt_out);
+
+            let (info_out, lock_args) = match migrated_to {
+                Some(type_args) => {
+                    let new_type_args = always_success_script(type_args.clone())
+                        .calc_script_hash()
+                        .as_bytes();
+                    let info_out = Outputs::new_upgraded_info(info_out).custom_type_args(type_args);
+                    (info_out, pool_info_lock_args(&new_type_args))
+                }
+                None => {
+                    let info_out = Outputs::new_info(info_out).custom_type_args(info_type_args);
+                    (info_out, info_lock_args)
+                }
+            };
+            outputs.push(info_out.custom_lock_args(lock_args.clone()));
+            outputs.push(Outputs::new_pool(pool_out).custom_lock_args(lock_args));
+        }
+        outputs.extend(case_outputs);
+
+        let (mut context, tx) = build_test_context(inputs, outputs);
+        let tx = match header {
+            Some(timestamp) => {
+                let header = HeaderBuilder::default()
+                    .timestamp((timestamp * 1000).pack())
+                    .build();
+                context.insert_header(header.clone());
+                context.link_cell_with_block(
+                    tx.inputs().get(0).unwrap().previous_output(),
+                    header.hash(),
+                    0,
+                );
+                tx.as_advanced_builder().header_dep(header.hash()).build()
+            }
+            None => tx,
+        };
+        let tx = context.complete_tx(tx);
+
+        (context, tx)
+    }
+}
+
+/// The always success script with `args`, which the test transactions use for the user locks
+/// and the sUDT types.
+fn always_success_script(args: Bytes) -> Script {
+    Script::new_builder()
+        .code_hash(CellOutput::calc_data_hash(&ALWAYS_SUCCESS))
+        .hash_type(ScriptHashType::Data.into())
+        .args(args.pack())
+        .build()
+}
+
+/// The lock args of the info and pool cells: the hash of the sUDT type hash, then the hash of
+/// the info type script with `info_type_args`.
+fn pool_info_lock_args(info_type_args: &Bytes) -> Bytes {
+    let sudt_type_hash: [u8; 32] = always_success_script(Bytes::new())
+        .calc_script_hash()
+        .unpack();
+    let info_type_hash: [u8; 32] = Script::new_builder()
+        .code_hash(CellOutput::calc_data_hash(
+            &Loader::default().load_binary("info-type-script"),
+        ))
+        .hash_type(ScriptHashType::Data.into())
+        .args(info_type_args.pack())
+        .build()
+        .calc_script_hash()
+        .unpack();
+
+    let mut lock_args = blake2b!("ckb", sudt_type_hash).to_vec();
+    lock_args.extend_from_slice(&info_type_hash);
+    Bytes::from(lock_args)
+}
+
 pub fn tx_error(
     error_code: i8,
     index: usize,
@@ -501,7 +908,33 @@ pub fn tx_error(
     }
 }
 
-struct DynLock;
+pub fn deploy_script(context: &mut Context, binary: Bytes) -> ScriptRef {
+    let code_hash = CellOutput::calc_data_hash(&binary);
+    let out_point = context.deploy_cell(binary);
+
+    ScriptRef {
+        code_hash,
+        hash_type: ScriptHashType::Data,
+        cell_dep: CellDep::new_builder().out_point(out_point).build(),
+    }
+}
+
+/// Deploys the DEX scripts, with an always success sUDT, as the SDK references them.
+pub fn sdk_deployment(context: &mut Context) -> Deployment {
+    let loader = Loader::default();
+
+    Deployment {
+        info_type:          deploy_script(context, loader.load_binary("info-type-script")),
+        info_lock:          deploy_script(context, loader.load_binary("info-lock-script")),
+        swap_lock:          deploy_script(context, loader.load_binary("swap-lock-script")),
+        liquidity_lock:     deploy_script(context, loader.load_binary("liquidity-lock-script")),
+        registry_type:      deploy_script(context, loader.load_binary("registry-type-script")),
+        sudt:               deploy_script(context, ALWAYS_SUCCESS.clone()),
+        registry_type_args: Bytes::from(vec![0u8; 32]),
+    }
+}
+
+pub struct DynLock;
 
 impl DynLock {
     fn deploy(context: &mut Context) -> (OutPoint, Vec<CellDep>) {
@@ -524,6 +957,18 @@ impl DynLock {
         ])
     }
 
+    /// Secp256k1 keccak256 lock of the pubkey, loadable by the request locks
+    pub fn lock_script(pubkey: Pubkey) -> Script {
+        let secp256k1_keccak256_bin = binary::get(Binary::Secp256k1Keccak256SighashAllDual);
+        Script::new_builder()
+            .code_hash(CellOutput::calc_data_hash(
+                &secp256k1_keccak256_bin.to_vec(),
+            ))
+            .hash_type(ScriptHashType::Data.into())
+            .args(Self::eth_pubkey(pubkey).pack())
+            .build()
+    }
+
     fn eth_pubkey(pubkey: Pubkey) -> Bytes {
         use sha3::{Digest, Keccak256};
 
@@ -548,7 +993,7 @@ fn create_test_folder(name: &str) -> PathBuf {
     path
 }
 
-fn build_mock_transaction(tx: &TransactionView, context: &Context) -> MockTransaction {
+pub fn build_mock_transaction(tx: &TransactionView, context: &Context) -> MockTransaction {
     let mock_inputs = tx
         .inputs()
         .into_iter()